@@ -0,0 +1,104 @@
+//! Reading every column of a 50×50 grid through
+//! [nonograms::Nonogram::column_view], against copying them with
+//! [nonograms::Nonogram::column]: by iterating over the cells, by
+//! indexing them, and by line-solving each column.
+//!
+//! Each timing is the median of a few runs, per column read, on
+//! random pictures half filled.
+
+use nonograms::{solve_line, CellState, Grid, Nonogram, Puzzle};
+
+use std::cell::RefCell;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const SIDE: usize = 50;
+const PUZZLES: usize = 20;
+const RUNS: usize = 7;
+
+/// A solved random 50×50 puzzle, from a xorshift of `seed`.
+fn puzzle(seed: u64) -> Nonogram {
+    let mut state = seed.wrapping_mul(0x9e3779b97f4a7c15) | 1;
+    let cells = (0..SIDE * SIDE)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            CellState::from(state & 1 == 1)
+        })
+        .collect();
+    let goal = Grid::from_cells(SIDE, SIDE, cells);
+    Nonogram::from_parts(Puzzle::from_solution(&goal), goal)
+}
+
+/// The median time of `f` per column read, over [RUNS] runs of
+/// `rounds` reads of every column.
+fn time(nonos: &[Nonogram], rounds: usize, f: impl Fn(&Nonogram, usize) -> usize) -> Duration {
+    let mut runs: Vec<Duration> = (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            for _ in 0..rounds {
+                for nono in nonos {
+                    for x in 0..SIDE {
+                        black_box(f(black_box(nono), x));
+                    }
+                }
+            }
+            start.elapsed() / (rounds * nonos.len() * SIDE) as u32
+        })
+        .collect();
+    runs.sort();
+    runs[RUNS / 2]
+}
+
+fn filled(cells: impl IntoIterator<Item = CellState>) -> usize {
+    cells
+        .into_iter()
+        .filter(|c| *c == CellState::Filled)
+        .count()
+}
+
+fn main() {
+    let nonos: Vec<Nonogram> = (0..PUZZLES as u64).map(puzzle).collect();
+    let report = |what: &str, view: Duration, copy: Duration| {
+        println!(
+            "{:<10} view {:>9.1?}  copy {:>9.1?}  ({:.2}×)",
+            what,
+            view,
+            copy,
+            copy.as_secs_f64() / view.as_secs_f64()
+        )
+    };
+
+    let view = time(&nonos, 1000, |n, x| filled(n.column_view(x).unwrap()));
+    let copy = time(&nonos, 1000, |n, x| filled(n.column(x).unwrap()));
+    report("iterate", view, copy);
+
+    let view = time(&nonos, 1000, |n, x| {
+        let column = n.column_view(x).unwrap();
+        (0..SIDE)
+            .filter(|y| column[*y] == CellState::Filled)
+            .count()
+    });
+    let copy = time(&nonos, 1000, |n, x| {
+        let column = n.column(x).unwrap();
+        (0..SIDE)
+            .filter(|y| column[*y] == CellState::Filled)
+            .count()
+    });
+    report("index", view, copy);
+
+    // Line logic needs the cells in a slice, so the view is copied
+    // into a reused buffer, as the solver does.
+    let buffer = RefCell::new(Vec::with_capacity(SIDE));
+    let view = time(&nonos, 20, |n, x| {
+        let mut buffer = buffer.borrow_mut();
+        buffer.clear();
+        buffer.extend(n.column_view(x).unwrap());
+        solve_line(&n.cols[x], &buffer).map_or(0, |r| r.changed.len())
+    });
+    let copy = time(&nonos, 20, |n, x| {
+        solve_line(&n.cols[x], &n.column(x).unwrap()).map_or(0, |r| r.changed.len())
+    });
+    report("line solve", view, copy);
+}
@@ -0,0 +1,17 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nonograms::formats;
+use nonograms_fuzz::{check_round_trip, BuilderInput};
+
+fuzz_target!(|input: BuilderInput| {
+    let builder = input.builder();
+    let valid = builder.validate().is_ok();
+    let Ok(nono) = builder.build() else {
+        assert!(!valid, "validate accepts what build refuses");
+        return;
+    };
+    let puzzle = nono.puzzle();
+    // Only the format of the crate holds every clue.
+    check_round_trip(&formats::non::Non, &puzzle);
+});
@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nonograms::Constraint;
+use nonograms_fuzz::Clue;
+
+fuzz_target!(|clue: Clue| {
+    let text = clue.0.to_string();
+    let again: Constraint = text.parse().expect("a written clue reads back");
+    assert_eq!(text, again.to_string());
+});
@@ -0,0 +1,18 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nonograms::formats;
+use nonograms_fuzz::{check_round_trip, ParserInput};
+
+fuzz_target!(|input: ParserInput| {
+    let format = match input.format {
+        Some(name) => formats::by_name(name),
+        None => formats::detect(&input.text),
+    };
+    let Ok(format) = format else {
+        return;
+    };
+    if let Ok(puzzle) = format.parse(&input.text) {
+        check_round_trip(format, &puzzle);
+    }
+});
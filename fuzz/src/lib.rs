@@ -0,0 +1,142 @@
+//! Inputs for the fuzz targets of nonograms, which [arbitrary] builds
+//! from the bytes of the fuzzer.
+//!
+//! With cargo-fuzz, from the root of the crate:
+//!
+//! <pre>
+//! cargo +nightly fuzz run roundtrip
+//! </pre>
+//!
+//! The targets are:
+//!
+//!  - `roundtrip`: read any text as a puzzle, in the format it looks
+//!    like or a given one, write it back, and check that it reads
+//!    back the same.
+//!  - `builder`: build puzzles from arbitrary clues and dimensions,
+//!    and round-trip them through every format that can hold them.
+//!  - `constraint`: write arbitrary clues, and read them back.
+//!
+//! `cargo test` in the crate checks the invariant of `roundtrip` on
+//! seeded variants of a fixed corpus, without a fuzzer.
+//!
+//! Formats lose what they can't express, eg a single run of unknown
+//! length reads back as an unknown clue, so round-trips compare the
+//! text written the first and second time, not the puzzles.
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+use nonograms::formats::{self, PuzzleFormat};
+use nonograms::{Constraint, NonogramBuilder, Puzzle};
+
+/// The longest run of [Clue]s, and the largest [BuilderInput]
+/// dimensions, to keep puzzles small.
+const MAX_LENGTH: usize = 40;
+
+/// An arbitrary clue: runs of known or unknown lengths, or an unknown
+/// clue, which may wrap.
+#[derive(Clone, Debug)]
+pub struct Clue(pub Constraint);
+
+impl<'a> Arbitrary<'a> for Clue {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut ret = match u.ratio(1, 8)? {
+            true => Constraint::unknown(),
+            false => {
+                let count = u.int_in_range(0..=8)?;
+                let lengths = (0..count)
+                    .map(|_| match u.ratio(1, 6)? {
+                        true => Ok(None),
+                        false => Ok(Some(u.int_in_range(1..=MAX_LENGTH)?)),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Constraint::with_unknowns(lengths).unwrap()
+            }
+        };
+        ret.set_wraps(u.ratio(1, 8)?);
+        Ok(Clue(ret))
+    }
+}
+
+/// Arbitrary calls to a [NonogramBuilder], which may well fail.
+#[derive(Clone, Debug)]
+pub struct BuilderInput {
+    pub width: Option<usize>,
+    pub height: Option<usize>,
+    pub rows: Vec<Clue>,
+    pub cols: Vec<Clue>,
+}
+
+impl<'a> Arbitrary<'a> for BuilderInput {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let dimension = |u: &mut Unstructured<'a>| match u.arbitrary()? {
+            true => Ok(Some(u.int_in_range(0..=MAX_LENGTH)?)),
+            false => Ok(None),
+        };
+        let width = dimension(u)?;
+        let height = dimension(u)?;
+        let clues = |u: &mut Unstructured<'a>| {
+            let count = u.int_in_range(0..=MAX_LENGTH)?;
+            (0..count)
+                .map(|_| u.arbitrary())
+                .collect::<Result<Vec<Clue>>>()
+        };
+        let rows = clues(u)?;
+        let cols = clues(u)?;
+        Ok(BuilderInput {
+            width,
+            height,
+            rows,
+            cols,
+        })
+    }
+}
+
+impl BuilderInput {
+    pub fn builder(&self) -> NonogramBuilder {
+        let mut ret = NonogramBuilder::new();
+        if let Some(width) = self.width {
+            ret = ret.width(width);
+        }
+        if let Some(height) = self.height {
+            ret = ret.height(height);
+        }
+        let rows = self.rows.iter().map(|c| c.0.clone()).collect();
+        let cols = self.cols.iter().map(|c| c.0.clone()).collect();
+        ret.rows(rows).cols(cols).infer_dimensions()
+    }
+}
+
+/// An arbitrary text to read as a puzzle, in the format named
+/// `format` or in the one it looks like.
+#[derive(Clone, Debug)]
+pub struct ParserInput {
+    pub format: Option<&'static str>,
+    pub text: String,
+}
+
+impl<'a> Arbitrary<'a> for ParserInput {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let format = match u.arbitrary()? {
+            true => Some(u.choose(formats::registry())?.name()),
+            false => None,
+        };
+        let bytes: &[u8] = u.arbitrary()?;
+        let text = String::from_utf8_lossy(bytes).into_owned();
+        Ok(ParserInput { format, text })
+    }
+}
+
+/// Write `puzzle` in `format`, read it back and write it again, and
+/// check that both texts are the same.
+pub fn check_round_trip(format: &dyn PuzzleFormat, puzzle: &Puzzle) {
+    let text = format.write(puzzle);
+    let again = match format.parse(&text) {
+        Ok(puzzle) => puzzle,
+        Err(e) => panic!(
+            "{} can't read what it wrote: {}\n{}",
+            format.name(),
+            e,
+            text
+        ),
+    };
+    assert_eq!(text, format.write(&again), "{} round-trip", format.name());
+}
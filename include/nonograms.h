@@ -0,0 +1,60 @@
+/* The C API of the nonograms crate, built with feature `capi`.  See
+ * src/ffi.rs. */
+
+#ifndef NONOGRAMS_H
+#define NONOGRAMS_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct Nonogram Nonogram;
+
+typedef enum {
+    NONOGRAM_UNDECIDED = 0,
+    NONOGRAM_EMPTY = 1,
+    NONOGRAM_FILLED = 2,
+} NonogramCell;
+
+typedef enum {
+    NONOGRAM_SOLVED = 0,
+    NONOGRAM_STALLED = 1,
+    NONOGRAM_CONTRADICTION = 2,
+    NONOGRAM_CANCELLED = 3,
+} NonogramOutcome;
+
+/* Read a puzzle in any supported format from UTF-8 text, or return
+ * NULL. */
+Nonogram *nonogram_parse(const char *text);
+
+NonogramOutcome nonogram_solve(Nonogram *nono);
+
+size_t nonogram_width(const Nonogram *nono);
+
+size_t nonogram_height(const Nonogram *nono);
+
+/* Cells outside the grid are undecided. */
+NonogramCell nonogram_cell_at(const Nonogram *nono, size_t x, size_t y);
+
+/* The clues in the format named `format`, eg "non" or "json", or NULL
+ * for an unknown format.  Free with nonogram_string_free. */
+char *nonogram_write(const Nonogram *nono, const char *format);
+
+/* A copy with as many clues as possible unknown, keeping the solution
+ * unique, or NULL if it isn't unique. */
+Nonogram *nonogram_blot_clues(const Nonogram *nono, uint64_t seed);
+
+/* NULL is ignored. */
+void nonogram_string_free(char *s);
+
+/* NULL is ignored. */
+void nonogram_free(Nonogram *nono);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,125 @@
+"""Python bindings to the nonograms crate, through its C API.
+
+Build the library first, from the root of the crate:
+
+    cargo rustc --release --lib --crate-type cdylib --features capi
+
+This module looks for it in target/release, or at the path in the
+NONOGRAMS_LIB environment variable.  Then:
+
+    import nonograms
+    nono = nonograms.Nonogram.from_file("puzzle.non")
+    if nonograms.Solver(nono).solve() == "solved":
+        print(nono)
+    blotted = nonograms.blot_clues(nono, seed=1)
+    print(blotted.write("non"))
+"""
+
+import ctypes
+import os
+import sys
+
+_SUFFIX = {"darwin": ".dylib", "win32": ".dll"}.get(sys.platform, ".so")
+_PREFIX = "" if sys.platform == "win32" else "lib"
+
+
+def _load():
+    path = os.environ.get("NONOGRAMS_LIB") or os.path.join(
+        os.path.dirname(os.path.abspath(__file__)),
+        "..", "target", "release", _PREFIX + "nonograms" + _SUFFIX)
+    lib = ctypes.CDLL(path)
+    p = ctypes.c_void_p
+    for name, args, ret in [
+            ("nonogram_parse", [ctypes.c_char_p], p),
+            ("nonogram_solve", [p], ctypes.c_int),
+            ("nonogram_width", [p], ctypes.c_size_t),
+            ("nonogram_height", [p], ctypes.c_size_t),
+            ("nonogram_cell_at", [p, ctypes.c_size_t, ctypes.c_size_t], ctypes.c_int),
+            ("nonogram_write", [p, ctypes.c_char_p], p),
+            ("nonogram_blot_clues", [p, ctypes.c_uint64], p),
+            ("nonogram_string_free", [p], None),
+            ("nonogram_free", [p], None)]:
+        f = getattr(lib, name)
+        f.argtypes, f.restype = args, ret
+    return lib
+
+
+_lib = _load()
+
+UNDECIDED, EMPTY, FILLED = 0, 1, 2
+_OUTCOMES = ["solved", "stalled", "contradiction", "cancelled"]
+
+
+class Nonogram:
+    """A puzzle and its grid."""
+
+    def __init__(self, handle):
+        if not handle:
+            raise ValueError("not a puzzle")
+        self._handle = handle
+
+    @classmethod
+    def parse(cls, text):
+        """Read a puzzle in any format the crate knows."""
+        return cls(_lib.nonogram_parse(text.encode("utf-8")))
+
+    @classmethod
+    def from_file(cls, path):
+        with open(path, encoding="utf-8") as f:
+            return cls.parse(f.read())
+
+    def __del__(self):
+        if getattr(self, "_handle", None):
+            _lib.nonogram_free(self._handle)
+            self._handle = None
+
+    @property
+    def width(self):
+        return _lib.nonogram_width(self._handle)
+
+    @property
+    def height(self):
+        return _lib.nonogram_height(self._handle)
+
+    def cell(self, x, y):
+        """UNDECIDED, EMPTY or FILLED."""
+        return _lib.nonogram_cell_at(self._handle, x, y)
+
+    def grid(self):
+        """The cells, as a list of rows."""
+        return [[self.cell(x, y) for x in range(self.width)]
+                for y in range(self.height)]
+
+    def write(self, format="non"):
+        """The clues in the format named `format`, eg "non" or "json"."""
+        s = _lib.nonogram_write(self._handle, format.encode("utf-8"))
+        if not s:
+            raise ValueError("unknown format: " + format)
+        try:
+            return ctypes.string_at(s).decode("utf-8")
+        finally:
+            _lib.nonogram_string_free(s)
+
+    def __str__(self):
+        chars = {UNDECIDED: "?", EMPTY: ".", FILLED: "#"}
+        return "\n".join("".join(chars[c] for c in row) for row in self.grid())
+
+
+class Solver:
+    """Solves a Nonogram in place, with the default configuration."""
+
+    def __init__(self, nono):
+        self.nono = nono
+
+    def solve(self):
+        """"solved", "stalled", "contradiction" or "cancelled"."""
+        return _OUTCOMES[_lib.nonogram_solve(self.nono._handle)]
+
+
+def blot_clues(nono, seed=0):
+    """A copy of `nono` with as many clues unknown as possible, keeping
+    its solution unique.  This raises ValueError if it isn't unique."""
+    handle = _lib.nonogram_blot_clues(nono._handle, seed)
+    if not handle:
+        raise ValueError("the solution is not unique")
+    return Nonogram(handle)
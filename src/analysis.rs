@@ -0,0 +1,236 @@
+//! What it takes to solve a puzzle, and whether its solution is
+//! unique.
+
+use crate::{
+    CellState, Decided, Grid, Nonogram, Point, Puzzle, SolveError, SolveOutcome, Solver,
+    SolverConfig,
+};
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// The weakest kind of reasoning that solves a puzzle.  Publishers
+/// tell "logical" puzzles, the first two, from those that need
+/// guesses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Class {
+    /// Line logic alone: each row or column at a time.
+    Line,
+    /// Line logic, and probing cells one at a time.
+    Probing,
+    /// Guesses and backtracking.
+    Search,
+    /// The puzzle has no solution.
+    Contradiction,
+}
+
+/// Classify `nono` from its clues, whatever its grid, by solving it
+/// with line logic, then probing, then search, until one of them
+/// succeeds.
+pub fn classification(nono: &Nonogram) -> Class {
+    let puzzle = nono.puzzle();
+    let stages = [
+        (Class::Line, 0, false),
+        (Class::Probing, 1, false),
+        (Class::Search, 1, true),
+    ];
+    for (class, probe_depth, search) in stages {
+        let config = SolverConfig {
+            probe_depth,
+            search,
+            ..SolverConfig::default()
+        };
+        match Solver::solve_puzzle(&puzzle, config) {
+            Ok(_) => return class,
+            Err(SolveError::Stalled(_)) => continue,
+            Err(SolveError::Contradiction | SolveError::Cancelled) => break,
+        }
+    }
+    Class::Contradiction
+}
+
+/// How hard a puzzle is, by the weakest solving method that solves
+/// it, for a game to offer levels, see [difficulty].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Difficulty {
+    /// Solved by line logic alone, see [Class::Line].
+    Easy,
+    /// Needs probing, see [Class::Probing].
+    Medium,
+    /// Needs guesses, see [Class::Search].
+    Hard,
+}
+
+impl Difficulty {
+    /// Solve `puzzle` to find how hard it is, see [classification],
+    /// whether its solution is unique or not.  None if it has no
+    /// solution.
+    pub fn of(puzzle: &Puzzle) -> Option<Difficulty> {
+        rate(classification(&Nonogram::from(puzzle.clone())))
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+        })
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "easy" => Ok(Difficulty::Easy),
+            "medium" => Ok(Difficulty::Medium),
+            "hard" => Ok(Difficulty::Hard),
+            _ => Err(format!("Unknown difficulty: {}", s)),
+        }
+    }
+}
+
+/// Rate `nono` by the reasoning it takes, or None if its solution
+/// isn't unique.
+pub fn difficulty(nono: &Nonogram) -> Option<Difficulty> {
+    match uniqueness(nono) {
+        Uniqueness::Unique(_) => rate(classification(nono)),
+        _ => None,
+    }
+}
+
+/// The difficulty of a puzzle of `class`.
+pub(crate) fn rate(class: Class) -> Option<Difficulty> {
+    match class {
+        Class::Line => Some(Difficulty::Easy),
+        Class::Probing => Some(Difficulty::Medium),
+        Class::Search => Some(Difficulty::Hard),
+        Class::Contradiction => None,
+    }
+}
+
+// * Uniqueness
+
+/// How many solutions a puzzle has.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Uniqueness {
+    NoSolution,
+    Unique(Grid),
+    /// Two of the solutions.
+    Multiple(Grid, Grid),
+}
+
+/// Find whether `nono` has a single solution, from its clues.
+pub fn uniqueness(nono: &Nonogram) -> Uniqueness {
+    check_uniqueness(nono, None).0
+}
+
+/// [uniqueness], unless `cancel` is set first, eg by a timeout, and
+/// how each cell of the first solution was decided, as in
+/// [Solver::guess_map], or nothing if there's none.
+pub fn uniqueness_cancellable(
+    nono: &Nonogram,
+    cancel: &Arc<AtomicBool>,
+) -> Option<(Uniqueness, Vec<Option<Decided>>)> {
+    let ret = check_uniqueness(nono, Some(cancel));
+    (!cancel.load(Ordering::Relaxed)).then_some(ret)
+}
+
+fn check_uniqueness(
+    nono: &Nonogram,
+    cancel: Option<&Arc<AtomicBool>>,
+) -> (Uniqueness, Vec<Option<Decided>>) {
+    let puzzle = nono.puzzle();
+    let Some((solution, decided)) = solve_from(&puzzle, puzzle.grid(), cancel) else {
+        return (Uniqueness::NoSolution, vec![]);
+    };
+    let ret = match another_solution(&puzzle, &puzzle.grid(), &solution, &decided, cancel) {
+        Some(other) => Uniqueness::Multiple(solution, other),
+        None => Uniqueness::Unique(solution),
+    };
+    (ret, decided)
+}
+
+/// A cell to give away at the start of a puzzle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CellFix {
+    pub cell: Point,
+    pub value: CellState,
+}
+
+/// Cells to give away at the start of `nono` so that its solution is
+/// unique: none if it already is, or if it has no solution.  This
+/// adds, one at a time, a cell on which two solutions disagree, so
+/// there are few fixes, though not always as few as possible.
+pub fn disambiguate(nono: &Nonogram) -> Vec<CellFix> {
+    let puzzle = nono.puzzle();
+    let Some((solution, mut decided)) = solve_from(&puzzle, puzzle.grid(), None) else {
+        return vec![];
+    };
+    let mut hints = puzzle.grid();
+    let mut ret = vec![];
+    while let Some(other) = another_solution(&puzzle, &hints, &solution, &decided, None) {
+        let i = (0..solution.cells().len())
+            .find(|i| solution.cells()[*i] != other.cells()[*i])
+            .unwrap();
+        let cell = Point::from_index(i, puzzle.width());
+        hints[cell] = solution[cell];
+        ret.push(CellFix {
+            cell,
+            value: solution[cell],
+        });
+        decided = solve_from(&puzzle, hints.clone(), None).unwrap().1;
+    }
+    ret
+}
+
+/// Solve `puzzle` from `grid`, and tell how each cell was decided,
+/// unless `cancel` is set first.
+fn solve_from(
+    puzzle: &Puzzle,
+    mut grid: Grid,
+    cancel: Option<&Arc<AtomicBool>>,
+) -> Option<(Grid, Vec<Option<Decided>>)> {
+    let mut solver = Solver::for_grid(puzzle, &mut grid, SolverConfig::default());
+    if let Some(cancel) = cancel {
+        solver.set_cancel_flag(Arc::clone(cancel));
+    }
+    if solver.resume() != SolveOutcome::Solved {
+        return None;
+    }
+    let decided = solver.guess_map();
+    Some((grid, decided))
+}
+
+/// A solution of `puzzle` from `hints` other than `solution`, if
+/// there is one.  `decided` tells how the solver decided each cell of
+/// `solution`: those it deduced without guessing are the same in
+/// every solution.
+fn another_solution(
+    puzzle: &Puzzle,
+    hints: &Grid,
+    solution: &Grid,
+    decided: &[Option<Decided>],
+    cancel: Option<&Arc<AtomicBool>>,
+) -> Option<Grid> {
+    for (i, how) in decided.iter().enumerate() {
+        if cancel.is_some_and(|flag| flag.load(Ordering::Relaxed)) {
+            break;
+        }
+        if *how == Some(Decided::Deduced) || hints.cells()[i] != CellState::Undecided {
+            continue;
+        }
+        let cell = Point::from_index(i, puzzle.width());
+        let mut grid = hints.clone();
+        grid[cell] = solution[cell].opposite();
+        if let Some((other, _)) = solve_from(puzzle, grid, cancel) {
+            return Some(other);
+        }
+    }
+    None
+}
@@ -0,0 +1,103 @@
+//! Help for players, for game frontends built on this crate.
+
+use crate::line::{line_consensus, LineMask};
+use crate::{CellState, LineRef, LineStatus, Mark, Nonogram, PlayerGrid, Point};
+
+use core::error;
+use core::fmt;
+
+/// A mark of the player that contradicts the solution.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Mistake {
+    pub cell: Point,
+    /// What the player put in the cell.
+    pub mark: Mark,
+}
+
+/// The marks of `player` (not counting pencil marks) that contradict
+/// the solution of `nono`, row by row.
+///
+/// If the grid of `nono` is complete, eg because it was set to the
+/// goal of the puzzle, it is taken as the solution; otherwise, the
+/// puzzle is solved first.  Cells the solver cannot decide, on an
+/// inconsistent puzzle, are never reported.
+pub fn check_progress(nono: &Nonogram, player: &PlayerGrid) -> Vec<Mistake> {
+    let solved;
+    let solution = if nono.cells.contains(&CellState::Undecided) {
+        let mut n = nono.clone();
+        n.solve();
+        solved = n;
+        &solved
+    } else {
+        nono
+    };
+
+    let mut ret = vec![];
+    for y in 0..nono.height() {
+        for x in 0..nono.width() {
+            let cell = Point::new(x, y);
+            let expected = solution[cell];
+            match player[cell] {
+                Some(mark) if expected != CellState::Undecided && mark.state() != expected => {
+                    ret.push(Mistake { cell, mark })
+                }
+                _ => (),
+            }
+        }
+    }
+    ret
+}
+
+/// The rows, then columns, where the marks of `player` (not counting
+/// pencil marks) contradict the clue, see [Nonogram::line_status].
+/// Unlike [check_progress], this doesn't need the solution, so it
+/// only catches mistakes visible from the line itself.
+pub fn violated_lines(nono: &Nonogram, player: &PlayerGrid) -> Vec<LineRef> {
+    let mut played = nono.clone();
+    player.apply_to(&mut played, false);
+    (played.lines())
+        .filter(|line| played.line_status(*line) == LineStatus::Violated)
+        .collect()
+}
+
+/// Cells that can be deduced next, all from the same line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hint {
+    pub line: LineRef,
+    /// The cells deduced, with their values.
+    pub cells: Vec<(Point, CellState)>,
+}
+
+/// The clue of `line` doesn't fit the current grid, see [next_hint].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LineContradiction {
+    pub line: LineRef,
+}
+
+impl fmt::Display for LineContradiction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "The clue of {} doesn't fit the grid.", self.line)
+    }
+}
+
+impl error::Error for LineContradiction {}
+
+/// The first row, or else column, of `nono` where line logic decides
+/// some cells of its current grid.  None if there's none, and an
+/// error if a line contradicts its clue.
+pub fn next_hint(nono: &Nonogram) -> Result<Option<Hint>, LineContradiction> {
+    for at in nono.lines() {
+        let line = LineMask::from(nono.line(at));
+        let clue = nono.clue(at);
+        let consensus = line_consensus(clue, &line).ok_or(LineContradiction { line: at })?;
+        let cells: Vec<(Point, CellState)> = (line.cells().iter().zip(consensus.cells()))
+            .enumerate()
+            .filter(|(_, (old, new))| old != new)
+            .map(|(i, (_, new))| (at.cell(i), *new))
+            .collect();
+        if !cells.is_empty() {
+            return Ok(Some(Hint { line: at, cells }));
+        }
+    }
+    Ok(None)
+}
@@ -0,0 +1,130 @@
+//! Saving and resuming solver state.
+//!
+//! A [SolverCheckpoint] only records the grid: the solver's candidate
+//! sets only ever shrink by being filtered against the grid, so they
+//! can be rebuilt from the clues and the saved cells when restoring.
+//! This keeps checkpoints small even for very large puzzles.
+//!
+//! The text format is:
+//!
+//! <pre>
+//! nonograms-checkpoint 1
+//! width 5
+//! height 2
+//! cells "01??10??11"
+//! </pre>
+//!
+//! where cells are listed row by row, `0` is empty, `1` filled and `?`
+//! undecided.
+
+use crate::CellState;
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::error;
+use core::fmt;
+use core::str::FromStr;
+
+const MAGIC: &str = "nonograms-checkpoint";
+const VERSION: &str = "1";
+
+/// A snapshot of a [crate::Solver] run, produced by
+/// [crate::Solver::checkpoint] and consumed by [crate::Solver::restore].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SolverCheckpoint {
+    pub width: usize,
+    pub height: usize,
+    pub cells: Vec<CellState>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CheckpointError {
+    /// The text is not a checkpoint, or not of a supported version.
+    BadHeader,
+    /// A line of the checkpoint couldn't be read.
+    Malformed(String),
+    /// The checkpoint doesn't have the dimensions of the puzzle.
+    DimensionMismatch,
+    /// The checkpointed grid contradicts the puzzle's clues.
+    Inconsistent,
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckpointError::BadHeader => f.write_str("Not a checkpoint file."),
+            CheckpointError::Malformed(line) => write!(f, "Malformed checkpoint line: {}", line),
+            CheckpointError::DimensionMismatch => {
+                f.write_str("Checkpoint dimensions don't match the puzzle.")
+            }
+            CheckpointError::Inconsistent => {
+                f.write_str("Checkpoint contradicts the puzzle's clues.")
+            }
+        }
+    }
+}
+
+impl error::Error for CheckpointError {}
+
+impl fmt::Display for SolverCheckpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} {}", MAGIC, VERSION)?;
+        writeln!(f, "width {}", self.width)?;
+        writeln!(f, "height {}", self.height)?;
+        let cells: String = self.cells.iter().map(CellState::as_char).collect();
+        writeln!(f, "cells \"{}\"", cells)
+    }
+}
+
+impl FromStr for SolverCheckpoint {
+    type Err = CheckpointError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().map(str::trim).filter(|l| !l.is_empty());
+        if lines.next() != Some(&format!("{} {}", MAGIC, VERSION)) {
+            return Err(CheckpointError::BadHeader);
+        }
+
+        let mut width = None;
+        let mut height = None;
+        let mut cells = None;
+        for line in lines {
+            let malformed = || CheckpointError::Malformed(line.to_string());
+            let (key, value) = line.split_once(' ').ok_or_else(malformed)?;
+            let value = value.trim();
+            match key {
+                "width" => width = Some(value.parse().map_err(|_| malformed())?),
+                "height" => height = Some(value.parse().map_err(|_| malformed())?),
+                "cells" => {
+                    cells = Some(
+                        value
+                            .trim_matches('"')
+                            .chars()
+                            .map(|c| CellState::from_char(c).ok_or_else(malformed))
+                            .collect::<Result<Vec<_>, _>>()?,
+                    )
+                }
+                _ => return Err(malformed()),
+            }
+        }
+
+        let missing = |what: &str| CheckpointError::Malformed(format!("missing {}", what));
+        let width: usize = width.ok_or_else(|| missing("width"))?;
+        let height = height.ok_or_else(|| missing("height"))?;
+        let cells = cells.ok_or_else(|| missing("cells"))?;
+        let size = width.checked_mul(height).ok_or_else(|| {
+            CheckpointError::Malformed(format!("width {} height {}", width, height))
+        })?;
+        if cells.len() != size {
+            return Err(CheckpointError::DimensionMismatch);
+        }
+        Ok(SolverCheckpoint {
+            width,
+            height,
+            cells,
+        })
+    }
+}
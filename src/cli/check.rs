@@ -0,0 +1,84 @@
+//! `nonograms check [--suggest-fixes] [--diff] FILES...`: tell whether
+//! puzzles have a single solution.
+//!
+//! Options:
+//!
+//!  - `--suggest-fixes`: for puzzles with several solutions, list
+//!    cells to give away so that the solution is unique, as `x y
+//!    filled` or `x y empty`, from 0.
+//!  - `--diff`: for puzzles with a goal, solve them and draw the
+//!    result against the goal, with mistakes in red, see
+//!    [nonograms::render::diff].
+
+use super::{puzzle_files, report_error, unknown_flag, Arg, Args, CliResult, UsageError};
+
+use nonograms::analysis::{self, Uniqueness};
+use nonograms::render::{self, RenderOptions};
+use nonograms::{formats, CellDiff, CellState, Nonogram};
+use std::path::Path;
+
+pub fn run(mut args: Args) -> CliResult {
+    let mut suggest_fixes = false;
+    let mut diff = false;
+    let mut files = vec![];
+    while let Some(arg) = args.next_arg() {
+        match arg {
+            Arg::Flag(f) if f == "suggest-fixes" => suggest_fixes = true,
+            Arg::Flag(f) if f == "diff" => diff = true,
+            Arg::Flag(f) => return Err(unknown_flag(&f)),
+            Arg::Positional(file) => files.push(file),
+        }
+    }
+    let files = puzzle_files(files);
+    if files.is_empty() {
+        return Err(UsageError(String::from("check needs puzzle files")).into());
+    }
+
+    for fname in files {
+        let (nono, goal) = match formats::read_file(Path::new(&fname)) {
+            Ok(puzzle) => {
+                let goal = puzzle.goal.clone();
+                (Nonogram::from(puzzle), goal)
+            }
+            Err(e) => {
+                eprint!("{}: ", fname);
+                report_error(&e);
+                continue;
+            }
+        };
+        match analysis::uniqueness(&nono) {
+            Uniqueness::NoSolution => println!("{}: no solution", fname),
+            Uniqueness::Unique(_) => println!("{}: unique solution", fname),
+            Uniqueness::Multiple(_, _) => {
+                println!("{}: several solutions", fname);
+                if suggest_fixes {
+                    for fix in analysis::disambiguate(&nono) {
+                        let value = match fix.value {
+                            CellState::Filled => "filled",
+                            _ => "empty",
+                        };
+                        println!("  {} {} {}", fix.cell.x, fix.cell.y, value);
+                    }
+                }
+            }
+        }
+        if diff {
+            match goal {
+                Some(goal) => {
+                    let mut solved = nono.clone();
+                    solved.solve();
+                    let wrong = (solved.diff_against_goal(&goal).iter())
+                        .filter(|d| **d != CellDiff::Match)
+                        .count();
+                    println!("  {} cells differ from the goal", wrong);
+                    print!(
+                        "{}",
+                        render::diff::render(&solved, &goal, &RenderOptions::default())
+                    );
+                }
+                None => println!("  no goal"),
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,72 @@
+//! `nonograms convert [--from FORMAT] [--to FORMAT] INPUT OUTPUT`:
+//! convert a puzzle between file formats.  `-` reads from stdin, or
+//! writes to stdout.
+//!
+//! Options:
+//!
+//!  - `--from auto|non|json|webpbn|tatham|csv`: the format of the input.
+//!    The default, `auto`, goes by the extension of the input, or else
+//!    by its contents.
+//!  - `--to non|json|webpbn|tatham|csv`: the format of the output.  This
+//!    defaults to the format of the extension of the output.
+//!  - `--allow-lossy`: convert even if the output format can't keep
+//!    everything about the puzzle, its title or goal for example, see
+//!    [nonograms::formats::round_trip_check].  What is lost is printed
+//!    as a warning.  Without this, such conversions fail.
+
+use super::{unknown_flag, Arg, Args, CliResult, UsageError};
+
+use nonograms::formats;
+use nonograms::parser::read_text;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub fn run(mut args: Args) -> CliResult {
+    let mut from = String::from("auto");
+    let mut to = None;
+    let mut allow_lossy = false;
+    let mut files = vec![];
+    while let Some(arg) = args.next_arg() {
+        match arg {
+            Arg::Flag(f) if f == "from" => from = args.value(&f)?,
+            Arg::Flag(f) if f == "to" => to = Some(args.value(&f)?),
+            Arg::Flag(f) if f == "allow-lossy" => allow_lossy = true,
+            Arg::Flag(f) => return Err(unknown_flag(&f)),
+            Arg::Positional(file) => files.push(file),
+        }
+    }
+    let [input, output] = &files[..] else {
+        return Err(UsageError(String::from("convert needs an input and an output")).into());
+    };
+    let to = match to {
+        Some(name) => formats::by_name(&name)?,
+        None => formats::for_path(Path::new(output))
+            .ok_or_else(|| UsageError(format!("--to is needed to write {}", output)))?,
+    };
+
+    let (text, path) = if input == "-" {
+        (read_text(&mut io::stdin())?, None)
+    } else {
+        (
+            read_text(&mut fs::File::open(input)?)?,
+            Some(Path::new(input)),
+        )
+    };
+    let puzzle = match from.as_str() {
+        "auto" => formats::parse(&text, path)?,
+        name => formats::by_name(name)?.parse(&text)?,
+    };
+    match formats::round_trip_check(&puzzle, to) {
+        Err(diff) if allow_lossy => eprintln!("Warning: {}", diff),
+        result => result?,
+    }
+    let converted = to.write(&puzzle);
+
+    if output == "-" {
+        print!("{}", converted);
+    } else {
+        fs::write(output, converted)?;
+    }
+    Ok(())
+}
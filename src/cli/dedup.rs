@@ -0,0 +1,54 @@
+//! `nonograms dedup [--symmetric] DIRECTORY`: report puzzle files
+//! under `DIRECTORY` with the same clues, one group of duplicates per
+//! paragraph.
+//!
+//! Options:
+//!
+//!  - `--symmetric`: also report puzzles that are rotations or
+//!    reflections of each other.
+
+use super::{report_error, unknown_flag, Arg, Args, CliResult, UsageError};
+
+use nonograms::collection::Collection;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+pub fn run(mut args: Args) -> CliResult {
+    let mut symmetric = false;
+    let mut dirs = vec![];
+    while let Some(arg) = args.next_arg() {
+        match arg {
+            Arg::Flag(f) if f == "symmetric" => symmetric = true,
+            Arg::Flag(f) => return Err(unknown_flag(&f)),
+            Arg::Positional(dir) => dirs.push(dir),
+        }
+    }
+    let [dir] = &dirs[..] else {
+        return Err(UsageError(String::from("dedup needs a directory")).into());
+    };
+
+    let collection = Collection::load(Path::new(dir))?;
+    for (path, e) in &collection.errors {
+        eprint!("{}: ", path.display());
+        report_error(e);
+    }
+    let mut by_hash: BTreeMap<u64, Vec<PathBuf>> = BTreeMap::new();
+    for entry in collection.entries() {
+        let hash = if symmetric {
+            entry.puzzle.canonical_hash_up_to_symmetry()
+        } else {
+            entry.puzzle.canonical_hash()
+        };
+        by_hash.entry(hash).or_default().push(entry.path.clone());
+    }
+
+    let mut groups: Vec<Vec<PathBuf>> = by_hash.into_values().filter(|g| g.len() > 1).collect();
+    groups.sort();
+    for (i, group) in groups.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        group.iter().for_each(|path| println!("{}", path.display()));
+    }
+    Ok(())
+}
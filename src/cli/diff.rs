@@ -0,0 +1,48 @@
+//! `nonograms diff [--symmetric] FILE1 FILE2`: check that two puzzle
+//! files have the same clues, whatever their formats and metadata, eg
+//! after converting one into the other.  This lists the lines whose
+//! clues differ, and exits with an error if there are any.
+//!
+//! Options:
+//!
+//!  - `--symmetric`: also accept puzzles that are rotations or
+//!    reflections of each other, and tell which.
+
+use super::{unknown_flag, Arg, Args, CliResult, UsageError};
+
+use nonograms::{formats, ClueDiff, Nonogram, Transform};
+use std::path::Path;
+
+pub fn run(mut args: Args) -> CliResult {
+    let mut symmetric = false;
+    let mut files = vec![];
+    while let Some(arg) = args.next_arg() {
+        match arg {
+            Arg::Flag(f) if f == "symmetric" => symmetric = true,
+            Arg::Flag(f) => return Err(unknown_flag(&f)),
+            Arg::Positional(file) => files.push(file),
+        }
+    }
+    let [a, b] = &files[..] else {
+        return Err(UsageError(String::from("diff needs two puzzle files")).into());
+    };
+    let ours = Nonogram::from(formats::read_file(Path::new(a))?);
+    let theirs = Nonogram::from(formats::read_file(Path::new(b))?);
+
+    let result = match symmetric {
+        true => ours.equivalent_up_to_symmetry(&theirs),
+        false => ours.equivalent_to(&theirs).map(|_| Transform::default()),
+    };
+    match result {
+        Ok(transform) if transform == Transform::default() => println!("Same clues."),
+        Ok(transform) => println!("Same clues, {}.", transform),
+        Err(ClueDiff::Lines(lines)) => {
+            for line in &lines {
+                println!("{}: {} vs {}", line, ours.clue(*line), theirs.clue(*line));
+            }
+            return Err(format!("The clues of {} lines differ", lines.len()).into());
+        }
+        Err(e) => return Err(e.to_string().into()),
+    }
+    Ok(())
+}
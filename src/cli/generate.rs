@@ -0,0 +1,144 @@
+//! `nonograms generate [options] --out-dir DIR`: write random puzzles,
+//! with their solutions as goals, to numbered files `001.non`,
+//! `002.non`... under `DIR`, eg to make a puzzle pack.
+//!
+//! The seed of each puzzle is listed in `DIR/manifest.txt`, after a
+//! comment line with the options to pass again, with `--seed` and
+//! `--count 1`, to make the same puzzle.
+//!
+//! Options:
+//!
+//!  - `--count N`: how many puzzles to write, 1 by default.
+//!  - `--size WxH`: the size of the puzzles, or `N` for N×N, 10×10 by
+//!    default.
+//!  - `--unique`: only write puzzles with a unique solution.  Grids
+//!    with several are repaired, see [nonograms::generator::unique].
+//!  - `--difficulty easy|medium|hard`: only write unique puzzles of
+//!    this difficulty, see [nonograms::analysis::Difficulty].
+//!  - `--seed N`: the seed of the first puzzle, where the next are
+//!    made from the next seeds.  This defaults to the current time.
+//!  - `--format non|json|webpbn|tatham|csv`: the format of the files,
+//!    `non` by default.
+//!  - `--out-dir DIR`: where to write the files, created if needed.
+
+use super::{unknown_flag, Arg, Args, CliResult, UsageError};
+
+use nonograms::analysis::Difficulty;
+use nonograms::formats;
+use nonograms::generator;
+use nonograms::Puzzle;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How many seeds in a row may fail to make a puzzle, before giving
+/// up.
+const FAILURES: usize = 10;
+
+pub fn run(mut args: Args) -> CliResult {
+    let mut count = 1;
+    let mut size = (10, 10);
+    let mut unique = false;
+    let mut difficulty = None;
+    let mut seed = None;
+    let mut format = formats::by_name("non")?;
+    let mut out_dir = None;
+    while let Some(arg) = args.next_arg() {
+        match arg {
+            Arg::Flag(f) if f == "count" => {
+                let value = args.value(&f)?;
+                count = value
+                    .parse()
+                    .map_err(|_| UsageError(format!("Bad count: {}", value)))?;
+            }
+            Arg::Flag(f) if f == "size" => size = parse_size(&args.value(&f)?)?,
+            Arg::Flag(f) if f == "unique" => unique = true,
+            Arg::Flag(f) if f == "difficulty" => {
+                difficulty = Some(args.value(&f)?.parse::<Difficulty>().map_err(UsageError)?)
+            }
+            Arg::Flag(f) if f == "seed" => {
+                let value = args.value(&f)?;
+                let n = value
+                    .parse()
+                    .map_err(|_| UsageError(format!("Bad seed: {}", value)))?;
+                seed = Some(n);
+            }
+            Arg::Flag(f) if f == "format" => format = formats::by_name(&args.value(&f)?)?,
+            Arg::Flag(f) if f == "out-dir" => out_dir = Some(args.value(&f)?),
+            Arg::Flag(f) => return Err(unknown_flag(&f)),
+            Arg::Positional(arg) => {
+                return Err(UsageError(format!("Unexpected argument: {}", arg)).into())
+            }
+        }
+    }
+    let Some(out_dir) = out_dir else {
+        return Err(UsageError(String::from("generate needs --out-dir")).into());
+    };
+    let mut seed = seed.unwrap_or_else(|| {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        now.as_nanos() as u64
+    });
+
+    // The options that make the same puzzles again, for the manifest.
+    let mut options = format!("--size {}x{}", size.0, size.1);
+    if let Some(difficulty) = difficulty {
+        options.push_str(&format!(" --difficulty {}", difficulty));
+    } else if unique {
+        options.push_str(" --unique");
+    }
+    let mut manifest = format!("# {}\n", options);
+
+    let out_dir = Path::new(&out_dir);
+    fs::create_dir_all(out_dir)?;
+    let digits = count.to_string().len().max(3);
+    let extension = format.extensions()[0];
+    for n in 1..=count {
+        let mut failures = 0;
+        let puzzle = loop {
+            if let Some(puzzle) = generate(size, unique, difficulty, seed) {
+                break puzzle;
+            }
+            eprintln!("Warning: no puzzle from seed {}", seed);
+            seed = seed.wrapping_add(1);
+            failures += 1;
+            if failures == FAILURES {
+                return Err(format!("Could not generate puzzle {} of {}", n, count).into());
+            }
+        };
+        let name = format!("{:0width$}.{}", n, extension, width = digits);
+        fs::write(out_dir.join(&name), format.write(&puzzle))?;
+        manifest.push_str(&format!("{} {}\n", name, seed));
+        seed = seed.wrapping_add(1);
+    }
+    fs::write(out_dir.join("manifest.txt"), manifest)?;
+    Ok(())
+}
+
+/// The puzzle from `seed`, if the generator makes one.
+fn generate(
+    size: (usize, usize),
+    unique: bool,
+    difficulty: Option<Difficulty>,
+    seed: u64,
+) -> Option<Puzzle> {
+    match difficulty {
+        Some(difficulty) => generator::with_difficulty(difficulty, size, seed),
+        None if unique => generator::unique(size, seed),
+        None => Some(generator::random(size, seed)),
+    }
+}
+
+/// Read a size, `WxH` or `N`.
+fn parse_size(s: &str) -> Result<(usize, usize), UsageError> {
+    let bad = || UsageError(format!("Bad size: {}", s));
+    let side = |n: &str| match n.parse::<usize>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(bad()),
+    };
+    match s.split_once(['x', '×']) {
+        Some((w, h)) => Ok((side(w)?, side(h)?)),
+        None => side(s).map(|n| (n, n)),
+    }
+}
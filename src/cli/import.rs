@@ -0,0 +1,131 @@
+//! `nonograms import [options] IMAGE OUTPUT`: make a puzzle out of a
+//! PNM image, see [nonograms::image], with the picture as its goal.
+//! `-` writes to stdout.  The puzzle is titled with the file name of
+//! the image.
+//!
+//! Puzzles are in black and white, a cell per pixel, unless
+//! `--colors` is given.  Photographs need downscaling, and usually
+//! `--dither`, to become recognizable puzzles rather than noise.
+//!
+//! Options:
+//!
+//!  - `--width N`, `--height N`: scale the image to this size first.
+//!    With only one of them, the image keeps its aspect ratio.
+//!  - `--threshold N`: pixels darker than this luminance, from 0 to
+//!    255, are filled, 128 by default.
+//!  - `--dither`: dither the image around the threshold, so that grays
+//!    come out as patterns.
+//!  - `--repair`: flip cells of the picture until the puzzle can be
+//!    solved by line logic, and so has a unique solution, see
+//!    [nonograms::generator::repair].  The flipped cells are listed on
+//!    stderr.
+//!  - `--to non|json|webpbn|tatham|csv`: the format of the output.  This
+//!    defaults to the format of the extension of the output, or else
+//!    `non`.
+//!  - `--colors N`: make a puzzle in colors instead, by reducing the
+//!    image to N colors, with the lightest as background, from 2 to
+//!    32.  These are written as webpbn XML only.
+
+use super::{unknown_flag, Arg, Args, CliResult, UsageError};
+
+use nonograms::formats::{self, webpbn};
+use nonograms::generator;
+use nonograms::image::{self, ColoredPuzzle, Image, Method, MAX_COLORS};
+use nonograms::Point;
+use std::fs;
+use std::path::Path;
+
+pub fn run(mut args: Args) -> CliResult {
+    let mut colors = None;
+    let mut width = None;
+    let mut height = None;
+    let mut threshold = None;
+    let mut dither = false;
+    let mut repair = false;
+    let mut to = None;
+    let mut files = vec![];
+    while let Some(arg) = args.next_arg() {
+        match arg {
+            Arg::Flag(f) if f == "colors" => {
+                let value = args.value(&f)?;
+                match value.parse::<usize>() {
+                    Ok(n) if (2..=MAX_COLORS).contains(&n) => colors = Some(n),
+                    _ => return Err(UsageError(format!("Bad number of colors: {}", value)).into()),
+                }
+            }
+            Arg::Flag(f) if f == "width" => width = Some(side(&args.value(&f)?)?),
+            Arg::Flag(f) if f == "height" => height = Some(side(&args.value(&f)?)?),
+            Arg::Flag(f) if f == "threshold" => {
+                let value = args.value(&f)?;
+                let n = value
+                    .parse()
+                    .map_err(|_| UsageError(format!("Bad threshold: {}", value)))?;
+                threshold = Some(n);
+            }
+            Arg::Flag(f) if f == "dither" => dither = true,
+            Arg::Flag(f) if f == "repair" => repair = true,
+            Arg::Flag(f) if f == "to" => to = Some(args.value(&f)?),
+            Arg::Flag(f) => return Err(unknown_flag(&f)),
+            Arg::Positional(file) => files.push(file),
+        }
+    }
+    let [input, output] = &files[..] else {
+        return Err(UsageError(String::from("import needs an image and an output")).into());
+    };
+
+    let image = Image::read_file(Path::new(input))?.resize_to(width, height);
+    let title = Path::new(input).file_stem().unwrap_or_default();
+    let title = Some(title.to_string_lossy().into_owned());
+    let text = match colors {
+        Some(colors) => {
+            if threshold.is_some() || dither || repair || to.is_some() {
+                let what =
+                    "--threshold, --dither, --repair and --to are for black and white puzzles";
+                return Err(UsageError(String::from(what)).into());
+            }
+            let mut puzzle = ColoredPuzzle::from_image(&image, colors);
+            puzzle.title = title;
+            webpbn::write_colored(&puzzle)
+        }
+        None => {
+            let format = match to {
+                Some(name) => formats::by_name(&name)?,
+                None => formats::for_path(Path::new(output)).unwrap_or(formats::by_name("non")?),
+            };
+            let threshold = threshold.unwrap_or(128);
+            let method = match dither {
+                true => Method::Dither(threshold),
+                false => Method::Threshold(threshold),
+            };
+            let mut puzzle = image::to_puzzle(&image, method);
+            puzzle.title = title;
+            if repair {
+                let repaired = generator::repair(&puzzle).ok_or(
+                    "Could not repair the puzzle without changing too much of the picture",
+                )?;
+                let cells: Vec<String> = repaired.flipped.iter().map(Point::to_string).collect();
+                match cells.len() {
+                    0 => eprintln!("The puzzle needed no repair."),
+                    n => eprintln!("Flipped {} cells: {}", n, cells.join(", ")),
+                }
+                puzzle = repaired.puzzle;
+            }
+            format.write(&puzzle)
+        }
+    };
+
+    if output == "-" {
+        print!("{}", text);
+    } else {
+        fs::write(output, text)?;
+    }
+    Ok(())
+}
+
+/// Read a width or a height.
+fn side(s: &str) -> Result<usize, UsageError> {
+    match s.parse::<usize>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(UsageError(format!("Bad size: {}", s))),
+    }
+}
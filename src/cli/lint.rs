@@ -0,0 +1,68 @@
+//! `nonograms lint FILES...`: look for mistakes in puzzles, without
+//! printing their solutions, eg to check a collection of puzzles.
+//!
+//! Each puzzle must parse, pass [nonograms::Nonogram::validate], and
+//! have a single solution, which is its goal if it has one.  This only
+//! prints the problems, one per line, and exits with an error if
+//! there are any.
+
+use super::{describe_error, puzzle_files, unknown_flag, Arg, Args, CliResult, UsageError};
+
+use nonograms::analysis::{self, Uniqueness};
+use nonograms::solver::is_solution;
+use nonograms::{formats, Nonogram};
+use std::path::Path;
+
+pub fn run(mut args: Args) -> CliResult {
+    let mut files = vec![];
+    while let Some(arg) = args.next_arg() {
+        match arg {
+            Arg::Flag(f) => return Err(unknown_flag(&f)),
+            Arg::Positional(file) => files.push(file),
+        }
+    }
+    let files = puzzle_files(files);
+    if files.is_empty() {
+        return Err(UsageError(String::from("lint needs puzzle files")).into());
+    }
+
+    let mut failed = 0;
+    for fname in &files {
+        let problems = lint(Path::new(fname));
+        for problem in &problems {
+            println!("{}: {}", fname, problem);
+        }
+        if !problems.is_empty() {
+            failed += 1;
+        }
+    }
+    match failed {
+        0 => Ok(()),
+        n => Err(format!("{} of {} files have problems", n, files.len()).into()),
+    }
+}
+
+/// The problems of the puzzle at `path`.
+fn lint(path: &Path) -> Vec<String> {
+    let puzzle = match formats::read_file(path) {
+        Ok(puzzle) => puzzle,
+        Err(e) => return vec![describe_error(&e)],
+    };
+    let nono = Nonogram::from(puzzle.clone());
+    if let Err(e) = nono.validate() {
+        return vec![describe_error(&e)];
+    }
+
+    let mut problems = vec![];
+    if let Some(goal) = &puzzle.goal {
+        if !is_solution(&puzzle, goal) {
+            problems.push(String::from("The goal doesn't fit the clues."));
+        }
+    }
+    match analysis::uniqueness(&nono) {
+        Uniqueness::NoSolution => problems.push(String::from("No solution.")),
+        Uniqueness::Multiple(_, _) => problems.push(String::from("Several solutions.")),
+        Uniqueness::Unique(_) => (),
+    }
+    problems
+}
@@ -0,0 +1,63 @@
+//! `nonograms list [options] DIRECTORY`: list the puzzles under
+//! `DIRECTORY`, with their size, title and author, separated by tabs.
+//!
+//! Options:
+//!
+//!  - `--title TEXT`, `--author TEXT`: only puzzles whose title, or
+//!    author, contains `TEXT`, ignoring case.
+//!  - `--min-size WxH`, `--max-size WxH`: only puzzles at least, or at
+//!    most, that large.
+//!  - `--difficulty easy|medium|hard`: only puzzles that line logic
+//!    alone solves, that need probing, or that need guesses.  This
+//!    solves the puzzles.
+
+use super::{report_error, unknown_flag, Arg, Args, CliResult, UsageError};
+
+use nonograms::collection::{Collection, Query};
+use std::path::Path;
+
+pub fn run(mut args: Args) -> CliResult {
+    let mut query = Query::default();
+    let mut dirs = vec![];
+    while let Some(arg) = args.next_arg() {
+        match arg {
+            Arg::Flag(f) if f == "title" => query.title = Some(args.value(&f)?),
+            Arg::Flag(f) if f == "author" => query.author = Some(args.value(&f)?),
+            Arg::Flag(f) if f == "min-size" => query.min_size = Some(size(&args.value(&f)?)?),
+            Arg::Flag(f) if f == "max-size" => query.max_size = Some(size(&args.value(&f)?)?),
+            Arg::Flag(f) if f == "difficulty" => {
+                query.difficulty = Some(args.value(&f)?.parse().map_err(UsageError)?)
+            }
+            Arg::Flag(f) => return Err(unknown_flag(&f)),
+            Arg::Positional(dir) => dirs.push(dir),
+        }
+    }
+    let [dir] = &dirs[..] else {
+        return Err(UsageError(String::from("list needs a directory")).into());
+    };
+
+    let collection = Collection::load(Path::new(dir))?;
+    for (path, e) in &collection.errors {
+        eprint!("{}: ", path.display());
+        report_error(e);
+    }
+    for entry in collection.query(&query) {
+        let puzzle = &entry.puzzle;
+        println!(
+            "{}\t{}x{}\t{}\t{}",
+            entry.path.display(),
+            puzzle.width(),
+            puzzle.height(),
+            puzzle.title.as_deref().unwrap_or(""),
+            puzzle.author.as_deref().unwrap_or(""),
+        );
+    }
+    Ok(())
+}
+
+/// Read a size written `WxH`.
+fn size(s: &str) -> Result<(usize, usize), UsageError> {
+    let bad = || UsageError(format!("Bad size: {} (expected eg 20x20)", s));
+    let (w, h) = s.split_once('x').ok_or_else(bad)?;
+    Ok((w.parse().map_err(|_| bad())?, h.parse().map_err(|_| bad())?))
+}
@@ -0,0 +1,244 @@
+//! Subcommands of the `nonograms` binary.
+//!
+//! Those reading puzzle files also take directories and wildcard
+//! patterns, see [puzzle_files].
+
+pub mod check;
+pub mod convert;
+pub mod dedup;
+pub mod diff;
+pub mod generate;
+pub mod import;
+pub mod lint;
+pub mod list;
+pub mod print;
+pub mod serve;
+pub mod solve;
+pub mod verify;
+pub mod watch;
+
+use nonograms::{collection, formats};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub type CliResult = Result<(), Box<dyn Error>>;
+
+/// Find the subcommand called `name`.
+pub fn command(name: &str) -> Option<fn(Args) -> CliResult> {
+    match name {
+        "check" => Some(check::run),
+        "convert" => Some(convert::run),
+        "dedup" => Some(dedup::run),
+        "diff" => Some(diff::run),
+        "generate" => Some(generate::run),
+        "import" => Some(import::run),
+        "lint" => Some(lint::run),
+        "list" => Some(list::run),
+        "print" => Some(print::run),
+        "serve" => Some(serve::run),
+        "solve" => Some(solve::run),
+        "verify" => Some(verify::run),
+        "watch" => Some(watch::run),
+        _ => None,
+    }
+}
+
+/// A mistake in the command line itself.
+#[derive(Debug)]
+pub struct UsageError(pub String);
+
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Error for UsageError {}
+
+/// A single command-line argument.
+pub enum Arg {
+    /// `--name` or `-n`, without the dashes.
+    Flag(String),
+    Positional(String),
+}
+
+/// The arguments of a subcommand, read one at a time.  Everything
+/// after a lone `--` is positional.
+pub struct Args {
+    args: std::vec::IntoIter<String>,
+    only_positional: bool,
+}
+
+impl Args {
+    pub fn new(args: Vec<String>) -> Args {
+        Args {
+            args: args.into_iter(),
+            only_positional: false,
+        }
+    }
+
+    pub fn next_arg(&mut self) -> Option<Arg> {
+        let arg = self.args.next()?;
+        if self.only_positional || arg == "-" || !arg.starts_with('-') {
+            Some(Arg::Positional(arg))
+        } else if arg == "--" {
+            self.only_positional = true;
+            self.next_arg()
+        } else {
+            Some(Arg::Flag(arg.trim_start_matches('-').to_string()))
+        }
+    }
+
+    /// The value of option `flag`, which is the next argument.
+    pub fn value(&mut self, flag: &str) -> Result<String, UsageError> {
+        self.args
+            .next()
+            .ok_or_else(|| UsageError(format!("--{} needs a value", flag)))
+    }
+}
+
+pub fn unknown_flag(flag: &str) -> Box<dyn Error> {
+    Box::new(UsageError(format!("Unknown option: --{}", flag)))
+}
+
+/// Print `error` and its chain of sources on a single line.
+pub fn report_error(error: &dyn Error) {
+    eprintln!("Error: {}", describe_error(error));
+}
+
+/// `error` and its chain of sources, on a single line.
+pub fn describe_error(error: &dyn Error) -> String {
+    let mut ret = error.to_string();
+    let mut source = error.source();
+    while let Some(e) = source {
+        ret.push(' ');
+        ret.push_str(&e.to_string());
+        source = e.source();
+    }
+    ret
+}
+
+// * Puzzle files
+
+/// The puzzle files named by the arguments `args`, in order.
+/// Directories stand for the files under them, and patterns with `*`,
+/// `?` or `[...]` that the shell didn't expand, for the files they
+/// match.  Of these, files in no puzzle format, by their extension,
+/// are skipped with a warning.  Other arguments, such as `-` or files
+/// that don't exist, are kept as they are.
+pub fn puzzle_files(args: Vec<String>) -> Vec<String> {
+    let mut ret = vec![];
+    for arg in args {
+        let path = Path::new(&arg);
+        let found = if path.is_dir() {
+            collection::files_under(path)
+        } else if !path.exists() && arg.contains(['*', '?', '[']) {
+            glob(&arg)
+        } else {
+            ret.push(arg);
+            continue;
+        };
+        match found {
+            Ok(found) if found.is_empty() => eprintln!("Warning: no puzzles in {}", arg),
+            Ok(found) => {
+                for path in found {
+                    if formats::for_path(&path).is_some() {
+                        ret.push(path.display().to_string());
+                    } else {
+                        eprintln!("Warning: skipping {}, not a puzzle file", path.display());
+                    }
+                }
+            }
+            Err(e) => eprintln!("Cannot read {}: {}", arg, e),
+        }
+    }
+    ret
+}
+
+/// The files matching `pattern`, in order, and those under the
+/// directories it matches.  Only the last component of the pattern
+/// matches files.
+fn glob(pattern: &str) -> io::Result<Vec<PathBuf>> {
+    let mut paths = vec![PathBuf::new()];
+    let components: Vec<_> = Path::new(pattern).components().collect();
+    for (i, component) in components.iter().enumerate() {
+        let last = i + 1 == components.len();
+        let name = component.as_os_str().to_string_lossy();
+        if !name.contains(['*', '?', '[']) {
+            paths.iter_mut().for_each(|path| path.push(component));
+            continue;
+        }
+        let mut matches = vec![];
+        for dir in &paths {
+            let here = dir.as_os_str().is_empty();
+            let Ok(entries) = fs::read_dir(if here { Path::new(".") } else { dir }) else {
+                continue;
+            };
+            for entry in entries {
+                let entry = entry?;
+                let file_name = entry.file_name().to_string_lossy().into_owned();
+                let hidden = file_name.starts_with('.') && !name.starts_with('.');
+                if !hidden && (last || entry.path().is_dir()) && wildcard(&name, &file_name) {
+                    matches.push(dir.join(file_name));
+                }
+            }
+        }
+        matches.sort();
+        paths = matches;
+    }
+
+    let mut ret = vec![];
+    for path in paths {
+        if path.is_dir() {
+            ret.extend(collection::files_under(&path)?);
+        } else if path.exists() {
+            ret.push(path);
+        }
+    }
+    Ok(ret)
+}
+
+/// Whether `name` matches the shell wildcard pattern `pattern`: `*`
+/// for any characters, `?` for one, and `[...]` for one of a set, as
+/// in `[abc]`, `[a-z]` or `[!0-9]`.
+fn wildcard(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    wildcard_chars(&pattern, &name)
+}
+
+fn wildcard_chars(pattern: &[char], name: &[char]) -> bool {
+    match pattern.first() {
+        None => name.is_empty(),
+        Some('*') => (0..=name.len()).any(|i| wildcard_chars(&pattern[1..], &name[i..])),
+        Some('?') => !name.is_empty() && wildcard_chars(&pattern[1..], &name[1..]),
+        Some('[') => {
+            let Some(end) = (2..pattern.len()).find(|&i| pattern[i] == ']') else {
+                return name.first() == Some(&'[') && wildcard_chars(&pattern[1..], &name[1..]);
+            };
+            let Some(&c) = name.first() else {
+                return false;
+            };
+            let (negated, set) = match pattern[1] {
+                '!' | '^' => (true, &pattern[2..end]),
+                _ => (false, &pattern[1..end]),
+            };
+            let mut found = false;
+            let mut i = 0;
+            while i < set.len() {
+                if i + 2 < set.len() && set[i + 1] == '-' {
+                    found |= set[i] <= c && c <= set[i + 2];
+                    i += 3;
+                } else {
+                    found |= set[i] == c;
+                    i += 1;
+                }
+            }
+            found != negated && wildcard_chars(&pattern[end + 1..], &name[1..])
+        }
+        Some(p) => name.first() == Some(p) && wildcard_chars(&pattern[1..], &name[1..]),
+    }
+}
@@ -0,0 +1,187 @@
+//! `nonograms print [options] FILES...`: render blank puzzles, with
+//! their clues, to print and solve by hand.  `-` reads from stdin.
+//!
+//! Options:
+//!
+//!  - `--format text|markdown|svg|pdf`: what to render a single
+//!    puzzle as.  This defaults to the extension of the output, or
+//!    else text.  Markdown is a table, to paste in issues and wikis.
+//!    PDF needs feature `pdf`.
+//!  - `--size N`: the side of a cell, in pixels for SVG and in points
+//!    for PDF.  Text always takes a character per cell.
+//!  - `--output FILE`: where to write a single puzzle, stdout by
+//!    default.
+//!  - `--pdf BOOK.pdf`: lay out all of the puzzles, several per A4
+//!    page, as a PDF booklet, with feature `pdf`.  Puzzles without a
+//!    title are titled with their file name.
+//!  - `--solutions`: add the solutions to the booklet, on pages of
+//!    their own after the puzzles.
+
+use super::{puzzle_files, unknown_flag, Arg, Args, CliResult, UsageError};
+
+use nonograms::formats;
+use nonograms::parser::read_text;
+use nonograms::render::svg::{self, SvgOptions};
+use nonograms::render::RenderOptions;
+use nonograms::{Nonogram, Puzzle};
+use std::error::Error;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// What to render a single puzzle as.
+#[derive(Clone, Copy)]
+enum Format {
+    Text,
+    Markdown,
+    Svg,
+    Pdf,
+}
+
+impl Format {
+    fn from_name(name: &str) -> Result<Format, UsageError> {
+        match name {
+            "text" | "txt" => Ok(Format::Text),
+            "markdown" | "md" => Ok(Format::Markdown),
+            "svg" => Ok(Format::Svg),
+            "pdf" => Ok(Format::Pdf),
+            _ => Err(UsageError(format!("Unknown print format: {}", name))),
+        }
+    }
+}
+
+pub fn run(mut args: Args) -> CliResult {
+    let mut format = None;
+    let mut size = None;
+    let mut output = None;
+    let mut book = None;
+    let mut solutions = false;
+    let mut files = vec![];
+    while let Some(arg) = args.next_arg() {
+        match arg {
+            Arg::Flag(f) if f == "format" => format = Some(Format::from_name(&args.value(&f)?)?),
+            Arg::Flag(f) if f == "size" => {
+                let value = args.value(&f)?;
+                match value.parse::<usize>() {
+                    Ok(n) if n > 0 => size = Some(n),
+                    _ => return Err(UsageError(format!("Bad cell size: {}", value)).into()),
+                }
+            }
+            Arg::Flag(f) if f == "output" => output = Some(args.value(&f)?),
+            Arg::Flag(f) if f == "pdf" => book = Some(args.value(&f)?),
+            Arg::Flag(f) if f == "solutions" => solutions = true,
+            Arg::Flag(f) => return Err(unknown_flag(&f)),
+            Arg::Positional(file) => files.push(file),
+        }
+    }
+
+    if let Some(book) = book {
+        let files = puzzle_files(files);
+        if files.is_empty() {
+            return Err(UsageError(String::from("print --pdf needs puzzle files")).into());
+        }
+        if format.is_some() || output.is_some() {
+            return Err(UsageError(String::from("--pdf writes a booklet only")).into());
+        }
+        let mut puzzles = vec![];
+        for file in &files {
+            let mut puzzle = read(file)?;
+            if puzzle.title.is_none() && file != "-" {
+                let stem = Path::new(file).file_stem().unwrap_or_default();
+                puzzle.title = Some(stem.to_string_lossy().into_owned());
+            }
+            puzzles.push(puzzle);
+        }
+        fs::write(book, write_book(&puzzles, size, solutions)?)?;
+        return Ok(());
+    }
+    if solutions {
+        return Err(UsageError(String::from("--solutions goes with --pdf")).into());
+    }
+
+    let [input] = &files[..] else {
+        return Err(UsageError(String::from("print needs a single puzzle file, or --pdf")).into());
+    };
+    let format = match (format, &output) {
+        (Some(format), _) => format,
+        (None, Some(output)) => match Path::new(output).extension() {
+            Some(ext) => Format::from_name(&ext.to_string_lossy())?,
+            None => Format::Text,
+        },
+        (None, None) => Format::Text,
+    };
+    let nono = Nonogram::from(read(input)?);
+
+    let rendered = match format {
+        Format::Text => {
+            let options = RenderOptions {
+                undecided: ' ',
+                ..RenderOptions::printed()
+            };
+            nono.as_text_with(&options).into_bytes()
+        }
+        Format::Markdown => nono.as_markdown().into_bytes(),
+        Format::Svg => {
+            let mut options = SvgOptions {
+                show_solution: false,
+                ..SvgOptions::default()
+            };
+            options.cell_size = size.unwrap_or(options.cell_size);
+            svg::render_with(&nono, &options).into_bytes()
+        }
+        Format::Pdf => write_pdf(&nono, size)?,
+    };
+
+    match output {
+        Some(path) if path != "-" => fs::write(path, rendered)?,
+        _ => io::stdout().write_all(&rendered)?,
+    }
+    Ok(())
+}
+
+/// Read the puzzle in `file`, or stdin for `-`.
+fn read(file: &str) -> Result<Puzzle, Box<dyn Error>> {
+    let puzzle = if file == "-" {
+        formats::parse(&read_text(&mut io::stdin())?, None)?
+    } else {
+        let text = read_text(&mut fs::File::open(file)?)?;
+        formats::parse(&text, Some(Path::new(file)))?
+    };
+    Ok(puzzle)
+}
+
+#[cfg(feature = "pdf")]
+fn write_pdf(nono: &Nonogram, size: Option<usize>) -> Result<Vec<u8>, Box<dyn Error>> {
+    use nonograms::render::pdf::{self, PdfOptions};
+    let mut options = PdfOptions {
+        show_solution: false,
+        ..PdfOptions::default()
+    };
+    options.cell_size = size.map_or(options.cell_size, |n| n as f64);
+    Ok(pdf::render(nono, &options))
+}
+
+#[cfg(feature = "pdf")]
+fn write_book(
+    puzzles: &[Puzzle],
+    size: Option<usize>,
+    solutions: bool,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    use nonograms::render::pdf::{self, BookOptions};
+    let mut options = BookOptions {
+        solutions,
+        ..BookOptions::default()
+    };
+    options.cell_size = size.map_or(options.cell_size, |n| n as f64);
+    Ok(pdf::book(puzzles, &options))
+}
+
+#[cfg(not(feature = "pdf"))]
+fn write_pdf(_: &Nonogram, _: Option<usize>) -> Result<Vec<u8>, Box<dyn Error>> {
+    Err("this build of nonograms cannot write PDF (feature `pdf`)".into())
+}
+
+#[cfg(not(feature = "pdf"))]
+fn write_book(_: &[Puzzle], _: Option<usize>, _: bool) -> Result<Vec<u8>, Box<dyn Error>> {
+    Err("this build of nonograms cannot write PDF (feature `pdf`)".into())
+}
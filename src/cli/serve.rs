@@ -0,0 +1,256 @@
+//! `nonograms serve [--listen ADDRESS]`: solve puzzles for a web
+//! front-end, as a small HTTP server, with feature `serve`.
+//!
+//! `POST /solve` takes a puzzle in any format of [nonograms::formats],
+//! eg JSON or the format of nonogram-db, as the body of the request,
+//! and answers with a JSON object:
+//!
+//! <pre>
+//! {
+//!   "width": 3,
+//!   "height": 2,
+//!   "outcome": "solved",
+//!   "grid": "011110",
+//!   "uniqueness": "unique",
+//!   "stats": {"milliseconds": 1, "decided": 6, "guessed": 0}
+//! }
+//! </pre>
+//!
+//! The outcome is `solved` or `contradiction`.  The grid holds the
+//! cells row by row, as in [nonograms::formats::savefile]: `1` for
+//! filled, `0` for empty and `?` for undecided, and is a solution
+//! unless there's none.  Uniqueness is `unique`, `multiple` or
+//! `none`.  `guessed` counts the cells the search had to guess.  Bad
+//! requests get `{"error": ...}`, as do puzzles that take too long,
+//! and requests while every worker is busy and others wait already.
+//!
+//! Responses allow any origin, so that pages served from elsewhere
+//! can call this.
+//!
+//! Options:
+//!
+//!  - `--listen ADDRESS`: where to listen, `127.0.0.1:8080` by default.
+//!  - `--workers N`: how many requests to answer at once, one per
+//!    core by default.
+//!  - `--timeout SECONDS`: how long to solve a puzzle before giving
+//!    up, 10 by default.
+
+use super::{unknown_flag, Arg, Args, CliResult, UsageError};
+
+use std::time::Duration;
+
+pub fn run(mut args: Args) -> CliResult {
+    let mut listen = String::from("127.0.0.1:8080");
+    let mut workers = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut timeout = Duration::from_secs(10);
+    while let Some(arg) = args.next_arg() {
+        match arg {
+            Arg::Flag(f) if f == "listen" => listen = args.value(&f)?,
+            Arg::Flag(f) if f == "workers" => {
+                workers = match args.value(&f)?.parse() {
+                    Ok(n) if n > 0 => n,
+                    _ => return Err(UsageError(String::from("Bad --workers")).into()),
+                }
+            }
+            Arg::Flag(f) if f == "timeout" => {
+                let seconds = args.value(&f)?.parse::<f64>();
+                timeout = match seconds.map(Duration::try_from_secs_f64) {
+                    Ok(Ok(timeout)) => timeout,
+                    _ => return Err(UsageError(String::from("Bad --timeout")).into()),
+                }
+            }
+            Arg::Flag(f) => return Err(unknown_flag(&f)),
+            Arg::Positional(arg) => return Err(UsageError(format!("Unexpected: {}", arg)).into()),
+        }
+    }
+    serve(&listen, workers, timeout)
+}
+
+#[cfg(not(feature = "serve"))]
+fn serve(_: &str, _: usize, _: Duration) -> CliResult {
+    Err("this build of nonograms cannot serve HTTP (feature `serve`)".into())
+}
+
+/// How many connections may wait for a worker, per worker, before
+/// the server answers others that it's busy.
+#[cfg(feature = "serve")]
+const QUEUE_PER_WORKER: usize = 4;
+
+#[cfg(feature = "serve")]
+fn serve(listen: &str, workers: usize, timeout: Duration) -> CliResult {
+    use std::sync::mpsc::{self, TrySendError};
+    use std::sync::{Arc, Mutex};
+
+    let listener = std::net::TcpListener::bind(listen)?;
+    eprintln!("Listening on http://{}/", listener.local_addr()?);
+    let (sender, receiver) = mpsc::sync_channel(workers * QUEUE_PER_WORKER);
+    let receiver = Arc::new(Mutex::new(receiver));
+    for _ in 0..workers {
+        let receiver = Arc::clone(&receiver);
+        std::thread::spawn(move || loop {
+            let Ok(stream) = receiver.lock().unwrap().recv() else {
+                return;
+            };
+            if let Err(e) = http::handle(stream, timeout) {
+                eprintln!("Error: {}", e);
+            }
+        });
+    }
+    for stream in listener.incoming() {
+        match stream.map(|stream| sender.try_send(stream)) {
+            Ok(Ok(())) => (),
+            Ok(Err(TrySendError::Full(stream))) => {
+                if let Err(e) = http::busy(stream) {
+                    eprintln!("Error: {}", e);
+                }
+            }
+            Ok(Err(TrySendError::Disconnected(_))) => return Err("every worker stopped".into()),
+            Err(e) => eprintln!("Error: {}", e),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "serve")]
+mod http {
+    use nonograms::analysis::{self, Uniqueness};
+    use nonograms::formats::{self, json::quote};
+    use nonograms::{CellState, Decided, Nonogram};
+
+    use std::io::{self, BufRead, BufReader, Read, Write};
+    use std::net::TcpStream;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{mpsc, Arc};
+    use std::time::{Duration, Instant};
+
+    /// The largest request body, in bytes.
+    const MAX_BODY: usize = 1 << 20;
+
+    /// Read a request from `stream`, and answer it, giving up on
+    /// puzzles that take longer than `timeout` to solve.
+    pub fn handle(stream: TcpStream, timeout: Duration) -> io::Result<()> {
+        stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        let mut words = request_line.split_whitespace();
+        let (method, path) = (words.next().unwrap_or(""), words.next().unwrap_or(""));
+
+        let mut length = 0;
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header)? == 0 || header.trim_end().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                if name.trim().eq_ignore_ascii_case("content-length") {
+                    length = value.trim().parse().unwrap_or(usize::MAX);
+                }
+            }
+        }
+
+        let (status, body) = match (method, path) {
+            ("OPTIONS", _) => ("204 No Content", String::new()),
+            (_, "/solve") if method != "POST" => ("405 Method Not Allowed", error("Use POST.")),
+            (_, "/solve") if length > MAX_BODY => ("413 Payload Too Large", error("Too large.")),
+            (_, "/solve") => {
+                let mut bytes = vec![0; length];
+                reader.read_exact(&mut bytes)?;
+                match String::from_utf8(bytes) {
+                    Ok(text) => match formats::parse(&text, None) {
+                        Ok(puzzle) => match solve(Nonogram::from(puzzle), timeout) {
+                            Some(body) => ("200 OK", body),
+                            None => ("503 Service Unavailable", error("Took too long.")),
+                        },
+                        Err(e) => ("400 Bad Request", error(&e.to_string())),
+                    },
+                    Err(_) => ("400 Bad Request", error("The puzzle is not UTF-8.")),
+                }
+            }
+            _ => ("404 Not Found", error("Not found.")),
+        };
+        respond(stream, status, &body)
+    }
+
+    /// Answer on `stream` that every worker is busy, without reading
+    /// the request.
+    pub fn busy(stream: TcpStream) -> io::Result<()> {
+        let body = error("Too busy, try again later.");
+        respond(stream, "503 Service Unavailable", &body)
+    }
+
+    fn respond(mut stream: TcpStream, status: &str, body: &str) -> io::Result<()> {
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\n\
+             Content-Type: application/json\r\n\
+             Content-Length: {}\r\n\
+             Access-Control-Allow-Origin: *\r\n\
+             Access-Control-Allow-Methods: POST, OPTIONS\r\n\
+             Access-Control-Allow-Headers: Content-Type\r\n\
+             Connection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        )?;
+        stream.flush()
+    }
+
+    fn error(message: &str) -> String {
+        format!("{{\"error\": {}}}\n", quote(message))
+    }
+
+    /// Solve `nono`, and describe the result as JSON, or None if that
+    /// takes longer than `timeout`.
+    fn solve(mut nono: Nonogram, timeout: Duration) -> Option<String> {
+        let start = Instant::now();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (done, finished) = mpsc::channel::<()>();
+        let found = std::thread::scope(|scope| {
+            let flag = &cancel;
+            scope.spawn(move || {
+                // This wakes up early if the solve drops `done` first.
+                if finished.recv_timeout(timeout) == Err(mpsc::RecvTimeoutError::Timeout) {
+                    flag.store(true, Ordering::Relaxed);
+                }
+            });
+            let found = analysis::uniqueness_cancellable(&nono, &cancel);
+            drop(done);
+            found
+        });
+        let (uniqueness, guesses) = found?;
+        let milliseconds = start.elapsed().as_millis();
+
+        let (outcome, uniqueness) = match uniqueness {
+            Uniqueness::NoSolution => ("contradiction", "none"),
+            Uniqueness::Unique(solution) => {
+                nono.cells = solution.cells().to_vec();
+                ("solved", "unique")
+            }
+            Uniqueness::Multiple(solution, _) => {
+                nono.cells = solution.cells().to_vec();
+                ("solved", "multiple")
+            }
+        };
+        let grid: String = nono.cells.iter().map(CellState::as_char).collect();
+        let decided = guesses.iter().filter(|d| d.is_some()).count();
+        let guessed = (guesses.iter())
+            .filter(|d| **d == Some(Decided::Guessed))
+            .count();
+
+        Some(format!(
+            "{{\n  \"width\": {},\n  \"height\": {},\n  \"outcome\": \"{}\",\n  \
+             \"grid\": {},\n  \"uniqueness\": \"{}\",\n  \
+             \"stats\": {{\"milliseconds\": {}, \"decided\": {}, \"guessed\": {}}}\n}}\n",
+            nono.width(),
+            nono.height(),
+            outcome,
+            quote(&grid),
+            uniqueness,
+            milliseconds,
+            decided,
+            guessed
+        ))
+    }
+}
@@ -0,0 +1,571 @@
+//! `nonograms [solve] [options] [files...]`: solve puzzles and print
+//! their solution.  Without files, or for `-`, read a puzzle from
+//! stdin.  Puzzles may be in any format of [nonograms::formats], and
+//! files may be directories or wildcard patterns, see
+//! [super::puzzle_files].
+//!
+//! `webpbn:ID` instead of a file downloads puzzle number `ID` of
+//! <https://webpbn.com>, with feature `fetch`.
+//!
+//! Options:
+//!
+//!  - `--format auto|non|json|webpbn|tatham|csv`: the format of the
+//!    puzzles.  The default, `auto`, goes by the extension of files,
+//!    or else by their contents.
+//!  - `--render text|ascii|printed|strike|braille|html|markdown|tikz|pbm|xpm|sixel|kitty`:
+//!    how to print solutions.  `strike` is `printed`, with the clues
+//!    whose runs are in place struck through, eg to see what's left
+//!    of puzzles that aren't solved.
+//!  - `--animate out.gif`: also write an animation of the solve.
+//!  - `--resume game.sav`: solve the puzzle of a save file, starting
+//!    from the saved marks, instead of reading puzzles.
+//!  - `--save game.sav`: write the result, solved or not, as a save
+//!    file.
+//!  - `--guesses`: also print how each cell was decided: `.` by
+//!    deduction, `G` by a guess, and `+` by deduction from guesses.
+//!  - `--emit-goal out.non`: write the puzzle again, with its
+//!    solution as its goal, see [nonograms::Puzzle::goal].  Puzzles in
+//!    the format of nonogram-db are written as they were, with their
+//!    `goal` line replaced, and others are written in that format.
+//!    This solves a single puzzle.
+//!  - `--dump-passes DIR`: write the grid after each propagation pass
+//!    to `DIR`, created if needed, as text and as SVG, to
+//!    `pass-0001.txt`, `pass-0001.svg` and so on, see
+//!    [nonograms::Solver::pass_snapshots].  This includes the passes of
+//!    probes and of guesses that were undone.  This solves a single
+//!    puzzle.
+//!  - `--dump-every N`: with `--dump-passes`, write every N-th pass
+//!    only.
+//!  - `--time`: also print how long each solve took, in wall-clock
+//!    time and in propagation passes, and whether it needed search.
+//!  - `--output text|json`: with `json`, print a JSON object per
+//!    puzzle, on a line of its own, instead of text: its `file`,
+//!    `width`, `height`, `outcome` and `grid` as with
+//!    `nonograms serve`, and with `--time`, `milliseconds`, `passes`
+//!    and `search`.
+//!  - `--race`: race the solver with each guess strategy on threads
+//!    of their own, and keep the first to finish, see
+//!    [nonograms::race].  `-v` tells which won.  This doesn't go with
+//!    `--animate`, `--guesses`, `--dump-passes` or `--resume`, and
+//!    `-vv` logs nothing more.
+//!  - `--threads N`: let search use N threads, see
+//!    [nonograms::SolverConfig::threads], 1 by default.
+//!  - `--seed N`: break ties between the cells the solver could guess
+//!    with this seed, see [nonograms::SolverConfig::seed], to try
+//!    other search orders.  The same seed always gives the same solve.
+//!  - `-q`, `--quiet`: print solutions only, without the file names
+//!    and dimensions.  Puzzles that aren't solved are reported on
+//!    stderr.
+//!  - `-v`, `--verbose`: also report each propagation pass and some
+//!    statistics on stderr.  `-vv` also logs every deduction, guess
+//!    and backtrack of the solver.
+
+use super::{puzzle_files, report_error, unknown_flag, Arg, Args, CliResult, UsageError};
+
+use nonograms::formats::{self, json::quote, non::Non, savefile::SaveFile, PuzzleFormat};
+use nonograms::parser::read_text;
+use nonograms::race;
+use nonograms::render::svg;
+use nonograms::{
+    render, CellState, Decided, Nonogram, PassSnapshot, PlayerGrid, Puzzle, SolveOutcome,
+    SolveStats, Solver, SolverCheckpoint, SolverConfig, SolverEvent,
+};
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Instant;
+
+#[derive(Default)]
+struct Options {
+    /// Write an animation of the solve here.
+    animate: Option<String>,
+    render: Render,
+    /// Write a save file of the result here.
+    save: Option<String>,
+    /// Write the puzzle with its solution as its goal here.
+    emit_goal: Option<String>,
+    /// Write the grids of the passes to this directory.
+    dump_passes: Option<String>,
+    /// Write the grid every this many passes.
+    dump_every: Option<usize>,
+    /// Print the guess map of the solve.
+    guesses: bool,
+    /// Read puzzles in this format, rather than guess it.
+    format: Option<&'static dyn PuzzleFormat>,
+    /// Print how long solves took.
+    time: bool,
+    /// Print JSON objects rather than text.
+    json: bool,
+    /// Race solvers with each guess strategy.
+    race: bool,
+    /// Break ties between guesses with this seed.
+    seed: Option<u64>,
+    /// How many threads search may use.
+    threads: Option<usize>,
+    verbosity: Verbosity,
+}
+
+/// How much to print besides solutions.
+#[derive(Clone, Copy, Default, PartialEq, PartialOrd)]
+enum Verbosity {
+    /// Solutions only.
+    Quiet,
+    /// Solutions, with a header of the file and the dimensions.
+    #[default]
+    Normal,
+    /// Also passes and statistics, on stderr.
+    Verbose,
+    /// Also every event of the solver, on stderr.
+    Debug,
+}
+
+/// How to print solutions.
+#[derive(Clone, Copy, Default)]
+enum Render {
+    #[default]
+    Text,
+    Ascii,
+    Printed,
+    Strike,
+    Braille,
+    Html,
+    Markdown,
+    Tikz,
+    Pbm,
+    Xpm,
+    Sixel,
+    Kitty,
+}
+
+impl Render {
+    fn from_name(name: &str) -> Result<Render, UsageError> {
+        match name {
+            "text" => Ok(Render::Text),
+            "ascii" => Ok(Render::Ascii),
+            "printed" => Ok(Render::Printed),
+            "strike" => Ok(Render::Strike),
+            "braille" => Ok(Render::Braille),
+            "html" => Ok(Render::Html),
+            "markdown" => Ok(Render::Markdown),
+            "tikz" => Ok(Render::Tikz),
+            "pbm" => Ok(Render::Pbm),
+            "xpm" => Ok(Render::Xpm),
+            "sixel" => Ok(Render::Sixel),
+            "kitty" => Ok(Render::Kitty),
+            _ => Err(UsageError(format!("Unknown renderer: {}", name))),
+        }
+    }
+
+    fn render(self, n: &Nonogram) -> String {
+        match self {
+            Render::Text => n.as_text(),
+            Render::Ascii => n.as_text_with(&render::RenderOptions::ascii()),
+            Render::Printed => n.as_text_with(&render::RenderOptions::printed()),
+            Render::Strike => n.as_text_with(&render::RenderOptions {
+                strike_clues: true,
+                ..render::RenderOptions::printed()
+            }),
+            Render::Braille => n.as_braille_text(),
+            Render::Html => render::html::render(n),
+            Render::Markdown => n.as_markdown(),
+            Render::Tikz => render::tikz::render(n, &render::tikz::TikzOptions::default()),
+            Render::Pbm => n.to_pbm(),
+            Render::Xpm => n.to_xpm(),
+            Render::Sixel => render::terminal::sixel(n, 8),
+            Render::Kitty => render::terminal::kitty(n, 8),
+        }
+    }
+}
+
+pub fn run(mut args: Args) -> CliResult {
+    let mut options = Options::default();
+    let mut files = vec![];
+    let mut resume = None;
+    while let Some(arg) = args.next_arg() {
+        match arg {
+            Arg::Flag(f) if f == "animate" => options.animate = Some(args.value(&f)?),
+            Arg::Flag(f) if f == "render" => options.render = Render::from_name(&args.value(&f)?)?,
+            Arg::Flag(f) if f == "resume" => resume = Some(args.value(&f)?),
+            Arg::Flag(f) if f == "save" => options.save = Some(args.value(&f)?),
+            Arg::Flag(f) if f == "emit-goal" => options.emit_goal = Some(args.value(&f)?),
+            Arg::Flag(f) if f == "dump-passes" => options.dump_passes = Some(args.value(&f)?),
+            Arg::Flag(f) if f == "dump-every" => {
+                let value = args.value(&f)?;
+                match value.parse::<usize>() {
+                    Ok(n) if n > 0 => options.dump_every = Some(n),
+                    _ => return Err(UsageError(format!("Bad number of passes: {}", value)).into()),
+                }
+            }
+            Arg::Flag(f) if f == "guesses" => options.guesses = true,
+            Arg::Flag(f) if f == "time" => options.time = true,
+            Arg::Flag(f) if f == "output" => {
+                options.json = match args.value(&f)?.as_str() {
+                    "text" => false,
+                    "json" => true,
+                    other => return Err(UsageError(format!("Unknown output: {}", other)).into()),
+                }
+            }
+            Arg::Flag(f) if f == "race" => options.race = true,
+            Arg::Flag(f) if f == "threads" => {
+                let value = args.value(&f)?;
+                match value.parse::<usize>() {
+                    Ok(n) if n > 0 => options.threads = Some(n),
+                    _ => return Err(UsageError(format!("Bad number of threads: {}", value)).into()),
+                }
+            }
+            Arg::Flag(f) if f == "seed" => {
+                let value = args.value(&f)?;
+                let n = value
+                    .parse()
+                    .map_err(|_| UsageError(format!("Bad seed: {}", value)))?;
+                options.seed = Some(n);
+            }
+            Arg::Flag(f) if f == "format" => {
+                options.format = match args.value(&f)?.as_str() {
+                    "auto" => None,
+                    name => Some(formats::by_name(name)?),
+                }
+            }
+            Arg::Flag(f) if f == "q" || f == "quiet" => options.verbosity = Verbosity::Quiet,
+            Arg::Flag(f) if f == "v" || f == "verbose" => {
+                options.verbosity = match options.verbosity {
+                    Verbosity::Quiet | Verbosity::Normal => Verbosity::Verbose,
+                    _ => Verbosity::Debug,
+                }
+            }
+            Arg::Flag(f) if f == "vv" => options.verbosity = Verbosity::Debug,
+            Arg::Flag(f) => return Err(unknown_flag(&f)),
+            Arg::Positional(file) => files.push(file),
+        }
+    }
+
+    let solo = options.animate.is_some() || options.guesses || options.dump_passes.is_some();
+    if options.race && (solo || resume.is_some()) {
+        let what = "--race doesn't go with --animate, --guesses, --dump-passes or --resume";
+        return Err(UsageError(String::from(what)).into());
+    }
+    if options.dump_every.is_some() && options.dump_passes.is_none() {
+        return Err(UsageError(String::from("--dump-every goes with --dump-passes")).into());
+    }
+    if let Some(path) = resume {
+        if !files.is_empty() {
+            return Err(UsageError(String::from("--resume reads no other puzzles")).into());
+        }
+        let save = SaveFile::read(&mut fs::File::open(&path)?)?;
+        let solved = solve(save.puzzle.clone(), Some(&save), &path, &options)?;
+        return emit_goal(None, save.puzzle.puzzle(), &solved, &options);
+    }
+    if files.is_empty() {
+        return read_and_solve(&mut io::stdin(), "-", &options);
+    }
+    let files = puzzle_files(files);
+    if options.emit_goal.is_some() && files.len() > 1 {
+        return Err(UsageError(String::from("--emit-goal solves a single puzzle")).into());
+    }
+    if options.dump_passes.is_some() && files.len() > 1 {
+        return Err(UsageError(String::from("--dump-passes solves a single puzzle")).into());
+    }
+    for fname in files {
+        if options.verbosity > Verbosity::Quiet && !options.json {
+            println!("File: {}", fname);
+        }
+        if fname == "-" {
+            if let Err(e) = read_and_solve(&mut io::stdin(), "-", &options) {
+                report_error(e.as_ref());
+            }
+            continue;
+        }
+        if let Some(id) = fname.strip_prefix("webpbn:") {
+            if let Err(e) = fetch_and_solve(id, &options) {
+                report_error(e.as_ref());
+            }
+            continue;
+        }
+        match fs::File::open(&fname) {
+            Ok(mut fd) => {
+                if let Err(e) = read_and_solve(&mut fd, &fname, &options) {
+                    report_error(e.as_ref());
+                }
+            }
+            Err(err) => eprintln!("Cannot read {}: {}", fname, err),
+        }
+    }
+    Ok(())
+}
+
+/// Read the puzzle of file `fname`, or stdin for `-`, from `r`, in
+/// the format of the options, or else in any format, going by the
+/// extension of `fname`, and solve it.
+fn read_and_solve(r: &mut impl io::Read, fname: &str, options: &Options) -> CliResult {
+    let text = read_text(r)?;
+    let path = Some(Path::new(fname)).filter(|_| fname != "-");
+    let format = match (options.format, path.and_then(formats::for_path)) {
+        (Some(format), _) | (None, Some(format)) => format,
+        (None, None) => formats::detect(&text)?,
+    };
+    let puzzle = format.parse(&text)?;
+    let solved = solve(Nonogram::from(puzzle.clone()), None, fname, options)?;
+    let original = Some(text.as_str()).filter(|_| format.name() == Non.name());
+    emit_goal(original, puzzle, &solved, options)
+}
+
+/// Download puzzle `id` of webpbn.com, and solve it.
+#[cfg(feature = "fetch")]
+fn fetch_and_solve(id: &str, options: &Options) -> CliResult {
+    let id = id
+        .parse()
+        .map_err(|_| UsageError(format!("Bad webpbn puzzle number: {}", id)))?;
+    let fname = format!("webpbn:{}", id);
+    let solved = solve(
+        nonograms::collection::fetch_webpbn(id)?,
+        None,
+        &fname,
+        options,
+    )?;
+    emit_goal(None, solved.puzzle(), &solved, options)
+}
+
+#[cfg(not(feature = "fetch"))]
+fn fetch_and_solve(_: &str, _: &Options) -> CliResult {
+    Err("this build of nonograms cannot download puzzles (feature `fetch`)".into())
+}
+
+/// Solve `n`, the puzzle of file `fname`, from the marks of `start`
+/// if given, and return it solved as far as it could be.
+fn solve(
+    mut n: Nonogram,
+    start: Option<&SaveFile>,
+    fname: &str,
+    options: &Options,
+) -> Result<Nonogram, Box<dyn Error>> {
+    let text = !options.json;
+    if options.verbosity > Verbosity::Quiet && text {
+        println!("Dimensions (w×h) = {}×{}", n.width(), n.height());
+    }
+
+    let config = SolverConfig {
+        trace: options.animate.is_some(),
+        seed: options.seed,
+        threads: options.threads.unwrap_or(1),
+        snapshot_every: match options.dump_passes {
+            Some(_) => options.dump_every.unwrap_or(1),
+            None => 0,
+        },
+        ..SolverConfig::default()
+    };
+    let start_time = Instant::now();
+    let (outcome, trace, guesses, passes, guessed) = match options.race {
+        true => {
+            let configs: Vec<SolverConfig> = (race::racers().into_iter())
+                .map(|racer| SolverConfig {
+                    seed: options.seed,
+                    threads: options.threads.unwrap_or(1),
+                    ..racer
+                })
+                .collect();
+            let race = race::race(&n.puzzle(), &configs);
+            n.cells = race.grid.cells().to_vec();
+            if options.verbosity >= Verbosity::Verbose {
+                let strategy = configs[race.winner].guess_strategy;
+                eprintln!("Won by the solver with {:?}", strategy);
+            }
+            (race.outcome, None, None, race.passes, race.guesses)
+        }
+        false => {
+            let mut solver = Solver::with_config(&mut n, config);
+            if options.verbosity >= Verbosity::Verbose {
+                let verbosity = options.verbosity;
+                solver.set_logger(move |event| match (event, verbosity) {
+                    (SolverEvent::Pass(pass), Verbosity::Verbose | Verbosity::Debug) => {
+                        eprintln!("Pass {}", pass)
+                    }
+                    (_, Verbosity::Debug) => eprintln!("  {}", event),
+                    _ => (),
+                });
+            }
+            let outcome = match start {
+                Some(save) => {
+                    solver.restore(SolverCheckpoint {
+                        width: save.player.width(),
+                        height: save.player.height(),
+                        cells: save.player.to_cells(false),
+                    })?;
+                    solver.resume()
+                }
+                None => solver.solve(),
+            };
+            let trace = solver.trace().cloned();
+            let snapshots = solver.pass_snapshots().to_vec();
+            let guesses = solver.guess_map();
+            let SolveStats {
+                passes,
+                guesses: guessed,
+            } = solver.stats();
+            if let Some(dir) = &options.dump_passes {
+                dump_passes(&n, &snapshots, Path::new(dir))?;
+            }
+            (outcome, trace, Some(guesses), passes, guessed)
+        }
+    };
+    let milliseconds = start_time.elapsed().as_millis();
+
+    if options.verbosity >= Verbosity::Verbose {
+        let decided = (n.cells.iter())
+            .filter(|c| **c != CellState::Undecided)
+            .count();
+        let mut line = format!(
+            "{} ms, {} passes, {} of {} cells decided",
+            milliseconds,
+            passes,
+            decided,
+            n.cells.len()
+        );
+        if let Some(guesses) = &guesses {
+            let guessed = (guesses.iter())
+                .filter(|d| **d == Some(Decided::Guessed))
+                .count();
+            line.push_str(&format!(", {} guessed", guessed));
+        }
+        eprintln!("{}", line);
+    }
+    let message = match outcome {
+        SolveOutcome::Solved => None,
+        SolveOutcome::Stalled => Some("Could not solve completely."),
+        SolveOutcome::Contradiction => Some("This puzzle has no solution."),
+        SolveOutcome::Cancelled => Some("Solving was interrupted."),
+    };
+    match message {
+        _ if !text => (),
+        Some(message) if options.verbosity == Verbosity::Quiet => eprintln!("{}", message),
+        Some(message) => println!("{}", message),
+        None => (),
+    }
+    if text {
+        println!("{}", options.render.render(&n));
+    }
+    if options.time && text {
+        let search = match guessed {
+            0 => String::from("no search"),
+            n => format!("search, {} guesses", n),
+        };
+        println!("Time: {} ms, {} passes, {}", milliseconds, passes, search);
+    }
+    if !text {
+        let outcome = match outcome {
+            SolveOutcome::Solved => "solved",
+            SolveOutcome::Stalled => "stalled",
+            SolveOutcome::Contradiction => "contradiction",
+            SolveOutcome::Cancelled => "cancelled",
+        };
+        let grid: String = n.cells.iter().map(CellState::as_char).collect();
+        let time = match options.time {
+            true => format!(
+                ", \"milliseconds\": {}, \"passes\": {}, \"search\": {}",
+                milliseconds,
+                passes,
+                guessed > 0
+            ),
+            false => String::new(),
+        };
+        println!(
+            "{{\"file\": {}, \"width\": {}, \"height\": {}, \"outcome\": \"{}\", \"grid\": {}{}}}",
+            quote(fname),
+            n.width(),
+            n.height(),
+            outcome,
+            quote(&grid),
+            time
+        );
+    }
+    if let (true, Some(guesses)) = (options.guesses && text, guesses) {
+        for row in guesses.chunks(n.width().max(1)) {
+            let row: String = row
+                .iter()
+                .map(|cell| match cell {
+                    None => '?',
+                    Some(Decided::Deduced) => '.',
+                    Some(Decided::Guessed) => 'G',
+                    Some(Decided::AfterGuess) => '+',
+                })
+                .collect();
+            println!("{}", row);
+        }
+    }
+
+    if let Some(path) = &options.save {
+        let mut save = SaveFile::new(n.clone(), PlayerGrid::from_grid(&n));
+        if let Some(start) = start {
+            save.moves = start.moves.clone();
+        }
+        fs::write(path, save.to_string())?;
+    }
+    if let (Some(path), Some(trace)) = (&options.animate, trace) {
+        write_animation(&n, &trace, path)?;
+    }
+    Ok(n)
+}
+
+/// Write each of `snapshots` of the solve of `n` into `dir`, as text
+/// and as SVG.
+fn dump_passes(n: &Nonogram, snapshots: &[PassSnapshot], dir: &Path) -> CliResult {
+    fs::create_dir_all(dir)?;
+    let mut nono = n.clone();
+    for snapshot in snapshots {
+        nono.cells = snapshot.grid.cells().to_vec();
+        let name = format!("pass-{:04}", snapshot.pass);
+        fs::write(dir.join(format!("{}.txt", name)), nono.as_text())?;
+        fs::write(dir.join(format!("{}.svg", name)), svg::render(&nono))?;
+    }
+    Ok(())
+}
+
+/// Write `puzzle` with the grid of `solved` as its goal, if the
+/// options say so: as the `original` text of the puzzle with its goal
+/// replaced, if given, or else in the format of [Non].
+fn emit_goal(
+    original: Option<&str>,
+    mut puzzle: Puzzle,
+    solved: &Nonogram,
+    options: &Options,
+) -> CliResult {
+    let Some(path) = &options.emit_goal else {
+        return Ok(());
+    };
+    let goal = solved.grid();
+    if !goal.is_complete() {
+        return Err("the puzzle isn't solved, so it has no goal to write".into());
+    }
+    let text = match original {
+        Some(text) => {
+            let mut ret: String = (text.lines())
+                .filter(|line| line.split_whitespace().next() != Some("goal"))
+                .flat_map(|line| [line, "\n"])
+                .collect();
+            ret.truncate(ret.trim_end().len());
+            let cells: String = (goal.cells().iter())
+                .map(|c| if *c == CellState::Filled { '1' } else { '0' })
+                .collect();
+            ret.push_str(&format!("\n\ngoal \"{}\"\n", cells));
+            ret
+        }
+        None => {
+            puzzle.goal = Some(goal);
+            Non.write(&puzzle)
+        }
+    };
+    fs::write(path, text)?;
+    Ok(())
+}
+
+#[cfg(feature = "animate")]
+fn write_animation(n: &Nonogram, trace: &nonograms::SolveTrace, path: &str) -> CliResult {
+    use nonograms::render::gif;
+    fs::write(path, gif::animate(n, trace, &gif::GifOptions::default())?)?;
+    Ok(())
+}
+
+#[cfg(not(feature = "animate"))]
+fn write_animation(_: &Nonogram, _: &nonograms::SolveTrace, _: &str) -> CliResult {
+    Err("this build of nonograms has no animation support (feature `animate`)".into())
+}
@@ -0,0 +1,83 @@
+//! `nonograms verify FILES...`: check the solver against a brute-force
+//! search, which shares no code with it, and report any puzzle on
+//! which they disagree about the solutions.
+//!
+//! The brute force is [nonograms::solver::brute_force], which only
+//! runs on small puzzles; larger ones are skipped.  This exits with
+//! an error if some puzzle disagrees.
+
+use super::{puzzle_files, report_error, unknown_flag, Arg, Args, CliResult, UsageError};
+
+use nonograms::analysis::{self, Uniqueness};
+use nonograms::solver::{brute_force, is_solution};
+use nonograms::{formats, Nonogram};
+use std::path::Path;
+
+pub fn run(mut args: Args) -> CliResult {
+    let mut files = vec![];
+    while let Some(arg) = args.next_arg() {
+        match arg {
+            Arg::Flag(f) => return Err(unknown_flag(&f)),
+            Arg::Positional(file) => files.push(file),
+        }
+    }
+    let files = puzzle_files(files);
+    if files.is_empty() {
+        return Err(UsageError(String::from("verify needs puzzle files")).into());
+    }
+
+    let mut disagreements = 0;
+    for fname in files {
+        let puzzle = match formats::read_file(Path::new(&fname)) {
+            Ok(puzzle) => puzzle,
+            Err(e) => {
+                eprint!("{}: ", fname);
+                report_error(&e);
+                continue;
+            }
+        };
+        let Some(brute) = brute_force(&puzzle, 2) else {
+            println!("{}: skipped, too large to brute-force", fname);
+            continue;
+        };
+
+        let solver = analysis::uniqueness(&Nonogram::from(puzzle.clone()));
+        let problem = match (&solver, &brute[..]) {
+            (Uniqueness::NoSolution, []) => None,
+            (Uniqueness::Unique(a), [b]) if a == b => None,
+            (Uniqueness::Unique(_), [_]) => Some(String::from("the solutions differ")),
+            (Uniqueness::Multiple(a, b), [_, _]) => [a, b]
+                .iter()
+                .find(|grid| !is_solution(&puzzle, grid))
+                .map(|_| String::from("a solution of the solver is wrong")),
+            _ => Some(format!(
+                "the solver finds {}, the brute force {}",
+                describe(&solver),
+                match brute.len() {
+                    0 => "none",
+                    1 => "one",
+                    _ => "several",
+                }
+            )),
+        };
+        match problem {
+            None => println!("{}: ok, {}", fname, describe(&solver)),
+            Some(problem) => {
+                disagreements += 1;
+                println!("{}: DISAGREEMENT: {}", fname, problem);
+            }
+        }
+    }
+    match disagreements {
+        0 => Ok(()),
+        n => Err(format!("{} puzzles disagree", n).into()),
+    }
+}
+
+fn describe(uniqueness: &Uniqueness) -> &'static str {
+    match uniqueness {
+        Uniqueness::NoSolution => "no solution",
+        Uniqueness::Unique(_) => "unique solution",
+        Uniqueness::Multiple(_, _) => "several solutions",
+    }
+}
@@ -0,0 +1,84 @@
+//! `nonograms watch [--interval MS] FILE`: check a puzzle again each
+//! time its file changes, to author puzzles in a text editor.  This
+//! runs until interrupted.
+//!
+//! Each check prints a line: the size of the puzzle, whether its
+//! solution is unique, and how hard it is, see
+//! [nonograms::analysis::Class].  Puzzles with a goal that isn't their
+//! unique solution say so, and mistakes in the file are reported as
+//! errors.
+//!
+//! Options:
+//!
+//!  - `--interval MS`: how often to look at the file, in milliseconds,
+//!    500 by default.
+
+use super::{report_error, unknown_flag, Arg, Args, CliResult, UsageError};
+
+use nonograms::analysis::{self, Class, Uniqueness};
+use nonograms::{formats, Nonogram, Puzzle};
+use std::fs;
+use std::path::Path;
+use std::thread::sleep;
+use std::time::{Duration, SystemTime};
+
+pub fn run(mut args: Args) -> CliResult {
+    let mut interval = 500;
+    let mut files = vec![];
+    while let Some(arg) = args.next_arg() {
+        match arg {
+            Arg::Flag(f) if f == "interval" => {
+                let value = args.value(&f)?;
+                interval = value
+                    .parse()
+                    .map_err(|_| UsageError(format!("Bad interval: {}", value)))?;
+            }
+            Arg::Flag(f) => return Err(unknown_flag(&f)),
+            Arg::Positional(file) => files.push(file),
+        }
+    }
+    let [fname] = &files[..] else {
+        return Err(UsageError(String::from("watch needs a single puzzle file")).into());
+    };
+
+    let path = Path::new(fname);
+    // The modification time and size of the file when last checked,
+    // or None if it couldn't be read.
+    let mut last: Option<Option<(SystemTime, u64)>> = None;
+    loop {
+        let current = fs::metadata(path)
+            .and_then(|m| Ok((m.modified()?, m.len())))
+            .ok();
+        if last != Some(current) {
+            last = Some(current);
+            match formats::read_file(path) {
+                Ok(puzzle) => println!("{}: {}", fname, describe(&puzzle)),
+                Err(e) => {
+                    eprint!("{}: ", fname);
+                    report_error(&e);
+                }
+            }
+        }
+        sleep(Duration::from_millis(interval));
+    }
+}
+
+/// Check `puzzle`, in a line.
+fn describe(puzzle: &Puzzle) -> String {
+    let nono = Nonogram::from(puzzle.clone());
+    let size = format!("{}×{}", nono.width(), nono.height());
+    let class = match analysis::classification(&nono) {
+        Class::Line => "line logic",
+        Class::Probing => "needs probing",
+        Class::Search => "needs guesses",
+        Class::Contradiction => return format!("{}, no solution", size),
+    };
+    match (analysis::uniqueness(&nono), &puzzle.goal) {
+        (Uniqueness::Unique(solution), Some(goal)) if solution != *goal => {
+            format!("{}, unique solution but not the goal, {}", size, class)
+        }
+        (Uniqueness::Unique(_), _) => format!("{}, unique solution, {}", size, class),
+        (Uniqueness::Multiple(_, _), _) => format!("{}, several solutions, {}", size, class),
+        (Uniqueness::NoSolution, _) => format!("{}, no solution", size),
+    }
+}
@@ -0,0 +1,276 @@
+//! Collections of puzzle files, such as a checkout of
+//! <https://github.com/mikix/nonogram-db/>.
+//!
+//! [Collection::load] reads every puzzle under a directory, in any
+//! format of [crate::formats], and [Collection::query] finds those
+//! matching a [Query].  With feature `fetch`, [fetch_webpbn]
+//! downloads puzzles from <https://webpbn.com>.
+
+use crate::formats::{self, FormatError};
+use crate::Puzzle;
+
+pub use crate::analysis::Difficulty;
+#[cfg(feature = "fetch")]
+pub use fetch::{fetch_webpbn, FetchError};
+
+use std::cell::OnceCell;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// * Collections
+
+/// A puzzle of a collection, and where it comes from.
+#[derive(Debug)]
+pub struct Entry {
+    pub path: PathBuf,
+    pub puzzle: Puzzle,
+    difficulty: OnceCell<Option<Difficulty>>,
+}
+
+impl Entry {
+    pub fn new(path: PathBuf, puzzle: Puzzle) -> Entry {
+        Entry {
+            path,
+            puzzle,
+            difficulty: OnceCell::new(),
+        }
+    }
+
+    /// See [Difficulty::of].  This solves the puzzle the first time.
+    pub fn difficulty(&self) -> Option<Difficulty> {
+        *self.difficulty.get_or_init(|| Difficulty::of(&self.puzzle))
+    }
+}
+
+/// The puzzles read from a directory tree.
+#[derive(Debug, Default)]
+pub struct Collection {
+    entries: Vec<Entry>,
+    /// Files that looked like puzzles but couldn't be read.
+    pub errors: Vec<(PathBuf, FormatError)>,
+    /// Indices of entries, by lowercase title and author.
+    by_title: BTreeMap<String, Vec<usize>>,
+    by_author: BTreeMap<String, Vec<usize>>,
+}
+
+impl Collection {
+    pub fn new() -> Collection {
+        Collection::default()
+    }
+
+    /// Read every puzzle file under `dir`, see [puzzle_files].  Files
+    /// that can't be read end up in [Collection::errors].
+    pub fn load(dir: &Path) -> io::Result<Collection> {
+        let mut ret = Collection::new();
+        for path in puzzle_files(dir)? {
+            match formats::read_file(&path) {
+                Ok(puzzle) => ret.push(Entry::new(path, puzzle)),
+                Err(e) => ret.errors.push((path, e)),
+            }
+        }
+        Ok(ret)
+    }
+
+    pub fn push(&mut self, entry: Entry) {
+        let i = self.entries.len();
+        if let Some(title) = &entry.puzzle.title {
+            self.by_title
+                .entry(title.to_lowercase())
+                .or_default()
+                .push(i);
+        }
+        if let Some(author) = &entry.puzzle.author {
+            self.by_author
+                .entry(author.to_lowercase())
+                .or_default()
+                .push(i);
+        }
+        self.entries.push(entry);
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The puzzles titled `title`, ignoring case.
+    pub fn by_title(&self, title: &str) -> impl Iterator<Item = &Entry> {
+        self.indexed(&self.by_title, title)
+    }
+
+    /// The puzzles by `author`, ignoring case.
+    pub fn by_author(&self, author: &str) -> impl Iterator<Item = &Entry> {
+        self.indexed(&self.by_author, author)
+    }
+
+    fn indexed<'a>(
+        &'a self,
+        index: &'a BTreeMap<String, Vec<usize>>,
+        key: &str,
+    ) -> impl Iterator<Item = &'a Entry> {
+        let indices = index
+            .get(&key.to_lowercase())
+            .map_or(&[][..], Vec::as_slice);
+        indices.iter().map(|i| &self.entries[*i])
+    }
+
+    /// The puzzles matching `query`, in order.
+    pub fn query<'a>(&'a self, query: &'a Query) -> impl Iterator<Item = &'a Entry> {
+        self.entries.iter().filter(|e| query.matches(e))
+    }
+}
+
+/// Every file under `dir` in a puzzle format, by its extension, in
+/// order.
+pub fn puzzle_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut ret = files_under(dir)?;
+    ret.retain(|path| formats::for_path(path).is_some());
+    Ok(ret)
+}
+
+/// Every file under `dir`, in order, whatever its format.
+pub fn files_under(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut ret = vec![];
+    let mut entries = fs::read_dir(dir)?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<io::Result<Vec<PathBuf>>>()?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            ret.extend(files_under(&path)?);
+        } else {
+            ret.push(path);
+        }
+    }
+    Ok(ret)
+}
+
+// * Queries
+
+/// What to look for in a [Collection].  Every criterion that is set
+/// must match.
+#[derive(Clone, Debug, Default)]
+pub struct Query {
+    /// Part of the title, ignoring case.
+    pub title: Option<String>,
+    /// Part of the name of the author, ignoring case.
+    pub author: Option<String>,
+    /// The smallest width and height.
+    pub min_size: Option<(usize, usize)>,
+    /// The largest width and height.
+    pub max_size: Option<(usize, usize)>,
+    /// This solves the puzzles that match every other criterion.
+    pub difficulty: Option<Difficulty>,
+}
+
+impl Query {
+    pub fn matches(&self, entry: &Entry) -> bool {
+        let puzzle = &entry.puzzle;
+        let contains = |field: &Option<String>, part: &Option<String>| match part {
+            None => true,
+            Some(part) => field
+                .as_ref()
+                .is_some_and(|f| f.to_lowercase().contains(&part.to_lowercase())),
+        };
+        let (w, h) = (puzzle.width(), puzzle.height());
+        contains(&puzzle.title, &self.title)
+            && contains(&puzzle.author, &self.author)
+            && self
+                .min_size
+                .is_none_or(|(min_w, min_h)| w >= min_w && h >= min_h)
+            && self
+                .max_size
+                .is_none_or(|(max_w, max_h)| w <= max_w && h <= max_h)
+            && self
+                .difficulty
+                .is_none_or(|d| entry.difficulty() == Some(d))
+    }
+}
+
+// * Downloads
+
+#[cfg(feature = "fetch")]
+mod fetch {
+    use crate::formats::webpbn::Webpbn;
+    use crate::formats::{FormatError, PuzzleFormat};
+    use crate::Nonogram;
+
+    use std::error;
+    use std::fmt;
+    use std::io::{self, Read, Write};
+    use std::net::TcpStream;
+
+    const HOST: &str = "webpbn.com";
+
+    #[derive(Debug)]
+    pub enum FetchError {
+        Io(io::Error),
+        /// The server answered with this status line, rather than
+        /// the puzzle.
+        Http(String),
+        /// The puzzle couldn't be read.
+        Format(FormatError),
+    }
+
+    impl fmt::Display for FetchError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                FetchError::Io(_) => f.write_str("Cannot download the puzzle."),
+                FetchError::Http(status) => write!(f, "Download failed: {}", status),
+                FetchError::Format(_) => f.write_str("Cannot read the downloaded puzzle."),
+            }
+        }
+    }
+
+    impl error::Error for FetchError {
+        fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+            match self {
+                FetchError::Io(e) => Some(e),
+                FetchError::Format(e) => Some(e),
+                FetchError::Http(_) => None,
+            }
+        }
+    }
+
+    impl From<io::Error> for FetchError {
+        fn from(value: io::Error) -> Self {
+            FetchError::Io(value)
+        }
+    }
+
+    impl From<FormatError> for FetchError {
+        fn from(value: FormatError) -> Self {
+            FetchError::Format(value)
+        }
+    }
+
+    /// Download puzzle number `id` of webpbn.com.  This speaks plain
+    /// HTTP, since the standard library has no TLS.
+    pub fn fetch_webpbn(id: u32) -> Result<Nonogram, FetchError> {
+        let mut stream = TcpStream::connect((HOST, 80))?;
+        write!(
+            stream,
+            "GET /XMLpuz.cgi?id={} HTTP/1.0\r\nHost: {}\r\nUser-Agent: nonograms\r\n\r\n",
+            id, HOST
+        )?;
+        let mut response = vec![];
+        stream.read_to_end(&mut response)?;
+        let response = String::from_utf8_lossy(&response);
+
+        let (head, body) = response.split_once("\r\n\r\n").unwrap_or((&response, ""));
+        let status = head.lines().next().unwrap_or("");
+        if status.split_whitespace().nth(1) != Some("200") {
+            return Err(FetchError::Http(status.to_string()));
+        }
+        Ok(Nonogram::from(Webpbn.parse(body)?))
+    }
+}
@@ -0,0 +1,410 @@
+//! The clue of a single row or column.
+
+use crate::writer::{self, ClueStyle};
+use crate::{CellState, LineStatus};
+
+use alloc::{
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::error;
+use core::fmt;
+use core::ops::Deref;
+use core::str::FromStr;
+
+// * Constraints
+
+/// The lengths of the runs of filled cells of a line, in order.
+///
+/// Every run has at least one cell.  A line with no filled cells has
+/// no runs, and its clue is written `0`: [Constraint::new] accepts
+/// `[0]` for it, but no other zero.
+///
+/// Some lengths may be unknown, written `?`, eg `3,?,1`: the run is
+/// there, but any length fits it.  The whole clue of a line may also
+/// be unknown, written `?` alone, as in puzzles with blotted clues:
+/// any line fits it.  So a clue of a single run of unknown length
+/// reads back as an unknown clue.
+///
+/// On toroidal puzzles, the last run may wrap around from the end of
+/// the line to its start, see [Constraint::wraps].
+///
+/// This dereferences to the slice of run lengths, where unknown
+/// lengths are 0, and which is empty for an unknown clue.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Constraint {
+    runs: Vec<usize>,
+    unknown: bool,
+    wraps: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConstraintError {
+    /// A run of no cells, other than the `0` of an empty line.
+    ZeroLengthRun,
+    /// This isn't a number.
+    NotANumber(String),
+}
+
+impl fmt::Display for ConstraintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConstraintError::ZeroLengthRun => f.write_str("Runs can't be empty."),
+            ConstraintError::NotANumber(s) => write!(f, "Not a run length: {:?}", s),
+        }
+    }
+}
+
+impl error::Error for ConstraintError {}
+
+impl Constraint {
+    /// The clue with runs of lengths `runs`, in order.
+    pub fn new(runs: Vec<usize>) -> Result<Constraint, ConstraintError> {
+        Constraint::with_unknowns(runs.into_iter().map(Some).collect())
+    }
+
+    /// The clue with runs of lengths `lengths`, in order, None for
+    /// runs of unknown length.
+    pub fn with_unknowns(lengths: Vec<Option<usize>>) -> Result<Constraint, ConstraintError> {
+        if lengths == [Some(0)] {
+            Ok(Constraint::empty())
+        } else if lengths.contains(&Some(0)) {
+            Err(ConstraintError::ZeroLengthRun)
+        } else {
+            Ok(Constraint {
+                runs: lengths.into_iter().map(|n| n.unwrap_or(0)).collect(),
+                ..Constraint::default()
+            })
+        }
+    }
+
+    /// The clue of a line without filled cells.
+    pub fn empty() -> Constraint {
+        Constraint::default()
+    }
+
+    /// The clue of a line whose clue is hidden.
+    pub fn unknown() -> Constraint {
+        Constraint {
+            unknown: true,
+            ..Constraint::default()
+        }
+    }
+
+    /// The clue of a line of decided cells: the runs of its filled
+    /// cells.
+    pub fn of_line(cells: &[CellState]) -> Constraint {
+        let runs = cells
+            .split(|c| *c != CellState::Filled)
+            .map(<[CellState]>::len)
+            .filter(|len| *len > 0)
+            .collect();
+        Constraint::new(runs).unwrap()
+    }
+
+    pub fn is_unknown(&self) -> bool {
+        self.unknown
+    }
+
+    /// Whether the runs may wrap around from the end of the line to
+    /// its start.  The run that wraps is then the last one: runs are
+    /// read from the first one that starts in the line.
+    pub fn wraps(&self) -> bool {
+        self.wraps
+    }
+
+    pub fn set_wraps(&mut self, wraps: bool) {
+        self.wraps = wraps;
+    }
+
+    /// The lengths of the runs, None for those that are unknown.
+    pub fn lengths(&self) -> impl Iterator<Item = Option<usize>> + '_ {
+        self.runs.iter().map(|n| Some(*n).filter(|n| *n > 0))
+    }
+
+    /// Whether the length of some run is unknown.
+    pub fn has_unknown_lengths(&self) -> bool {
+        self.runs.contains(&0)
+    }
+
+    /// The same clue, with its runs in reverse order: the clue of the
+    /// line mirrored, unless it wraps.
+    pub fn reversed(&self) -> Constraint {
+        Constraint {
+            runs: self.runs.iter().rev().copied().collect(),
+            unknown: self.unknown,
+            wraps: self.wraps,
+        }
+    }
+
+    pub fn runs(&self) -> &[usize] {
+        &self.runs
+    }
+
+    pub fn into_runs(self) -> Vec<usize> {
+        self.runs
+    }
+
+    /// How many cells are filled, at least: a run of unknown length
+    /// fills one.
+    pub fn sum(&self) -> usize {
+        self.runs.iter().map(|n| (*n).max(1)).sum()
+    }
+
+    /// How many runs there are.
+    pub fn block_count(&self) -> usize {
+        self.runs.len()
+    }
+
+    /// The length of the shortest line this fits in: the runs, with a
+    /// single empty cell between two runs.
+    pub fn min_length(&self) -> usize {
+        self.sum() + self.block_count().saturating_sub(1)
+    }
+
+    /// The numbers of the clue as written: `?` for an unknown clue or
+    /// length, and none for an empty line.
+    pub fn numbers(&self) -> Vec<String> {
+        let style = ClueStyle {
+            empty: "",
+            ..ClueStyle::compact()
+        };
+        writer::numbers(self, &style)
+    }
+
+    /// Whether this fits in a line of `len` cells.  An unknown clue
+    /// fits in any line.
+    pub fn fits(&self, len: usize) -> bool {
+        self.min_length() <= len
+    }
+
+    /// Whether `line` fits this clue so far, see [LineStatus].
+    ///
+    /// A decided line is satisfied if it fits the clue, and violated
+    /// otherwise.  In other lines, the runs of filled cells before
+    /// the first undecided cell, and after the last one, must be the
+    /// first and last runs of the clue, where the run touching the
+    /// undecided cell may still grow: it only must not be longer than
+    /// its run.  Lines whose clue is unknown or wraps are incomplete
+    /// until decided.
+    pub fn line_status(&self, line: &[CellState]) -> LineStatus {
+        if !line.contains(&CellState::Undecided) {
+            return match crate::line::solve_line(self, line) {
+                Ok(_) => LineStatus::Satisfied,
+                Err(_) => LineStatus::Violated,
+            };
+        }
+        if self.unknown || self.wraps {
+            return LineStatus::Incomplete;
+        }
+        let filled = line.iter().filter(|c| **c == CellState::Filled).count();
+        let has_unknowns = self.runs.contains(&0);
+        if (!has_unknowns && filled > self.sum())
+            || !prefix_fits(self.runs.iter().copied(), line.iter().copied())
+            || !prefix_fits(self.runs.iter().rev().copied(), line.iter().rev().copied())
+        {
+            LineStatus::Violated
+        } else {
+            LineStatus::Incomplete
+        }
+    }
+
+    /// Which runs are in place in `line`, eg to cross out clues as a
+    /// player fills the grid, with one entry per run, or a single one
+    /// for an empty or unknown clue.
+    ///
+    /// Every run is in place in a satisfied line, and none in a
+    /// violated one, see [Constraint::line_status].  In other lines,
+    /// runs are matched from each end, in order, to the runs of
+    /// filled cells bounded by empty cells or the edge, up to the
+    /// first undecided cell.  This doesn't look inside lines whose
+    /// clue is unknown or wraps.
+    pub fn satisfied_runs(&self, line: &[CellState]) -> Vec<bool> {
+        let count = self.runs.len().max(1);
+        match self.line_status(line) {
+            LineStatus::Satisfied => return vec![true; count],
+            LineStatus::Violated => return vec![false; count],
+            LineStatus::Incomplete => (),
+        }
+        let mut ret = vec![false; count];
+        if self.unknown || self.wraps || self.runs.is_empty() {
+            return ret;
+        }
+
+        let matches = |run: usize, len: usize| run == 0 || run == len;
+        let mut start = 0;
+        for len in prefix_runs(line.iter().copied()).0 {
+            match self.runs.get(start) {
+                Some(&run) if matches(run, len) => start += 1,
+                _ => break,
+            }
+        }
+        let mut end = self.runs.len();
+        for len in prefix_runs(line.iter().rev().copied()).0 {
+            match self.runs.get(end.wrapping_sub(1)) {
+                Some(&run) if end > start && matches(run, len) => end -= 1,
+                _ => break,
+            }
+        }
+        ret[..start].fill(true);
+        ret[end..].fill(true);
+        ret
+    }
+}
+
+/// Whether the runs of filled cells at the start of `cells`, as from
+/// [prefix_runs], can begin a line of `runs`.
+fn prefix_fits(
+    mut runs: impl Iterator<Item = usize>,
+    cells: impl Iterator<Item = CellState>,
+) -> bool {
+    let (closed, open) = prefix_runs(cells);
+    for len in closed {
+        match runs.next() {
+            Some(run) if run == 0 || run == len => (),
+            _ => return false,
+        }
+    }
+    match (open, runs.next()) {
+        (0, _) => true,
+        (open, Some(run)) => run == 0 || open <= run,
+        (_, None) => false,
+    }
+}
+
+/// The runs of filled cells from the start of `cells` up to the first
+/// undecided cell: those bounded by an empty cell or the end, and the
+/// length of the run touching the undecided cell, 0 if there is none.
+fn prefix_runs(cells: impl Iterator<Item = CellState>) -> (Vec<usize>, usize) {
+    let mut closed = vec![];
+    let mut run = 0;
+    for cell in cells {
+        match cell {
+            CellState::Filled => run += 1,
+            CellState::Empty if run > 0 => {
+                closed.push(run);
+                run = 0;
+            }
+            CellState::Empty => (),
+            CellState::Undecided => return (closed, run),
+        }
+    }
+    if run > 0 {
+        closed.push(run);
+    }
+    (closed, 0)
+}
+
+impl Deref for Constraint {
+    type Target = [usize];
+
+    fn deref(&self) -> &[usize] {
+        &self.runs
+    }
+}
+
+impl TryFrom<Vec<usize>> for Constraint {
+    type Error = ConstraintError;
+
+    fn try_from(runs: Vec<usize>) -> Result<Self, Self::Error> {
+        Constraint::new(runs)
+    }
+}
+
+/// Parse comma-separated run lengths, eg `3,1,2` or `3,?,2`, `0` for
+/// an empty line, or `?` for an unknown clue.  Spaces around numbers
+/// are ignored.
+impl FromStr for Constraint {
+    type Err = ConstraintError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim() == "?" {
+            return Ok(Constraint::unknown());
+        }
+        let lengths = s
+            .split(',')
+            .map(|n| parse_length(n.trim()))
+            .collect::<Result<Vec<Option<usize>>, _>>()?;
+        Constraint::with_unknowns(lengths)
+    }
+}
+
+/// Parse the length of a single run, `?` being unknown.
+pub(crate) fn parse_length(s: &str) -> Result<Option<usize>, ConstraintError> {
+    if s == "?" {
+        return Ok(None);
+    }
+    s.parse::<usize>()
+        .map(Some)
+        .map_err(|_| ConstraintError::NotANumber(s.to_string()))
+}
+
+/// Write the run lengths separated by commas, `0` for an empty line,
+/// or `?` for an unknown clue, as [Constraint::from_str] reads them.
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&writer::clue(self, &ClueStyle::compact()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A line written as in checkpoints, `1` filled, `0` empty and `?`
+    /// undecided.
+    fn line(s: &str) -> Vec<CellState> {
+        s.chars()
+            .map(|c| CellState::from_char(c).unwrap())
+            .collect()
+    }
+
+    fn check(clue: &str, cells: &str, status: LineStatus, runs: &[bool]) {
+        let clue: Constraint = clue.parse().unwrap();
+        let cells = line(cells);
+        assert_eq!(clue.line_status(&cells), status, "{} in {:?}", clue, cells);
+        assert_eq!(clue.satisfied_runs(&cells), runs, "{} in {:?}", clue, cells);
+    }
+
+    #[test]
+    fn complete_lines_are_satisfied_or_violated() {
+        use LineStatus::*;
+        check("2,1", "110100", Satisfied, &[true, true]);
+        check("2,1", "011001", Satisfied, &[true, true]);
+        check("2,1", "111000", Violated, &[false, false]);
+        check("2,1", "110000", Violated, &[false, false]);
+        check("0", "000", Satisfied, &[true]);
+        check("0", "010", Violated, &[false]);
+        check("?", "101", Satisfied, &[true]);
+        check("?,1", "111001", Satisfied, &[true, true]);
+    }
+
+    #[test]
+    fn partial_lines_match_runs_from_each_end() {
+        use LineStatus::*;
+        check("2,1", "??????", Incomplete, &[false, false]);
+        // A run touching an undecided cell may still grow.
+        check("2,1", "11????", Incomplete, &[false, false]);
+        check("2,1", "110???", Incomplete, &[true, false]);
+        check("2,1", "????01", Incomplete, &[false, true]);
+        check("2,1", "0110?1", Incomplete, &[true, false]);
+        check("2,1", "0110?01", Incomplete, &[true, true]);
+        check("0", "0?0", Incomplete, &[false]);
+        check("?", "1?0", Incomplete, &[false]);
+        check("?,1", "1110?0", Incomplete, &[true, false]);
+    }
+
+    #[test]
+    fn contradictory_partial_lines_are_violated() {
+        use LineStatus::*;
+        // Too many filled cells.
+        check("2,1", "11?111", Violated, &[false, false]);
+        // A first run longer than its clue.
+        check("2,1", "1110??", Violated, &[false, false]);
+        check("2,1", "111???", Violated, &[false, false]);
+        // A last run that doesn't match.
+        check("2,1", "???011", Violated, &[false, false]);
+        check("0", "1??", Violated, &[false]);
+    }
+}
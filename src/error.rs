@@ -0,0 +1,233 @@
+#[cfg(feature = "std")]
+use crate::assist::LineContradiction;
+#[cfg(feature = "fetch")]
+use crate::collection::FetchError;
+#[cfg(feature = "std")]
+use crate::formats::savefile::SaveFileError;
+#[cfg(feature = "std")]
+use crate::formats::{FormatError, RoundTripDiff};
+#[cfg(feature = "std")]
+use crate::image::ImageError;
+#[cfg(feature = "animate")]
+use crate::render::gif::GifTooLarge;
+#[cfg(feature = "std")]
+use crate::ParserError;
+use crate::{
+    BuilderError, CheckpointError, ConstraintError, LineConflict, RleError, SolveError,
+    TraceParseError,
+};
+
+use core::error;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
+
+// * The crate-level error type
+
+/// Any error this crate can produce.  Errors of the file formats and
+/// of I/O need feature `std`.
+///
+/// Every module-specific error converts into this type, so code that
+/// mixes parsing, building and solving can just use `?`.  Variants
+/// are transparent: they display as the wrapped error, and
+/// [error::Error::source] continues with the wrapped error's own
+/// source.
+#[derive(Debug)]
+pub enum Error {
+    Builder(BuilderError),
+    Checkpoint(CheckpointError),
+    Constraint(ConstraintError),
+    #[cfg(feature = "std")]
+    Contradiction(LineContradiction),
+    #[cfg(feature = "fetch")]
+    Fetch(FetchError),
+    #[cfg(feature = "std")]
+    Format(FormatError),
+    #[cfg(feature = "animate")]
+    Gif(GifTooLarge),
+    #[cfg(feature = "std")]
+    Image(ImageError),
+    Line(LineConflict),
+    #[cfg(feature = "std")]
+    Parser(ParserError),
+    Rle(RleError),
+    #[cfg(feature = "std")]
+    RoundTrip(RoundTripDiff),
+    #[cfg(feature = "std")]
+    SaveFile(SaveFileError),
+    Solve(SolveError),
+    Trace(TraceParseError),
+    #[cfg(feature = "std")]
+    Io(io::Error),
+}
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Builder(e) => e.fmt(f),
+            Error::Checkpoint(e) => e.fmt(f),
+            Error::Constraint(e) => e.fmt(f),
+            #[cfg(feature = "std")]
+            Error::Contradiction(e) => e.fmt(f),
+            #[cfg(feature = "fetch")]
+            Error::Fetch(e) => e.fmt(f),
+            #[cfg(feature = "std")]
+            Error::Format(e) => e.fmt(f),
+            #[cfg(feature = "animate")]
+            Error::Gif(e) => e.fmt(f),
+            #[cfg(feature = "std")]
+            Error::Image(e) => e.fmt(f),
+            Error::Line(e) => e.fmt(f),
+            #[cfg(feature = "std")]
+            Error::Parser(e) => e.fmt(f),
+            Error::Rle(e) => e.fmt(f),
+            #[cfg(feature = "std")]
+            Error::RoundTrip(e) => e.fmt(f),
+            #[cfg(feature = "std")]
+            Error::SaveFile(e) => e.fmt(f),
+            Error::Solve(e) => e.fmt(f),
+            Error::Trace(e) => e.fmt(f),
+            #[cfg(feature = "std")]
+            Error::Io(e) => e.fmt(f),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Builder(e) => e.source(),
+            Error::Checkpoint(e) => e.source(),
+            Error::Constraint(e) => e.source(),
+            #[cfg(feature = "std")]
+            Error::Contradiction(e) => e.source(),
+            #[cfg(feature = "fetch")]
+            Error::Fetch(e) => e.source(),
+            #[cfg(feature = "std")]
+            Error::Format(e) => e.source(),
+            #[cfg(feature = "animate")]
+            Error::Gif(e) => e.source(),
+            #[cfg(feature = "std")]
+            Error::Image(e) => e.source(),
+            Error::Line(e) => e.source(),
+            #[cfg(feature = "std")]
+            Error::Parser(e) => e.source(),
+            Error::Rle(e) => e.source(),
+            #[cfg(feature = "std")]
+            Error::RoundTrip(e) => e.source(),
+            #[cfg(feature = "std")]
+            Error::SaveFile(e) => e.source(),
+            Error::Solve(e) => e.source(),
+            Error::Trace(e) => e.source(),
+            #[cfg(feature = "std")]
+            Error::Io(e) => e.source(),
+        }
+    }
+}
+
+impl From<BuilderError> for Error {
+    fn from(value: BuilderError) -> Self {
+        Error::Builder(value)
+    }
+}
+
+impl From<CheckpointError> for Error {
+    fn from(value: CheckpointError) -> Self {
+        Error::Checkpoint(value)
+    }
+}
+
+impl From<ConstraintError> for Error {
+    fn from(value: ConstraintError) -> Self {
+        Error::Constraint(value)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<LineContradiction> for Error {
+    fn from(value: LineContradiction) -> Self {
+        Error::Contradiction(value)
+    }
+}
+
+#[cfg(feature = "fetch")]
+impl From<FetchError> for Error {
+    fn from(value: FetchError) -> Self {
+        Error::Fetch(value)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<FormatError> for Error {
+    fn from(value: FormatError) -> Self {
+        Error::Format(value)
+    }
+}
+
+#[cfg(feature = "animate")]
+impl From<GifTooLarge> for Error {
+    fn from(value: GifTooLarge) -> Self {
+        Error::Gif(value)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<ImageError> for Error {
+    fn from(value: ImageError) -> Self {
+        Error::Image(value)
+    }
+}
+
+impl From<LineConflict> for Error {
+    fn from(value: LineConflict) -> Self {
+        Error::Line(value)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<ParserError> for Error {
+    fn from(value: ParserError) -> Self {
+        Error::Parser(value)
+    }
+}
+
+impl From<RleError> for Error {
+    fn from(value: RleError) -> Self {
+        Error::Rle(value)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<RoundTripDiff> for Error {
+    fn from(value: RoundTripDiff) -> Self {
+        Error::RoundTrip(value)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<SaveFileError> for Error {
+    fn from(value: SaveFileError) -> Self {
+        Error::SaveFile(value)
+    }
+}
+
+impl From<SolveError> for Error {
+    fn from(value: SolveError) -> Self {
+        Error::Solve(value)
+    }
+}
+
+impl From<TraceParseError> for Error {
+    fn from(value: TraceParseError) -> Self {
+        Error::Trace(value)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        Error::Io(value)
+    }
+}
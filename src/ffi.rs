@@ -0,0 +1,194 @@
+//! A C API, with feature `capi`, to embed the solver in C, C++ or
+//! Swift programs.  `include/nonograms.h` declares it.
+//!
+//! Build a shared or static library with:
+//!
+//! <pre>
+//! cargo rustc --release --lib --crate-type cdylib --features capi
+//! cargo rustc --release --lib --crate-type staticlib --features capi
+//! </pre>
+//!
+//! Puzzles are opaque pointers from [nonogram_parse], to free with
+//! [nonogram_free].  Strings from this API are freed with
+//! [nonogram_string_free].  Every enum is `repr(C)`, and its values
+//! won't change.
+
+use crate::formats;
+use crate::generator::blot_clues;
+use crate::{CellState, Nonogram, Point, SolveOutcome};
+
+use std::ffi::{c_char, CStr, CString};
+
+/// The state of a cell, as in [CellState].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NonogramCell {
+    Undecided = 0,
+    Empty = 1,
+    Filled = 2,
+}
+
+impl From<CellState> for NonogramCell {
+    fn from(value: CellState) -> Self {
+        match value {
+            CellState::Undecided => NonogramCell::Undecided,
+            CellState::Empty => NonogramCell::Empty,
+            CellState::Filled => NonogramCell::Filled,
+        }
+    }
+}
+
+/// How solving ended, as in [SolveOutcome].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NonogramOutcome {
+    Solved = 0,
+    Stalled = 1,
+    Contradiction = 2,
+    Cancelled = 3,
+}
+
+impl From<SolveOutcome> for NonogramOutcome {
+    fn from(value: SolveOutcome) -> Self {
+        match value {
+            SolveOutcome::Solved => NonogramOutcome::Solved,
+            SolveOutcome::Stalled => NonogramOutcome::Stalled,
+            SolveOutcome::Contradiction => NonogramOutcome::Contradiction,
+            SolveOutcome::Cancelled => NonogramOutcome::Cancelled,
+        }
+    }
+}
+
+/// Read a puzzle in any format of [crate::formats], from the
+/// NUL-terminated UTF-8 `text`.  This returns NULL if `text` isn't a
+/// puzzle.
+///
+/// # Safety
+///
+/// `text` must be NULL or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn nonogram_parse(text: *const c_char) -> *mut Nonogram {
+    if text.is_null() {
+        return std::ptr::null_mut();
+    }
+    let Ok(text) = CStr::from_ptr(text).to_str() else {
+        return std::ptr::null_mut();
+    };
+    match formats::parse(text, None) {
+        Ok(puzzle) => Box::into_raw(Box::new(Nonogram::from(puzzle))),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Solve `nono` with the default [crate::SolverConfig].
+///
+/// # Safety
+///
+/// `nono` must come from [nonogram_parse], and not be freed.
+#[no_mangle]
+pub unsafe extern "C" fn nonogram_solve(nono: *mut Nonogram) -> NonogramOutcome {
+    (*nono).solve().into()
+}
+
+/// The width of `nono`.
+///
+/// # Safety
+///
+/// `nono` must come from [nonogram_parse], and not be freed.
+#[no_mangle]
+pub unsafe extern "C" fn nonogram_width(nono: *const Nonogram) -> usize {
+    (*nono).width()
+}
+
+/// The height of `nono`.
+///
+/// # Safety
+///
+/// `nono` must come from [nonogram_parse], and not be freed.
+#[no_mangle]
+pub unsafe extern "C" fn nonogram_height(nono: *const Nonogram) -> usize {
+    (*nono).height()
+}
+
+/// The cell of `nono` at column `x` and row `y`, from 0.  Cells
+/// outside the grid are undecided.
+///
+/// # Safety
+///
+/// `nono` must come from [nonogram_parse], and not be freed.
+#[no_mangle]
+pub unsafe extern "C" fn nonogram_cell_at(
+    nono: *const Nonogram,
+    x: usize,
+    y: usize,
+) -> NonogramCell {
+    let nono = &*nono;
+    let cell = Point::new(x, y);
+    if !cell.within(nono.width(), nono.height()) {
+        return NonogramCell::Undecided;
+    }
+    nono[cell].into()
+}
+
+/// The clues of `nono` in the format named `format`, eg `non` or
+/// `json`, see [crate::formats::by_name], or NULL if there's no such
+/// format.
+///
+/// # Safety
+///
+/// `nono` must come from [nonogram_parse], and not be freed, and
+/// `format` must be a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn nonogram_write(
+    nono: *const Nonogram,
+    format: *const c_char,
+) -> *mut c_char {
+    let format = CStr::from_ptr(format).to_str().map_err(|_| ());
+    match format.and_then(|f| formats::by_name(f).map_err(|_| ())) {
+        Ok(format) => {
+            let text = format.write(&(*nono).puzzle());
+            CString::new(text).map_or(std::ptr::null_mut(), CString::into_raw)
+        }
+        Err(()) => std::ptr::null_mut(),
+    }
+}
+
+/// A copy of `nono` with as many clues as possible unknown, see
+/// [crate::generator::blot_clues], or NULL if the solution of `nono`
+/// isn't unique.
+///
+/// # Safety
+///
+/// `nono` must come from [nonogram_parse], and not be freed.
+#[no_mangle]
+pub unsafe extern "C" fn nonogram_blot_clues(nono: *const Nonogram, seed: u64) -> *mut Nonogram {
+    match blot_clues(&(*nono).puzzle(), seed) {
+        Some(puzzle) => Box::into_raw(Box::new(Nonogram::from(puzzle))),
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Free a string from this API.  NULL is ignored.
+///
+/// # Safety
+///
+/// `s` must be NULL or come from this API, and not be freed already.
+#[no_mangle]
+pub unsafe extern "C" fn nonogram_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Free `nono`.  NULL is ignored.
+///
+/// # Safety
+///
+/// `nono` must be NULL or come from [nonogram_parse], and not be
+/// freed already.
+#[no_mangle]
+pub unsafe extern "C" fn nonogram_free(nono: *mut Nonogram) {
+    if !nono.is_null() {
+        drop(Box::from_raw(nono));
+    }
+}
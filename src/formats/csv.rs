@@ -0,0 +1,155 @@
+//! Clues laid out as in a spreadsheet, saved as CSV: the first row
+//! holds the clues of the columns, the first column those of the
+//! rows, with the numbers of a clue separated by spaces.
+//!
+//! <pre>
+//! A,1,1,2
+//! 2
+//! 1 1
+//! </pre>
+//!
+//! The top left cell is the title, if any.  Other cells, the grid,
+//! are ignored, so that authors can draw the picture there.  An empty
+//! line has the clue `0`, or an empty cell, except at the end of the
+//! first row, where spreadsheets pad lines with empty cells.  An
+//! unknown clue or length is `?`.  Cells may be quoted, but not span
+//! lines, and may be separated by `;` instead of `,`, as spreadsheets
+//! do in some languages.  There are no toroidal puzzles, and no
+//! authors or goals.
+
+use super::{build, FormatError, PuzzleFormat};
+use crate::writer::{self, ClueStyle};
+use crate::{Constraint, Puzzle};
+
+use std::mem;
+
+/// Numbers separated by spaces, as `3 1`.
+const CLUES: ClueStyle = ClueStyle {
+    separator: " ",
+    ..ClueStyle::compact()
+};
+
+pub struct Csv;
+
+/// The lines of `text`, without the byte order mark spreadsheets may
+/// start files with, nor blank lines at the end.
+fn lines(text: &str) -> impl Iterator<Item = &str> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    text.trim_end().lines()
+}
+
+/// What separates the cells of the first line `line`.
+fn delimiter(line: &str) -> char {
+    match line.contains(';') && !line.contains(',') {
+        true => ';',
+        false => ',',
+    }
+}
+
+/// The cells of `line`, unquoted, or `None` if a quote isn't closed.
+fn cells(line: &str, delimiter: char) -> Option<Vec<String>> {
+    let mut ret = vec![];
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                cell.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if cell.trim().is_empty() => {
+                cell.clear();
+                quoted = true;
+            }
+            c if c == delimiter && !quoted => ret.push(mem::take(&mut cell)),
+            c => cell.push(c),
+        }
+    }
+    ret.push(cell);
+    (!quoted).then_some(ret)
+}
+
+/// Whether `cell` can be a clue, though not necessarily a valid one.
+fn looks_like_clue(cell: &str) -> bool {
+    (cell.split_whitespace()).all(|n| n == "?" || n.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn clue(cell: &str) -> Result<Constraint, FormatError> {
+    let numbers: Vec<&str> = cell.split_whitespace().collect();
+    match numbers.is_empty() {
+        true => Ok(Constraint::empty()),
+        false => Ok(numbers.join(",").parse()?),
+    }
+}
+
+/// `cell`, quoted if a spreadsheet would read it otherwise.
+fn quote(cell: &str) -> String {
+    match cell.contains([',', ';', '"']) {
+        true => format!("\"{}\"", cell.replace('"', "\"\"")),
+        false => cell.to_string(),
+    }
+}
+
+impl PuzzleFormat for Csv {
+    fn name(&self) -> &'static str {
+        "csv"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["csv"]
+    }
+
+    fn detect(&self, text: &str) -> bool {
+        let Some(first) = lines(text).next() else {
+            return false;
+        };
+        match cells(first, delimiter(first)) {
+            Some(cells) => cells.len() > 1 && cells[1..].iter().all(|c| looks_like_clue(c)),
+            None => false,
+        }
+    }
+
+    fn parse(&self, text: &str) -> Result<Puzzle, FormatError> {
+        let mut lines = lines(text);
+        let first = lines
+            .next()
+            .ok_or_else(|| FormatError::Syntax(String::from("no clues")))?;
+        let delimiter = delimiter(first);
+        let row = |n: usize, line: &str| {
+            cells(line, delimiter)
+                .ok_or_else(|| FormatError::Syntax(format!("unclosed quote on line {}", n + 1)))
+        };
+        let mut header = row(0, first)?;
+        // Spreadsheets write empty cells up to the widest line.
+        while header.len() > 1 && header.last().is_some_and(|c| c.trim().is_empty()) {
+            header.pop();
+        }
+        let title = header[0].trim();
+        let cols = (header[1..].iter())
+            .map(|cell| clue(cell))
+            .collect::<Result<Vec<Constraint>, _>>()?;
+        let rows = (lines.enumerate())
+            .map(|(n, line)| clue(&row(n + 1, line)?[0]))
+            .collect::<Result<Vec<Constraint>, _>>()?;
+        let mut puzzle = build(None, None, cols, rows)?;
+        puzzle.title = (!title.is_empty()).then(|| title.to_string());
+        Ok(puzzle)
+    }
+
+    fn write(&self, puzzle: &Puzzle) -> String {
+        let title = puzzle.title.as_deref().unwrap_or("");
+        let mut ret = quote(title);
+        for clue in &puzzle.cols {
+            ret.push(',');
+            ret.push_str(&writer::clue(clue, &CLUES));
+        }
+        ret.push('\n');
+        for clue in &puzzle.rows {
+            ret.push_str(&writer::clue(clue, &CLUES));
+            ret.push('\n');
+        }
+        ret
+    }
+}
@@ -0,0 +1,381 @@
+//! Puzzles as JSON objects:
+//!
+//! <pre>
+//! {
+//!   "title": "A",
+//!   "author": "Someone",
+//!   "width": 3,
+//!   "height": 2,
+//!   "rows": [[2], [1, 1]],
+//!   "columns": [[1], [1], [2]],
+//!   "goal": ["1w2b", "1b1w1b"]
+//! }
+//! </pre>
+//!
+//! An empty line has the clue `[]`, or `[0]`.  `"toroidal": true`
+//! makes runs wrap around, see [crate::Constraint::wraps].  An unknown
+//! clue is `"?"`, and so is an unknown length in a clue, eg
+//! `[3, "?"]`.  The goal, see [crate::Puzzle::goal], is a run-length
+//! string per row, see [crate::rle].  The title, author, dimensions
+//! and goal are optional: the dimensions are then the numbers of
+//! clues.
+
+use super::{build, FormatError, PuzzleFormat};
+use crate::writer::{self, ClueStyle};
+use crate::{Constraint, Grid, Nonogram, Puzzle};
+
+use std::fmt::Write;
+
+/// The numbers of a clue, within its brackets: an empty line is `[]`.
+const CLUES: ClueStyle = ClueStyle {
+    separator: ", ",
+    empty: "",
+    unknown: "\"?\"",
+    ..ClueStyle::compact()
+};
+
+pub struct Json;
+
+impl PuzzleFormat for Json {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["json"]
+    }
+
+    fn detect(&self, text: &str) -> bool {
+        text.trim_start().starts_with('{')
+    }
+
+    fn parse(&self, text: &str) -> Result<Puzzle, FormatError> {
+        let value = Reader::new(text).document()?;
+        let Value::Object(fields) = value else {
+            return Err(syntax("expected an object"));
+        };
+        let field = |name: &str| fields.iter().find(|(k, _)| k == name).map(|(_, v)| v);
+        let string = |name: &str| match field(name) {
+            None | Some(Value::Null) => Ok(None),
+            Some(Value::String(s)) => Ok(Some(s.clone())),
+            Some(_) => Err(syntax(&format!("{} should be a string", name))),
+        };
+        let clues = |name: &str| -> Result<Vec<Constraint>, FormatError> {
+            let Some(Value::Array(clues)) = field(name) else {
+                return Err(syntax(&format!("{} should be an array of clues", name)));
+            };
+            clues.iter().map(|clue| clue.to_constraint(name)).collect()
+        };
+        let size = |name: &str| match field(name) {
+            None => Ok(None),
+            Some(v) => (v.to_usize().map(Some)).ok_or_else(|| syntax(&format!("bad {}", name))),
+        };
+
+        let (rows, cols) = (clues("rows")?, clues("columns")?);
+        let mut puzzle = build(size("width")?, size("height")?, cols, rows)?;
+        puzzle.title = string("title")?;
+        puzzle.author = string("author")?;
+        match field("toroidal") {
+            None | Some(Value::Null) | Some(Value::Bool(false)) => (),
+            Some(Value::Bool(true)) => puzzle.set_toroidal(true),
+            Some(_) => return Err(syntax("toroidal should be a boolean")),
+        }
+        match field("goal") {
+            None | Some(Value::Null) => (),
+            Some(Value::Array(rows)) => puzzle.goal = Some(read_goal(&puzzle, rows)?),
+            Some(_) => return Err(syntax("goal should be an array of run-length rows")),
+        }
+        Ok(puzzle)
+    }
+
+    fn write(&self, puzzle: &Puzzle) -> String {
+        let mut ret = String::from("{\n");
+        if let Some(title) = &puzzle.title {
+            writeln!(ret, "  \"title\": {},", quote(title)).unwrap();
+        }
+        if let Some(author) = &puzzle.author {
+            writeln!(ret, "  \"author\": {},", quote(author)).unwrap();
+        }
+        if puzzle.is_toroidal() {
+            ret.push_str("  \"toroidal\": true,\n");
+        }
+        writeln!(ret, "  \"width\": {},", puzzle.width()).unwrap();
+        writeln!(ret, "  \"height\": {},", puzzle.height()).unwrap();
+        let clues = |clues: &[Constraint]| -> String {
+            let clues: Vec<String> = clues
+                .iter()
+                .map(|c| match c.is_unknown() {
+                    true => format!("    {}", writer::clue(c, &CLUES)),
+                    false => format!("    [{}]", writer::clue(c, &CLUES)),
+                })
+                .collect();
+            clues.join(",\n")
+        };
+        writeln!(ret, "  \"rows\": [\n{}\n  ],", clues(&puzzle.rows)).unwrap();
+        write!(ret, "  \"columns\": [\n{}\n  ]", clues(&puzzle.cols)).unwrap();
+        let goal = (puzzle.goal.as_ref())
+            .and_then(|goal| Nonogram::from_parts(puzzle.clone(), goal.clone()).solution_rle());
+        if let Some(goal) = goal {
+            let rows: Vec<String> = goal
+                .iter()
+                .map(|row| format!("    {}", quote(row)))
+                .collect();
+            write!(ret, ",\n  \"goal\": [\n{}\n  ]", rows.join(",\n")).unwrap();
+        }
+        ret.push_str("\n}\n");
+        ret
+    }
+}
+
+/// The goal of `puzzle`, from a run-length string per row.
+fn read_goal(puzzle: &Puzzle, rows: &[Value]) -> Result<Grid, FormatError> {
+    let rows = (rows.iter())
+        .map(|row| match row {
+            Value::String(row) => Ok(row.as_str()),
+            _ => Err(syntax("goal should be an array of run-length rows")),
+        })
+        .collect::<Result<Vec<&str>, _>>()?;
+    let mut nono = Nonogram::from(puzzle.clone());
+    (nono.set_solution_rle(&rows)).map_err(|e| syntax(&format!("bad goal: {}", e)))?;
+    Ok(nono.grid())
+}
+
+fn syntax(what: &str) -> FormatError {
+    FormatError::Syntax(what.to_string())
+}
+
+/// `s` as a JSON string, eg to write other JSON documents.
+pub fn quote(s: &str) -> String {
+    let mut ret = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => ret.push_str("\\\""),
+            '\\' => ret.push_str("\\\\"),
+            '\n' => ret.push_str("\\n"),
+            '\t' => ret.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(ret, "\\u{:04x}", c as u32).unwrap(),
+            c => ret.push(c),
+        }
+    }
+    ret.push('"');
+    ret
+}
+
+// * Reading JSON
+
+enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    fn to_usize(&self) -> Option<usize> {
+        match self {
+            Value::Number(n) if *n >= 0.0 && n.fract() == 0.0 => Some(*n as usize),
+            _ => None,
+        }
+    }
+
+    fn to_constraint(&self, name: &str) -> Result<Constraint, FormatError> {
+        let bad = || syntax(&format!("{} should be arrays of run lengths", name));
+        let runs = match self {
+            Value::Array(runs) => runs,
+            Value::String(s) if s == "?" => return Ok(Constraint::unknown()),
+            _ => return Err(bad()),
+        };
+        let lengths = runs
+            .iter()
+            .map(|n| match n {
+                Value::String(s) if s == "?" => Ok(None),
+                n => n.to_usize().map(Some).ok_or_else(bad),
+            })
+            .collect::<Result<Vec<Option<usize>>, _>>()?;
+        Ok(Constraint::with_unknowns(lengths)?)
+    }
+}
+
+/// A parser for the whole of JSON, though puzzles need little of it.
+struct Reader<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(text: &'a str) -> Reader<'a> {
+        Reader { text, pos: 0 }
+    }
+
+    fn error(&self, what: &str) -> FormatError {
+        syntax(&format!("{} at byte {}", what, self.pos))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek().filter(|c| c.is_ascii_whitespace()) {
+            self.pos += c.len_utf8();
+        }
+    }
+
+    /// Skip whitespace, then `expected`.
+    fn expect(&mut self, expected: char) -> Result<(), FormatError> {
+        self.skip_whitespace();
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected {:?}", expected)))
+        }
+    }
+
+    /// Skip whitespace, then `c` if it's next.
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        let ret = self.peek() == Some(c);
+        if ret {
+            self.pos += 1;
+        }
+        ret
+    }
+
+    fn document(&mut self) -> Result<Value, FormatError> {
+        let ret = self.value()?;
+        self.skip_whitespace();
+        match self.peek() {
+            None => Ok(ret),
+            Some(_) => Err(self.error("trailing characters")),
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, FormatError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.object(),
+            Some('[') => self.array(),
+            Some('"') => Ok(Value::String(self.string()?)),
+            Some('-' | '0'..='9') => self.number(),
+            _ => {
+                for (word, value) in [
+                    ("null", Value::Null),
+                    ("true", Value::Bool(true)),
+                    ("false", Value::Bool(false)),
+                ] {
+                    if self.text[self.pos..].starts_with(word) {
+                        self.pos += word.len();
+                        return Ok(value);
+                    }
+                }
+                Err(self.error("expected a value"))
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Value, FormatError> {
+        self.expect('{')?;
+        let mut fields = vec![];
+        if self.eat('}') {
+            return Ok(Value::Object(fields));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.expect(':')?;
+            fields.push((key, self.value()?));
+            if self.eat('}') {
+                return Ok(Value::Object(fields));
+            }
+            self.expect(',')?;
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, FormatError> {
+        self.expect('[')?;
+        let mut values = vec![];
+        if self.eat(']') {
+            return Ok(Value::Array(values));
+        }
+        loop {
+            values.push(self.value()?);
+            if self.eat(']') {
+                return Ok(Value::Array(values));
+            }
+            self.expect(',')?;
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, FormatError> {
+        let len = self.text[self.pos..]
+            .find(|c: char| !matches!(c, '-' | '+' | '.' | 'e' | 'E' | '0'..='9'))
+            .unwrap_or(self.text.len() - self.pos);
+        let number = self.text[self.pos..self.pos + len]
+            .parse()
+            .map_err(|_| self.error("bad number"))?;
+        self.pos += len;
+        Ok(Value::Number(number))
+    }
+
+    fn string(&mut self) -> Result<String, FormatError> {
+        self.expect('"')?;
+        let mut ret = String::new();
+        loop {
+            let c = self
+                .peek()
+                .ok_or_else(|| self.error("unterminated string"))?;
+            self.pos += c.len_utf8();
+            match c {
+                '"' => return Ok(ret),
+                '\\' => {
+                    let escape = self
+                        .peek()
+                        .ok_or_else(|| self.error("unterminated string"))?;
+                    self.pos += escape.len_utf8();
+                    ret.push(match escape {
+                        '"' | '\\' | '/' => escape,
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        'u' => self.unicode_escape()?,
+                        _ => return Err(self.error("bad escape")),
+                    });
+                }
+                c => ret.push(c),
+            }
+        }
+    }
+
+    /// The character of a `\u` escape, after the `u`, including
+    /// surrogate pairs.
+    fn unicode_escape(&mut self) -> Result<char, FormatError> {
+        let high = self.hex4()?;
+        let code = if (0xd800..0xdc00).contains(&high) {
+            if !self.text[self.pos..].starts_with("\\u") {
+                return Err(self.error("unpaired surrogate"));
+            }
+            self.pos += 2;
+            let low = self.hex4()?;
+            if !(0xdc00..0xe000).contains(&low) {
+                return Err(self.error("unpaired surrogate"));
+            }
+            0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+        } else {
+            high
+        };
+        char::from_u32(code).ok_or_else(|| self.error("bad unicode escape"))
+    }
+
+    fn hex4(&mut self) -> Result<u32, FormatError> {
+        let digits = self.text.get(self.pos..self.pos + 4);
+        let n = digits.and_then(|d| u32::from_str_radix(d, 16).ok());
+        let n = n.ok_or_else(|| self.error("bad unicode escape"))?;
+        self.pos += 4;
+        Ok(n)
+    }
+}
@@ -0,0 +1,464 @@
+//! Reading and writing puzzles and other files.
+//!
+//! Every puzzle file format implements [PuzzleFormat], and
+//! [registry] lists them, so that callers can convert between any two
+//! of them.  [parse] finds the format of a file from its extension,
+//! or else from its contents, and reads it.  [crate::Parser] reads
+//! the format of [non].
+//!
+//! [savefile] reads and writes games in progress, which are not
+//! plain puzzles.
+
+pub mod csv;
+pub mod json;
+pub mod non;
+pub mod savefile;
+pub mod tatham;
+pub mod webpbn;
+
+use crate::parser::read_text;
+use crate::{
+    BuilderError, ClueDiff, Constraint, ConstraintError, LineRef, Nonogram, NonogramBuilder,
+    ParserError, Puzzle,
+};
+
+use std::error;
+use std::fmt;
+use std::fs::File;
+use std::path::Path;
+
+// * Formats
+
+/// A file format for puzzle definitions.
+pub trait PuzzleFormat {
+    /// A short name for the format, eg `json`.
+    fn name(&self) -> &'static str;
+
+    /// Extensions of files in this format, without the dot.
+    fn extensions(&self) -> &'static [&'static str];
+
+    /// Whether `text` looks like a puzzle in this format.  This is a
+    /// quick guess, and parsing may still fail.
+    fn detect(&self, text: &str) -> bool;
+
+    fn parse(&self, text: &str) -> Result<Puzzle, FormatError>;
+
+    fn write(&self, puzzle: &Puzzle) -> String;
+}
+
+/// Every puzzle format, in the order [detect] tries them.
+pub fn registry() -> &'static [&'static dyn PuzzleFormat] {
+    &[
+        &webpbn::Webpbn,
+        &json::Json,
+        &tatham::Tatham,
+        &csv::Csv,
+        &non::Non,
+    ]
+}
+
+/// The format called `name`.
+pub fn by_name(name: &str) -> Result<&'static dyn PuzzleFormat, FormatError> {
+    registry()
+        .iter()
+        .find(|f| f.name() == name)
+        .copied()
+        .ok_or_else(|| FormatError::UnknownFormat(name.to_string()))
+}
+
+/// The format of files named like `path`, from its extension.
+pub fn for_path(path: &Path) -> Option<&'static dyn PuzzleFormat> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    registry()
+        .iter()
+        .find(|f| f.extensions().contains(&extension.as_str()))
+        .copied()
+}
+
+/// The first format `text` looks like.
+pub fn detect(text: &str) -> Result<&'static dyn PuzzleFormat, FormatError> {
+    registry()
+        .iter()
+        .find(|f| f.detect(text))
+        .copied()
+        .ok_or(FormatError::Undetected)
+}
+
+/// Read the puzzle `text`, in the format of files named like `path`
+/// if there's one and it has a known extension, or else in the format
+/// it looks like.
+pub fn parse(text: &str, path: Option<&Path>) -> Result<Puzzle, FormatError> {
+    let format = match path.and_then(for_path) {
+        Some(format) => format,
+        None => detect(text)?,
+    };
+    format.parse(text)
+}
+
+/// Read the puzzle file at `path`, as [parse] does.
+pub fn read_file(path: &Path) -> Result<Puzzle, FormatError> {
+    let mut file = File::open(path).map_err(ParserError::from)?;
+    parse(&read_text(&mut file)?, Some(path))
+}
+
+/// Build a puzzle from clues read from any format, checking that they
+/// match the dimensions, if given, or else taking the dimensions from
+/// the numbers of clues.
+fn build(
+    width: Option<usize>,
+    height: Option<usize>,
+    cols: Vec<Constraint>,
+    rows: Vec<Constraint>,
+) -> Result<Puzzle, FormatError> {
+    let mut builder = NonogramBuilder::new();
+    if let Some(width) = width {
+        builder = builder.width(width);
+    }
+    if let Some(height) = height {
+        builder = builder.height(height);
+    }
+    let builder = builder.cols(cols).rows(rows).infer_dimensions();
+    Ok(builder.build()?.into_parts().0)
+}
+
+// * Round trips
+
+/// Check that `format` keeps everything about `puzzle`: that writing
+/// it, then reading it back, gives the same clues, title, author and
+/// goal.  The error lists what would be lost.
+pub fn round_trip_check(puzzle: &Puzzle, format: &dyn PuzzleFormat) -> Result<(), RoundTripDiff> {
+    let losses = match format.parse(&format.write(puzzle)) {
+        Err(e) => vec![Loss::Unreadable(e.to_string())],
+        Ok(back) => {
+            let mut ret = vec![];
+            let ours = Nonogram::from(puzzle.clone());
+            if let Err(diff) = ours.equivalent_to(&Nonogram::from(back.clone())) {
+                ret.push(Loss::Clues(diff));
+            }
+            if back.title != puzzle.title {
+                ret.push(Loss::Title);
+            }
+            if back.author != puzzle.author {
+                ret.push(Loss::Author);
+            }
+            if back.goal != puzzle.goal {
+                ret.push(Loss::Goal);
+            }
+            ret
+        }
+    };
+    match losses.is_empty() {
+        true => Ok(()),
+        false => Err(RoundTripDiff {
+            format: format.name(),
+            losses,
+        }),
+    }
+}
+
+/// What a format doesn't keep of a puzzle, see [round_trip_check].
+#[derive(Clone, Debug, PartialEq)]
+pub struct RoundTripDiff {
+    /// The name of the format.
+    pub format: &'static str,
+    pub losses: Vec<Loss>,
+}
+
+/// Something a format doesn't keep.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Loss {
+    /// What the format writes can't be read back; this says why.
+    Unreadable(String),
+    Clues(ClueDiff),
+    Title,
+    Author,
+    Goal,
+}
+
+impl fmt::Display for Loss {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Loss::Unreadable(e) => write!(f, "the whole puzzle ({})", e),
+            Loss::Clues(ClueDiff::Size { .. }) => f.write_str("the size"),
+            Loss::Clues(ClueDiff::Lines(lines)) => {
+                let lines: Vec<String> = lines.iter().map(LineRef::to_string).collect();
+                write!(f, "the clues of {}", lines.join(", "))
+            }
+            Loss::Title => f.write_str("the title"),
+            Loss::Author => f.write_str("the author"),
+            Loss::Goal => f.write_str("the goal"),
+        }
+    }
+}
+
+impl fmt::Display for RoundTripDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut losses: Vec<String> = self.losses.iter().map(Loss::to_string).collect();
+        let last = losses.pop().unwrap_or_default();
+        match losses.is_empty() {
+            true => write!(f, "The {} format loses {}.", self.format, last),
+            false => write!(
+                f,
+                "The {} format loses {} and {}.",
+                self.format,
+                losses.join(", "),
+                last
+            ),
+        }
+    }
+}
+
+impl error::Error for RoundTripDiff {}
+
+// * Errors
+
+#[derive(Debug)]
+pub enum FormatError {
+    /// No format has this name.
+    UnknownFormat(String),
+    /// The input doesn't look like any format.
+    Undetected,
+    /// The input is malformed; this says where or how.
+    Syntax(String),
+    /// The input is well-formed, but isn't a puzzle this crate can
+    /// solve, eg a puzzle in colors.
+    Unsupported(String),
+    Builder(BuilderError),
+    Constraint(ConstraintError),
+    Parser(ParserError),
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::UnknownFormat(name) => write!(f, "Unknown format: {}", name),
+            FormatError::Undetected => f.write_str("Cannot detect the format of the input."),
+            FormatError::Syntax(what) => write!(f, "Malformed input: {}", what),
+            FormatError::Unsupported(what) => write!(f, "Unsupported puzzle: {}", what),
+            FormatError::Builder(_) => f.write_str("Invalid nonogram definition."),
+            FormatError::Constraint(_) => f.write_str("Invalid clue."),
+            FormatError::Parser(_) => f.write_str("Cannot read the puzzle."),
+        }
+    }
+}
+
+impl error::Error for FormatError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            FormatError::Builder(e) => Some(e),
+            FormatError::Constraint(e) => Some(e),
+            FormatError::Parser(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<BuilderError> for FormatError {
+    fn from(value: BuilderError) -> Self {
+        FormatError::Builder(value)
+    }
+}
+
+impl From<ConstraintError> for FormatError {
+    fn from(value: ConstraintError) -> Self {
+        FormatError::Constraint(value)
+    }
+}
+
+impl From<ParserError> for FormatError {
+    fn from(value: ParserError) -> Self {
+        FormatError::Parser(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+    use crate::{CellState, Grid};
+
+    /// A 5×4 picture, with its clues, as its goal.
+    fn with_goal() -> Puzzle {
+        let picture = ["#.##.", "#####", "..#..", "##.##"];
+        let cells = (picture.iter().flat_map(|row| row.chars()))
+            .map(|c| match c {
+                '#' => CellState::Filled,
+                _ => CellState::Empty,
+            })
+            .collect();
+        Puzzle::from_solution(&Grid::from_cells(5, 4, cells))
+    }
+
+    /// The same, titled, with an author and no goal.
+    fn plain() -> Puzzle {
+        let mut ret = with_goal();
+        ret.goal = None;
+        ret.title = Some(String::from("A \"title\", with commas"));
+        ret.author = Some(String::from("Someone"));
+        ret
+    }
+
+    fn toroidal() -> Puzzle {
+        let mut ret = with_goal();
+        ret.goal = None;
+        ret.set_toroidal(true);
+        ret
+    }
+
+    /// With an unknown clue in row 0, and an unknown length in row 3.
+    fn with_unknowns() -> Puzzle {
+        let mut ret = with_goal();
+        ret.goal = None;
+        ret.rows[0] = Constraint::unknown();
+        ret.rows[3] = Constraint::with_unknowns(vec![Some(2), None]).unwrap();
+        ret
+    }
+
+    fn losses(puzzle: &Puzzle, format: &dyn PuzzleFormat) -> Vec<Loss> {
+        match round_trip_check(puzzle, format) {
+            Ok(()) => vec![],
+            Err(diff) => {
+                assert_eq!(diff.format, format.name());
+                diff.losses
+            }
+        }
+    }
+
+    /// Every line of a 5×4 puzzle.
+    fn every_line() -> Loss {
+        let rows = (0..4).map(LineRef::Row);
+        Loss::Clues(ClueDiff::Lines(
+            rows.chain((0..5).map(LineRef::Column)).collect(),
+        ))
+    }
+
+    #[test]
+    fn formats_keep_what_they_document() {
+        for format in registry() {
+            let name = format.name();
+            let expected = match name {
+                "tatham" => vec![Loss::Title, Loss::Author],
+                "csv" => vec![Loss::Author],
+                _ => vec![],
+            };
+            assert_eq!(losses(&plain(), *format), expected, "{}", name);
+
+            let expected = match name {
+                "webpbn" | "tatham" | "csv" => vec![Loss::Goal],
+                _ => vec![],
+            };
+            assert_eq!(losses(&with_goal(), *format), expected, "{}", name);
+
+            let expected = match name {
+                "webpbn" | "tatham" | "csv" => vec![every_line()],
+                _ => vec![],
+            };
+            assert_eq!(losses(&toroidal(), *format), expected, "{}", name);
+
+            let expected = match name {
+                "webpbn" => vec![Loss::Clues(ClueDiff::Lines(vec![
+                    LineRef::Row(0),
+                    LineRef::Row(3),
+                ]))],
+                _ => vec![],
+            };
+            assert_eq!(losses(&with_unknowns(), *format), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn formats_detect_what_they_write() {
+        for format in registry() {
+            for puzzle in [plain(), with_goal(), toroidal(), with_unknowns()] {
+                let text = format.write(&puzzle);
+                assert_eq!(detect(&text).unwrap().name(), format.name(), "{}", text);
+            }
+        }
+    }
+
+    #[test]
+    fn round_trip_diff_lists_losses() {
+        let diff = round_trip_check(&plain(), &tatham::Tatham).unwrap_err();
+        assert_eq!(
+            diff.to_string(),
+            "The tatham format loses the title and the author."
+        );
+    }
+
+    // * The invariant of fuzz/fuzz_targets/roundtrip.rs
+
+    /// Texts for [mutants] to start from: what every format writes of
+    /// the puzzles above, and some written by hand.
+    fn seeds() -> Vec<String> {
+        let mut ret: Vec<String> = (registry().iter())
+            .flat_map(|f| [plain(), with_goal(), toroidal(), with_unknowns()].map(|p| f.write(&p)))
+            .collect();
+        let by_hand = [
+            "width 3\nheight 1\n\nrows\n1,?\n\ncolumns\n1\n0\n?\ntoroidal\n",
+            "title \"\\\"A\\\"\"\nrows\n?\ncolumns\n?\ngoal \"1\"\n",
+            "{\"rows\": [[1], \"?\"], \"columns\": [[0], [\"?\"]], \"title\": \"\\u00e9\\n\"}",
+            "<puzzleset><puzzle><clues type=\"rows\"><line><count>1</count></line></clues>\
+             <clues type=\"columns\"><line><count>1</count></line></clues></puzzle></puzzleset>",
+            "pattern:2x2:1/1/1/1,abc",
+            "\u{feff}\"T;1\";1 1;?\r\n2;#\r\n0\r\n\"1\"\r\n",
+        ];
+        ret.extend(by_hand.map(String::from));
+        ret
+    }
+
+    /// `count` variants of `seed`, each with a few characters
+    /// inserted, removed or replaced.
+    fn mutants(seed: &str, rng: &mut Rng, count: usize) -> Vec<String> {
+        let alphabet: Vec<char> = (seed.chars())
+            .chain("0123456789?,;.:/ \n\"[]{}<>#bw".chars())
+            .collect();
+        (0..count)
+            .map(|_| {
+                let mut chars: Vec<char> = seed.chars().collect();
+                for _ in 0..1 + rng.below(4) {
+                    let at = rng.below(chars.len() + 1);
+                    let c = alphabet[rng.below(alphabet.len())];
+                    match rng.below(3) {
+                        0 => chars.insert(at, c),
+                        _ if at == chars.len() => (),
+                        1 => _ = chars.remove(at),
+                        _ => chars[at] = c,
+                    }
+                }
+                chars.into_iter().collect()
+            })
+            .collect()
+    }
+
+    /// Write `puzzle` in `format`, read it back and write it again, and
+    /// check that both texts are the same.
+    fn check_round_trip(format: &dyn PuzzleFormat, puzzle: &Puzzle) {
+        let text = format.write(puzzle);
+        let again = match format.parse(&text) {
+            Ok(puzzle) => puzzle,
+            Err(e) => panic!(
+                "{} can't read what it wrote: {}\n{}",
+                format.name(),
+                e,
+                text
+            ),
+        };
+        assert_eq!(text, format.write(&again), "{} round-trip", format.name());
+    }
+
+    #[test]
+    fn what_reads_back_writes_back_the_same() {
+        let mut rng = Rng::new(870);
+        for seed in seeds() {
+            assert!(parse(&seed, None).is_ok(), "{}", seed);
+            for text in mutants(&seed, &mut rng, 200).into_iter().chain([seed]) {
+                let formats = (registry().iter().copied()).chain(detect(&text).ok());
+                for format in formats {
+                    if let Ok(puzzle) = format.parse(&text) {
+                        check_round_trip(format, &puzzle);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! The format of <https://github.com/mikix/nonogram-db/>, which
+//! [crate::Parser] reads.
+//!
+//! <pre>
+//! title "A"
+//! by "Someone"
+//! width 3
+//! height 2
+//!
+//! rows
+//! 2
+//! 1,1
+//!
+//! columns
+//! 1
+//! 1
+//! 2
+//! </pre>
+//!
+//! A line `toroidal` makes runs wrap around, see
+//! [crate::Constraint::wraps].  A line `goal "011001"` gives the
+//! solution, see [crate::Puzzle::goal], with a `1` for each filled
+//! cell and a `0` for each empty one, row by row.
+
+use super::{FormatError, PuzzleFormat};
+use crate::writer::{self, ClueStyle};
+use crate::{CellState, Parser, Puzzle};
+
+use std::fmt::Write;
+
+pub struct Non;
+
+impl PuzzleFormat for Non {
+    fn name(&self) -> &'static str {
+        "non"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["non"]
+    }
+
+    fn detect(&self, text: &str) -> bool {
+        text.lines()
+            .any(|l| matches!(l.split_whitespace().next(), Some("width" | "rows")))
+    }
+
+    fn parse(&self, text: &str) -> Result<Puzzle, FormatError> {
+        Ok(Parser::new().parse_puzzle(&mut text.as_bytes())?)
+    }
+
+    fn write(&self, puzzle: &Puzzle) -> String {
+        let mut ret = String::new();
+        if let Some(title) = &puzzle.title {
+            writeln!(ret, "title \"{}\"", title).unwrap();
+        }
+        if let Some(author) = &puzzle.author {
+            writeln!(ret, "by \"{}\"", author).unwrap();
+        }
+        if puzzle.is_toroidal() {
+            ret.push_str("toroidal\n");
+        }
+        writeln!(ret, "width {}", puzzle.width()).unwrap();
+        writeln!(ret, "height {}", puzzle.height()).unwrap();
+        ret.push_str("\nrows\n");
+        for clue in writer::lines(&puzzle.rows, &ClueStyle::compact()) {
+            writeln!(ret, "{}", clue).unwrap();
+        }
+        ret.push_str("\ncolumns\n");
+        for clue in writer::lines(&puzzle.cols, &ClueStyle::compact()) {
+            writeln!(ret, "{}", clue).unwrap();
+        }
+        if let Some(goal) = &puzzle.goal {
+            let cells: String = (goal.cells().iter())
+                .map(|c| if *c == CellState::Filled { '1' } else { '0' })
+                .collect();
+            writeln!(ret, "\ngoal \"{}\"", cells).unwrap();
+        }
+        ret
+    }
+}
@@ -0,0 +1,229 @@
+//! Save files of a game in progress: the puzzle, the player's grid
+//! and, optionally, the moves that led to it.
+//!
+//! A save file is a puzzle definition in the format of
+//! [crate::Parser], which ignores the extra lines, so it can be read
+//! as a plain puzzle too.  For example:
+//!
+//! <pre>
+//! nonograms-save 1
+//! width 3
+//! height 2
+//!
+//! rows
+//! 2
+//! 1,1
+//!
+//! columns
+//! 1
+//! 1
+//! 2
+//!
+//! marks "?11?01"
+//! pencil "??????"
+//! move 1 0 fill
+//! move 2 0 fill
+//! move 1 1 cross
+//! move 2 1 fill pencil
+//! move 2 1 clear pencil
+//! move 2 1 fill
+//! </pre>
+//!
+//! `marks` and `pencil` list cells row by row, with `1` for filled,
+//! `0` for crossed and `?` for unmarked.  Each `move` line is `x y`,
+//! then `fill`, `cross` or `clear`, then `pencil` for a pencil mark.
+//! Moves are optional: the grids alone are enough to resume a game.
+//! An empty clue is written `0`.
+
+use crate::formats::non::Non;
+use crate::formats::PuzzleFormat;
+use crate::parser::read_text;
+use crate::{Mark, Move, Nonogram, Parser, ParserError, PlayerGrid, Point};
+
+use std::error;
+use std::fmt;
+use std::io;
+
+const MAGIC: &str = "nonograms-save";
+const VERSION: &str = "1";
+
+/// A game in progress.
+#[derive(Clone, Debug)]
+pub struct SaveFile {
+    pub puzzle: Nonogram,
+    pub player: PlayerGrid,
+    /// The moves played so far, oldest first, if they were recorded.
+    pub moves: Vec<Move>,
+}
+
+#[derive(Debug)]
+pub enum SaveFileError {
+    /// The text is not a save file, or not of a supported version.
+    BadHeader,
+    /// The puzzle definition couldn't be read.
+    Puzzle(ParserError),
+    /// A line of the save file couldn't be read.
+    Malformed(String),
+    /// The grids don't have the dimensions of the puzzle.
+    DimensionMismatch,
+}
+
+impl fmt::Display for SaveFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveFileError::BadHeader => f.write_str("Not a save file."),
+            SaveFileError::Puzzle(_) => f.write_str("Cannot read the saved puzzle."),
+            SaveFileError::Malformed(line) => write!(f, "Malformed save file line: {}", line),
+            SaveFileError::DimensionMismatch => {
+                f.write_str("Saved grid dimensions don't match the puzzle.")
+            }
+        }
+    }
+}
+
+impl error::Error for SaveFileError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            SaveFileError::Puzzle(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ParserError> for SaveFileError {
+    fn from(value: ParserError) -> Self {
+        SaveFileError::Puzzle(value)
+    }
+}
+
+impl From<io::Error> for SaveFileError {
+    fn from(value: io::Error) -> Self {
+        SaveFileError::Puzzle(ParserError::IOError(value))
+    }
+}
+
+impl SaveFile {
+    /// A save of `player` playing `puzzle`, without moves.
+    pub fn new(puzzle: Nonogram, player: PlayerGrid) -> SaveFile {
+        SaveFile {
+            puzzle,
+            player,
+            moves: vec![],
+        }
+    }
+
+    /// Read a save file.
+    pub fn read(r: &mut impl io::Read) -> Result<SaveFile, SaveFileError> {
+        let source = read_text(r)?;
+        let mut lines = source.lines().map(str::trim).filter(|l| !l.is_empty());
+        if lines.next() != Some(&format!("{} {}", MAGIC, VERSION)) {
+            return Err(SaveFileError::BadHeader);
+        }
+
+        let puzzle = Parser::new().parse(&mut source.as_bytes())?;
+        let mut save = SaveFile::new(puzzle.clone(), PlayerGrid::for_puzzle(&puzzle));
+        for line in lines {
+            let malformed = || SaveFileError::Malformed(line.to_string());
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            match key {
+                "marks" | "pencil" => {
+                    let marks = parse_marks(value.trim()).ok_or_else(malformed)?;
+                    if marks.len() != puzzle.width() * puzzle.height() {
+                        return Err(SaveFileError::DimensionMismatch);
+                    }
+                    for (i, mark) in marks.into_iter().enumerate() {
+                        let cell = Point::from_index(i, puzzle.width());
+                        match key {
+                            "marks" => save.player[cell] = mark,
+                            _ => save.player.set_pencil(cell, mark),
+                        }
+                    }
+                }
+                "move" => {
+                    let m = parse_move(value).ok_or_else(malformed)?;
+                    if !m.cell.within(puzzle.width(), puzzle.height()) {
+                        return Err(malformed());
+                    }
+                    save.moves.push(m);
+                }
+                // Everything else belongs to the puzzle definition.
+                _ => (),
+            }
+        }
+        Ok(save)
+    }
+}
+
+impl fmt::Display for SaveFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let puzzle = &self.puzzle;
+        writeln!(f, "{} {}", MAGIC, VERSION)?;
+        writeln!(f, "{}", Non.write(&puzzle.puzzle()))?;
+
+        let grid = |pencil: bool| -> String {
+            (0..puzzle.height())
+                .flat_map(|y| (0..puzzle.width()).map(move |x| Point::new(x, y)))
+                .map(|cell| {
+                    if pencil {
+                        self.player.pencil(cell)
+                    } else {
+                        self.player[cell]
+                    }
+                })
+                .map(mark_char)
+                .collect()
+        };
+        writeln!(f, "marks \"{}\"", grid(false))?;
+        writeln!(f, "pencil \"{}\"", grid(true))?;
+        for m in &self.moves {
+            let action = match m.mark {
+                Some(Mark::Filled) => "fill",
+                Some(Mark::Crossed) => "cross",
+                None => "clear",
+            };
+            let pencil = if m.pencil { " pencil" } else { "" };
+            writeln!(f, "move {} {} {}{}", m.cell.x, m.cell.y, action, pencil)?;
+        }
+        Ok(())
+    }
+}
+
+fn mark_char(mark: Option<Mark>) -> char {
+    match mark {
+        None => '?',
+        Some(Mark::Crossed) => '0',
+        Some(Mark::Filled) => '1',
+    }
+}
+
+fn parse_marks(s: &str) -> Option<Vec<Option<Mark>>> {
+    s.strip_prefix('"')?
+        .strip_suffix('"')?
+        .chars()
+        .map(|c| match c {
+            '?' => Some(None),
+            '0' => Some(Some(Mark::Crossed)),
+            '1' => Some(Some(Mark::Filled)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn parse_move(s: &str) -> Option<Move> {
+    let mut words = s.split_whitespace();
+    let x = words.next()?.parse().ok()?;
+    let y = words.next()?.parse().ok()?;
+    let mark = match words.next()? {
+        "fill" => Some(Mark::Filled),
+        "cross" => Some(Mark::Crossed),
+        "clear" => None,
+        _ => return None,
+    };
+    let pencil = match words.next() {
+        None => false,
+        Some("pencil") => true,
+        Some(_) => return None,
+    };
+    let cell = Point::new(x, y);
+    Some(Move { cell, mark, pencil }).filter(|_| words.next().is_none())
+}
@@ -0,0 +1,81 @@
+//! Game IDs of Pattern, in Simon Tatham's Portable Puzzle Collection.
+//!
+//! A game ID is the size, then the clues of the columns, then those of
+//! the rows, separated by `/`, with numbers in a clue separated by
+//! `.`, eg `3x2:1/1/2/2/1.1`.  The game name may come first, as in
+//! `pattern:3x2:...`, and cells given at the start of the game follow
+//! a comma, which this ignores.  There are no toroidal puzzles, and
+//! no titles, authors or goals.
+
+use super::{build, FormatError, PuzzleFormat};
+use crate::writer::{self, ClueStyle};
+use crate::{Constraint, Puzzle};
+
+/// Numbers separated by dots, as `3.1`.
+const CLUES: ClueStyle = ClueStyle {
+    separator: ".",
+    ..ClueStyle::compact()
+};
+
+pub struct Tatham;
+
+/// The size and clues of a game ID, without the game name.
+fn split(text: &str) -> Option<(usize, usize, &str)> {
+    let text = text.trim();
+    let text = text.strip_prefix("pattern:").unwrap_or(text);
+    let (size, clues) = text.split_once(':')?;
+    let (w, h) = size.split_once('x')?;
+    Some((w.parse().ok()?, h.parse().ok()?, clues))
+}
+
+impl PuzzleFormat for Tatham {
+    fn name(&self) -> &'static str {
+        "tatham"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["tatham"]
+    }
+
+    fn detect(&self, text: &str) -> bool {
+        split(text).is_some()
+    }
+
+    fn parse(&self, text: &str) -> Result<Puzzle, FormatError> {
+        let (width, height, clues) =
+            split(text).ok_or_else(|| FormatError::Syntax(String::from("not a game ID")))?;
+        let clues = clues.split(',').next().unwrap_or("");
+        let mut clues = clues
+            .split('/')
+            .map(|clue| {
+                if clue.is_empty() {
+                    Ok(Constraint::empty())
+                } else {
+                    clue.replace('.', ",").parse()
+                }
+            })
+            .collect::<Result<Vec<Constraint>, _>>()?;
+        if clues.len() != width + height {
+            return Err(FormatError::Syntax(format!(
+                "{} clues for a {}x{} grid",
+                clues.len(),
+                width,
+                height
+            )));
+        }
+        let rows = clues.split_off(width);
+        build(Some(width), Some(height), clues, rows)
+    }
+
+    fn write(&self, puzzle: &Puzzle) -> String {
+        let clues: Vec<String> = (puzzle.cols.iter().chain(&puzzle.rows))
+            .map(|c| writer::clue(c, &CLUES))
+            .collect();
+        format!(
+            "{}x{}:{}\n",
+            puzzle.width(),
+            puzzle.height(),
+            clues.join("/")
+        )
+    }
+}
@@ -0,0 +1,394 @@
+//! The XML format of <https://webpbn.com>, as described at
+//! <https://webpbn.com/pbn_fmt.html>.
+//!
+//! Only black and white puzzles of the first `<puzzle>` of a file are
+//! read: this crate doesn't solve puzzles in colors, though
+//! [write_colored] writes them.  The format has
+//! no unknown clues, which are written as clues of empty lines, nor
+//! unknown lengths, which are left out, and no toroidal puzzles.
+//! Goals are written for puzzles in colors only.
+
+use super::{build, FormatError, PuzzleFormat};
+use crate::image::ColoredPuzzle;
+use crate::{Constraint, Puzzle};
+
+use std::fmt::Write;
+
+pub struct Webpbn;
+
+impl PuzzleFormat for Webpbn {
+    fn name(&self) -> &'static str {
+        "webpbn"
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["xml", "pbn"]
+    }
+
+    fn detect(&self, text: &str) -> bool {
+        text.contains("<puzzleset") || text.contains("<puzzle")
+    }
+
+    fn parse(&self, text: &str) -> Result<Puzzle, FormatError> {
+        let mut reader = Reader {
+            text,
+            pos: 0,
+            pending_end: None,
+        };
+        let mut puzzle = None;
+        while let Some(event) = reader.next()? {
+            if let Event::Start(name, attributes) = event {
+                if name == "puzzle" {
+                    puzzle = Some(read_puzzle(&mut reader, &attributes)?);
+                    break;
+                }
+            }
+        }
+        puzzle.ok_or_else(|| syntax("no <puzzle> element"))
+    }
+
+    fn write(&self, puzzle: &Puzzle) -> String {
+        let mut ret = String::from("<?xml version=\"1.0\"?>\n");
+        ret.push_str("<!DOCTYPE pbn SYSTEM \"https://webpbn.com/pbn-0.3.dtd\">\n");
+        ret.push_str("<puzzleset>\n<puzzle type=\"grid\" defaultcolor=\"black\">\n");
+        if let Some(title) = &puzzle.title {
+            writeln!(ret, "<title>{}</title>", escape(title)).unwrap();
+        }
+        if let Some(author) = &puzzle.author {
+            writeln!(ret, "<author>{}</author>", escape(author)).unwrap();
+        }
+        ret.push_str("<color name=\"white\" char=\".\">fff</color>\n");
+        ret.push_str("<color name=\"black\" char=\"X\">000</color>\n");
+        for (kind, clues) in [("columns", &puzzle.cols), ("rows", &puzzle.rows)] {
+            writeln!(ret, "<clues type=\"{}\">", kind).unwrap();
+            for clue in clues.iter() {
+                ret.push_str("<line>");
+                clue.lengths()
+                    .flatten()
+                    .for_each(|n| write!(ret, "<count>{}</count>", n).unwrap());
+                ret.push_str("</line>\n");
+            }
+            ret.push_str("</clues>\n");
+        }
+        ret.push_str("</puzzle>\n</puzzleset>\n");
+        ret
+    }
+}
+
+/// The characters of the colors of a [ColoredPuzzle] in its goal,
+/// after `.` for the background.
+const COLOR_CHARS: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// Write a puzzle in colors, with its goal.  Colors are called `c0`,
+/// `c1`... after their index in the palette.
+pub fn write_colored(puzzle: &ColoredPuzzle) -> String {
+    let mut chars = COLOR_CHARS.chars();
+    let chars: Vec<char> = (0..puzzle.palette.len())
+        .map(|i| match i == puzzle.background {
+            true => '.',
+            false => chars.next().expect("too many colors"),
+        })
+        .collect();
+    let default = (0..puzzle.palette.len()).find(|i| *i != puzzle.background);
+
+    let mut ret = String::from("<?xml version=\"1.0\"?>\n");
+    ret.push_str("<!DOCTYPE pbn SYSTEM \"https://webpbn.com/pbn-0.3.dtd\">\n");
+    write!(ret, "<puzzleset>\n<puzzle type=\"grid\"").unwrap();
+    if let Some(default) = default {
+        write!(ret, " defaultcolor=\"c{}\"", default).unwrap();
+    }
+    writeln!(ret, " backgroundcolor=\"c{}\">", puzzle.background).unwrap();
+    if let Some(title) = &puzzle.title {
+        writeln!(ret, "<title>{}</title>", escape(title)).unwrap();
+    }
+    for (i, [r, g, b]) in puzzle.palette.iter().enumerate() {
+        writeln!(
+            ret,
+            "<color name=\"c{}\" char=\"{}\">{:02x}{:02x}{:02x}</color>",
+            i, chars[i], r, g, b
+        )
+        .unwrap();
+    }
+    for (kind, clues) in [("columns", &puzzle.cols), ("rows", &puzzle.rows)] {
+        writeln!(ret, "<clues type=\"{}\">", kind).unwrap();
+        for clue in clues.iter() {
+            ret.push_str("<line>");
+            for run in clue {
+                write!(ret, "<count color=\"c{}\">{}</count>", run.color, run.len).unwrap();
+            }
+            ret.push_str("</line>\n");
+        }
+        ret.push_str("</clues>\n");
+    }
+    ret.push_str("<solution type=\"goal\">\n<image>\n");
+    for row in puzzle.goal.chunks(puzzle.width().max(1)) {
+        writeln!(
+            ret,
+            "|{}|",
+            row.iter().map(|c| chars[*c]).collect::<String>()
+        )
+        .unwrap();
+    }
+    ret.push_str("</image>\n</solution>\n");
+    ret.push_str("</puzzle>\n</puzzleset>\n");
+    ret
+}
+
+/// Read a `<puzzle>` element, up to its end tag.
+fn read_puzzle(
+    reader: &mut Reader,
+    attributes: &[(String, String)],
+) -> Result<Puzzle, FormatError> {
+    let attribute = |name: &str| {
+        attributes
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    };
+    if !matches!(attribute("type"), None | Some("grid")) {
+        return Err(FormatError::Unsupported(String::from("not a grid puzzle")));
+    }
+    let default_color = attribute("defaultcolor").unwrap_or("black").to_string();
+
+    let (mut title, mut author) = (None, None);
+    let (mut rows, mut cols) = (None, None);
+    loop {
+        match reader
+            .next()?
+            .ok_or_else(|| syntax("unterminated <puzzle>"))?
+        {
+            Event::Start(name, attributes) => match name.as_str() {
+                "title" => title = Some(reader.text_content()?),
+                "author" => author = Some(reader.text_content()?),
+                "clues" => {
+                    let clues = read_clues(reader, &default_color)?;
+                    match attributes
+                        .iter()
+                        .find(|(k, _)| k == "type")
+                        .map(|(_, v)| v.as_str())
+                    {
+                        Some("rows") => rows = Some(clues),
+                        Some("columns") => cols = Some(clues),
+                        _ => return Err(syntax("<clues> without a type")),
+                    }
+                }
+                _ => reader.skip_element()?,
+            },
+            Event::End(name) if name == "puzzle" => break,
+            _ => (),
+        }
+    }
+
+    let (Some(rows), Some(cols)) = (rows, cols) else {
+        return Err(syntax("missing row or column clues"));
+    };
+    let mut puzzle = build(None, None, cols, rows)?;
+    puzzle.title = title;
+    puzzle.author = author;
+    Ok(puzzle)
+}
+
+/// Read the `<line>`s of a `<clues>` element, up to its end tag.
+fn read_clues(reader: &mut Reader, default_color: &str) -> Result<Vec<Constraint>, FormatError> {
+    let mut ret = vec![];
+    let mut runs = vec![];
+    loop {
+        match reader
+            .next()?
+            .ok_or_else(|| syntax("unterminated <clues>"))?
+        {
+            Event::Start(name, attributes) if name == "count" => {
+                let color = attributes.iter().find(|(k, _)| k == "color");
+                if color.is_some_and(|(_, c)| c != default_color) {
+                    return Err(FormatError::Unsupported(String::from("puzzle in colors")));
+                }
+                let count = reader.text_content()?;
+                runs.push(count.trim().parse().map_err(|_| syntax("bad <count>"))?);
+            }
+            Event::Start(name, _) if name == "line" => runs.clear(),
+            Event::End(name) if name == "line" => {
+                ret.push(Constraint::new(std::mem::take(&mut runs))?)
+            }
+            Event::End(name) if name == "clues" => return Ok(ret),
+            Event::Start(_, _) => reader.skip_element()?,
+            _ => (),
+        }
+    }
+}
+
+fn syntax(what: &str) -> FormatError {
+    FormatError::Syntax(what.to_string())
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Replace the entities of `s`.
+fn unescape(s: &str) -> Result<String, FormatError> {
+    let mut ret = String::new();
+    let mut rest = s;
+    while let Some(amp) = rest.find('&') {
+        ret.push_str(&rest[..amp]);
+        let semi = rest[amp..].find(';').ok_or_else(|| syntax("bad entity"))? + amp;
+        let entity = &rest[amp + 1..semi];
+        let c = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => match entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+            {
+                Some(hex) => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+                None => entity
+                    .strip_prefix('#')
+                    .and_then(|n| n.parse().ok())
+                    .and_then(char::from_u32),
+            },
+        };
+        ret.push(c.ok_or_else(|| syntax(&format!("unknown entity &{};", entity)))?);
+        rest = &rest[semi + 1..];
+    }
+    ret.push_str(rest);
+    Ok(ret)
+}
+
+// * Reading XML
+
+/// What [Reader::next] finds: tags, with their attributes, or text.
+/// Empty elements `<a/>` come as a start then an end.
+enum Event {
+    Start(String, Vec<(String, String)>),
+    End(String),
+    Text(String),
+}
+
+/// Just enough XML for puzzles: no namespaces, nor validation.
+struct Reader<'a> {
+    text: &'a str,
+    pos: usize,
+    /// The end of the empty element just started.
+    pending_end: Option<String>,
+}
+
+impl Reader<'_> {
+    fn rest(&self) -> &str {
+        &self.text[self.pos..]
+    }
+
+    /// Skip past the next `end`.
+    fn skip_past(&mut self, end: &str) -> Result<(), FormatError> {
+        let i = self
+            .rest()
+            .find(end)
+            .ok_or_else(|| syntax(&format!("missing {}", end)))?;
+        self.pos += i + end.len();
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<Event>, FormatError> {
+        if let Some(name) = self.pending_end.take() {
+            return Ok(Some(Event::End(name)));
+        }
+        loop {
+            let rest = self.rest();
+            if rest.is_empty() {
+                return Ok(None);
+            }
+            if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if rest.starts_with("<![CDATA[") {
+                self.pos += 9;
+                let end = self
+                    .rest()
+                    .find("]]>")
+                    .ok_or_else(|| syntax("missing ]]>"))?;
+                let text = self.rest()[..end].to_string();
+                self.pos += end + 3;
+                return Ok(Some(Event::Text(text)));
+            } else if rest.starts_with("<!") {
+                self.skip_past(">")?;
+            } else if let Some(tag) = rest.strip_prefix("</") {
+                let end = tag.find('>').ok_or_else(|| syntax("unterminated tag"))?;
+                let name = tag[..end].trim().to_string();
+                self.pos += end + 3;
+                return Ok(Some(Event::End(name)));
+            } else if rest.starts_with('<') {
+                return self.start_tag().map(Some);
+            } else {
+                let end = rest.find('<').unwrap_or(rest.len());
+                let text = unescape(&rest[..end])?;
+                self.pos += end;
+                return Ok(Some(Event::Text(text)));
+            }
+        }
+    }
+
+    /// Read a start tag.  After an empty element tag, the next event
+    /// is its end.
+    fn start_tag(&mut self) -> Result<Event, FormatError> {
+        let rest = self.rest();
+        let end = rest.find('>').ok_or_else(|| syntax("unterminated tag"))?;
+        let mut tag = &rest[1..end];
+        let empty = tag.ends_with('/');
+        if empty {
+            tag = &tag[..tag.len() - 1];
+        }
+        let name_end = tag.find(|c: char| c.is_whitespace()).unwrap_or(tag.len());
+        let name = tag[..name_end].to_string();
+
+        let mut attributes = vec![];
+        let mut attrs = tag[name_end..].trim_start();
+        while !attrs.is_empty() {
+            let eq = attrs.find('=').ok_or_else(|| syntax("bad attribute"))?;
+            let key = attrs[..eq].trim().to_string();
+            let value = attrs[eq + 1..].trim_start();
+            let quote = value.chars().next().filter(|q| *q == '"' || *q == '\'');
+            let quote = quote.ok_or_else(|| syntax("unquoted attribute"))?;
+            let close = value[1..]
+                .find(quote)
+                .ok_or_else(|| syntax("bad attribute"))?
+                + 1;
+            attributes.push((key, unescape(&value[1..close])?));
+            attrs = value[close + 1..].trim_start();
+        }
+
+        self.pos += end + 1;
+        if empty {
+            self.pending_end = Some(name.clone());
+        }
+        Ok(Event::Start(name, attributes))
+    }
+
+    /// The text inside the element just started, up to its end tag.
+    fn text_content(&mut self) -> Result<String, FormatError> {
+        let mut ret = String::new();
+        loop {
+            match self.next()?.ok_or_else(|| syntax("unterminated element"))? {
+                Event::Text(text) => ret.push_str(&text),
+                Event::End(_) => return Ok(ret),
+                Event::Start(_, _) => self.skip_element()?,
+            }
+        }
+    }
+
+    /// Skip the element just started, up to its end tag.
+    fn skip_element(&mut self) -> Result<(), FormatError> {
+        let mut depth = 1;
+        while depth > 0 {
+            match self.next()?.ok_or_else(|| syntax("unterminated element"))? {
+                Event::Start(_, _) => depth += 1,
+                Event::End(_) => depth -= 1,
+                Event::Text(_) => (),
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,222 @@
+//! Making new puzzles, from scratch or out of existing ones.
+
+use crate::analysis::{self, Difficulty, Uniqueness};
+use crate::rng::Rng;
+use crate::{
+    CellState, Constraint, Grid, Nonogram, Point, Puzzle, SolveError, Solver, SolverConfig,
+};
+
+// * Blotted clues
+
+/// Replace as many clues of `puzzle` as possible by unknown clues,
+/// `?`, while its solution stays unique, as in puzzles with blotted
+/// clues.  Lines are tried in an order shuffled from `seed`, so that
+/// different seeds blot different clues.  This is None if the solution
+/// of `puzzle` isn't unique to begin with.
+pub fn blot_clues(puzzle: &Puzzle, seed: u64) -> Option<Puzzle> {
+    if !is_unique(puzzle) {
+        return None;
+    }
+    let mut lines: Vec<(bool, usize)> = (0..puzzle.height())
+        .map(|y| (true, y))
+        .chain((0..puzzle.width()).map(|x| (false, x)))
+        .collect();
+    let mut rng = Rng::new(seed);
+    for i in (1..lines.len()).rev() {
+        lines.swap(i, rng.below(i + 1));
+    }
+
+    let mut ret = puzzle.clone();
+    for (is_row, i) in lines {
+        let mut blotted = ret.clone();
+        let clues = if is_row {
+            &mut blotted.rows
+        } else {
+            &mut blotted.cols
+        };
+        clues[i] = Constraint::unknown();
+        if is_unique(&blotted) {
+            ret = blotted;
+        }
+    }
+    Some(ret)
+}
+
+// * Random puzzles
+
+/// How many random grids [unique] and [with_difficulty] try.
+const ATTEMPTS: usize = 1000;
+
+/// How many cells [unique] and [with_difficulty] flip in a grid to
+/// make its solution unique, before trying another grid.
+const REPAIRS: usize = 20;
+
+/// A new puzzle of `width` by `height` cells from a random grid, which
+/// is its goal.  Its solution may not be unique.  The same seed gives
+/// the same puzzle.
+pub fn random((width, height): (usize, usize), seed: u64) -> Puzzle {
+    let cells = random_cells(&mut Rng::new(seed), width * height);
+    Puzzle::from_solution(&Grid::from_cells(width, height, cells))
+}
+
+/// A new puzzle of `width` by `height` cells with a unique solution,
+/// which is its goal.  The same seed gives the same puzzle.
+///
+/// This draws random grids, and repairs those whose puzzle has several
+/// solutions by flipping a cell on which two of them disagree.  This
+/// is None if none could be repaired, after many tries.
+pub fn unique(size: (usize, usize), seed: u64) -> Option<Puzzle> {
+    generate(size, seed, |_| true)
+}
+
+/// A new puzzle of `width` by `height` cells that [analysis::difficulty]
+/// rates `target`, with its solution as goal, eg for a game to offer
+/// levels.  The same seed gives the same puzzle.
+///
+/// This generates puzzles as [unique] does, until one is rated
+/// `target`.  This is None if none was, after many tries: small grids
+/// are rarely hard.  Hard puzzles take a while, as checking their
+/// uniqueness needs search.
+pub fn with_difficulty(target: Difficulty, size: (usize, usize), seed: u64) -> Option<Puzzle> {
+    generate(size, seed, |nono| {
+        analysis::rate(analysis::classification(nono)) == Some(target)
+    })
+}
+
+/// The first repaired puzzle with a unique solution that `accept`s.
+fn generate(
+    (width, height): (usize, usize),
+    seed: u64,
+    accept: impl Fn(&Nonogram) -> bool,
+) -> Option<Puzzle> {
+    let mut rng = Rng::new(seed);
+    for _ in 0..ATTEMPTS {
+        let mut cells = random_cells(&mut rng, width * height);
+        for _ in 0..REPAIRS {
+            let puzzle = Puzzle::from_solution(&Grid::from_cells(width, height, cells.clone()));
+            let nono = Nonogram::from(puzzle.clone());
+            match analysis::uniqueness(&nono) {
+                Uniqueness::Unique(_) if accept(&nono) => return Some(puzzle),
+                Uniqueness::Unique(_) | Uniqueness::NoSolution => break,
+                Uniqueness::Multiple(a, b) => {
+                    let diff: Vec<usize> = (0..cells.len())
+                        .filter(|i| a.cells()[*i] != b.cells()[*i])
+                        .collect();
+                    let i = diff[rng.below(diff.len())];
+                    cells[i] = cells[i].opposite();
+                }
+            }
+        }
+    }
+    None
+}
+
+/// `len` random cells.  Denser grids give easier puzzles, so the
+/// density varies too.
+fn random_cells(rng: &mut Rng, len: usize) -> Vec<CellState> {
+    let density = 0.35 + 0.3 * rng.fraction();
+    (0..len)
+        .map(|_| match rng.fraction() < density {
+            true => CellState::Filled,
+            false => CellState::Empty,
+        })
+        .collect()
+}
+
+// * Repairs
+
+/// How many flips [repair] weighs at each step, among the cells with
+/// the least visual impact.
+const CANDIDATES: usize = 8;
+
+/// A puzzle fixed by [repair].
+#[derive(Clone, Debug)]
+pub struct Repair {
+    pub puzzle: Puzzle,
+    /// The cells of the goal that were flipped, in order.
+    pub flipped: Vec<Point>,
+}
+
+/// Flip cells of the goal of `puzzle` until line logic alone solves
+/// it, so that its solution is also unique, eg for puzzles made from
+/// images.  None if `puzzle` has no goal, or if it took flipping more
+/// than a quarter of the cells.
+///
+/// At each step, the cells line logic leaves undecided are the
+/// candidates.  Of those with the fewest neighbours of the same value,
+/// which change the picture least, this flips the one after which line
+/// logic leaves the fewest cells undecided.  So there are few flips,
+/// though not always as few as possible.
+pub fn repair(puzzle: &Puzzle) -> Option<Repair> {
+    let goal = puzzle.goal.as_ref()?;
+    let (w, h) = (goal.width(), goal.height());
+    let mut cells = goal.cells().to_vec();
+    let mut flipped = vec![];
+    let mut undecided = line_undecided(w, h, &cells);
+    while !undecided.is_empty() {
+        if flipped.len() > w * h / 4 {
+            return None;
+        }
+        // The number of neighbours of the same value, out of the grid
+        // being empty.
+        let impact = |i: usize| {
+            let p = Point::from_index(i, w);
+            let neighbour = |dx: isize, dy: isize| match p.offset(dx, dy) {
+                Some(n) if n.within(w, h) => cells[n.index(w)],
+                _ => CellState::Empty,
+            };
+            (-1..=1)
+                .flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+                .filter(|(dx, dy)| (*dx, *dy) != (0, 0) && neighbour(*dx, *dy) == cells[i])
+                .count()
+        };
+        let mut candidates = undecided.clone();
+        candidates.sort_by_key(|i| impact(*i));
+        candidates.truncate(CANDIDATES);
+        let (i, left) = candidates
+            .into_iter()
+            .map(|i| {
+                let mut flip = cells.clone();
+                flip[i] = flip[i].opposite();
+                (i, line_undecided(w, h, &flip))
+            })
+            .min_by_key(|(_, left)| left.len())
+            .unwrap();
+        cells[i] = cells[i].opposite();
+        flipped.push(Point::from_index(i, w));
+        undecided = left;
+    }
+
+    let mut ret = Puzzle::from_solution(&Grid::from_cells(w, h, cells));
+    ret.title = puzzle.title.clone();
+    ret.author = puzzle.author.clone();
+    Some(Repair {
+        puzzle: ret,
+        flipped,
+    })
+}
+
+/// The indices of the cells that line logic leaves undecided in the
+/// puzzle of the grid `cells`.
+fn line_undecided(width: usize, height: usize, cells: &[CellState]) -> Vec<usize> {
+    let puzzle = Puzzle::from_solution(&Grid::from_cells(width, height, cells.to_vec()));
+    let config = SolverConfig {
+        probe_depth: 0,
+        search: false,
+        ..SolverConfig::default()
+    };
+    match Solver::solve_puzzle(&puzzle, config) {
+        Err(SolveError::Stalled(grid)) => (grid.cells().iter().enumerate())
+            .filter(|(_, c)| **c == CellState::Undecided)
+            .map(|(i, _)| i)
+            .collect(),
+        _ => vec![],
+    }
+}
+
+fn is_unique(puzzle: &Puzzle) -> bool {
+    matches!(
+        analysis::uniqueness(&Nonogram::from(puzzle.clone())),
+        Uniqueness::Unique(_)
+    )
+}
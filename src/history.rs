@@ -0,0 +1,180 @@
+//! Undo and redo for grids.
+//!
+//! A [History] is a journal of the cell changes made through it,
+//! which can be walked back and forth.  Returning to an earlier
+//! point only replays the changes since, instead of copying the
+//! whole grid: the solver uses it to backtrack, and game frontends
+//! for their undo and redo commands.
+
+use crate::{CellState, Mark, Move, Nonogram, PlayerGrid};
+
+use alloc::{vec, vec::Vec};
+
+/// Anything with cells a [History] can change, by index.
+pub trait Cells {
+    type Cell: Copy + PartialEq;
+
+    fn cell(&self, index: usize) -> Self::Cell;
+    fn set_cell(&mut self, index: usize, value: Self::Cell);
+}
+
+impl<T: Copy + PartialEq> Cells for [T] {
+    type Cell = T;
+
+    fn cell(&self, index: usize) -> T {
+        self[index]
+    }
+
+    fn set_cell(&mut self, index: usize, value: T) {
+        self[index] = value
+    }
+}
+
+/// The grid, row by row.
+impl Cells for Nonogram {
+    type Cell = CellState;
+
+    fn cell(&self, index: usize) -> CellState {
+        self.cells[index]
+    }
+
+    fn set_cell(&mut self, index: usize, value: CellState) {
+        self.cells[index] = value
+    }
+}
+
+/// The marks row by row, then the pencil marks row by row; see
+/// [PlayerGrid::cell_index].
+impl Cells for PlayerGrid {
+    type Cell = Option<Mark>;
+
+    fn cell(&self, index: usize) -> Option<Mark> {
+        let (cell, pencil) = self.index_to_cell(index);
+        if pencil {
+            self.pencil(cell)
+        } else {
+            self[cell]
+        }
+    }
+
+    fn set_cell(&mut self, index: usize, value: Option<Mark>) {
+        let (cell, pencil) = self.index_to_cell(index);
+        if pencil {
+            self.set_pencil(cell, value)
+        } else {
+            self[cell] = value
+        }
+    }
+}
+
+/// A change of the cell at `index`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Change<T> {
+    pub index: usize,
+    pub before: T,
+    pub after: T,
+}
+
+/// A position in a [History], to [History::revert_to] later.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HistoryPoint(usize);
+
+/// A journal of changes, with undo and redo.
+///
+/// Making a change after undoing some forgets the undone changes, as
+/// in any editor.  A [HistoryPoint] among them is then meaningless.
+#[derive(Clone, Debug)]
+pub struct History<T> {
+    changes: Vec<Change<T>>,
+    /// How many of `changes` are applied.
+    position: usize,
+}
+
+impl<T> Default for History<T> {
+    fn default() -> Self {
+        History {
+            changes: vec![],
+            position: 0,
+        }
+    }
+}
+
+impl<T: Copy + PartialEq> History<T> {
+    pub fn new() -> History<T> {
+        History::default()
+    }
+
+    /// Set the cell at `index` of `cells` to `value`, and record the
+    /// change.  Setting a cell to its current value records nothing.
+    pub fn set<C: Cells<Cell = T> + ?Sized>(&mut self, cells: &mut C, index: usize, value: T) {
+        let before = cells.cell(index);
+        if before == value {
+            return;
+        }
+        cells.set_cell(index, value);
+        self.changes.truncate(self.position);
+        self.changes.push(Change {
+            index,
+            before,
+            after: value,
+        });
+        self.position += 1;
+    }
+
+    /// Revert the last change on `cells`, and return it.
+    pub fn undo<C: Cells<Cell = T> + ?Sized>(&mut self, cells: &mut C) -> Option<Change<T>> {
+        let change = *self.changes[..self.position].last()?;
+        cells.set_cell(change.index, change.before);
+        self.position -= 1;
+        Some(change)
+    }
+
+    /// Reapply the last undone change on `cells`, and return it.
+    pub fn redo<C: Cells<Cell = T> + ?Sized>(&mut self, cells: &mut C) -> Option<Change<T>> {
+        let change = *self.changes.get(self.position)?;
+        cells.set_cell(change.index, change.after);
+        self.position += 1;
+        Some(change)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.position > 0
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.position < self.changes.len()
+    }
+
+    /// The current position, after the changes made so far.
+    pub fn point(&self) -> HistoryPoint {
+        HistoryPoint(self.position)
+    }
+
+    /// Undo, or redo, changes on `cells` until back at `point`.
+    pub fn revert_to<C: Cells<Cell = T> + ?Sized>(&mut self, cells: &mut C, point: HistoryPoint) {
+        while self.position > point.0 {
+            self.undo(cells);
+        }
+        while self.position < point.0 && self.redo(cells).is_some() {}
+    }
+
+    /// The changes made so far, oldest first, without the undone
+    /// ones.
+    pub fn changes(&self) -> &[Change<T>] {
+        &self.changes[..self.position]
+    }
+
+    /// Forget every change, leaving the cells as they are.
+    pub fn clear(&mut self) {
+        self.changes.clear();
+        self.position = 0;
+    }
+}
+
+impl History<Option<Mark>> {
+    /// Make move `m` on `grid`, and record it.
+    pub fn play(&mut self, grid: &mut PlayerGrid, m: Move) {
+        let index = grid.cell_index(m.cell, m.pencil);
+        self.set(grid, index, m.mark);
+    }
+}
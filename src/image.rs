@@ -0,0 +1,487 @@
+//! Puzzles from pictures.
+//!
+//! An [Image] is read from a PNM file: PBM, PGM or PPM, plain or raw,
+//! which are the image formats this crate reads without dependencies.
+//! Other images convert to PNM with most image tools.
+//!
+//! [to_puzzle] makes a black and white puzzle of an image, by a
+//! threshold or by dithering, usually after [Image::resize] makes it
+//! small enough to solve.
+//!
+//! [ColoredPuzzle::from_image] reduces an image to a few colors, see
+//! [quantize], and takes the clues of each line in these colors.  This
+//! crate doesn't solve puzzles in colors: they are written out with
+//! [crate::formats::webpbn::write_colored], for other solvers.
+
+use crate::{CellState, Grid, Puzzle};
+
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// A color, as red, green and blue from 0 to 255.
+pub type Rgb = [u8; 3];
+
+/// How many colors [ColoredPuzzle::from_image] takes at most,
+/// background included.
+pub const MAX_COLORS: usize = 32;
+
+// * Images
+
+/// A picture, as a color per pixel, row by row.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgb>,
+}
+
+impl Image {
+    /// An image of `width` by `height` pixels, row by row.
+    pub fn new(width: usize, height: usize, pixels: Vec<Rgb>) -> Image {
+        assert_eq!(pixels.len(), width * height);
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> Rgb {
+        self.pixels[y * self.width + x]
+    }
+
+    /// Every pixel, row by row.
+    pub fn pixels(&self) -> &[Rgb] {
+        &self.pixels
+    }
+
+    /// The image scaled to `width` by `height` pixels.  Each pixel is
+    /// the mean of the pixels it covers, so that downscaling keeps
+    /// shapes thinner than a pixel as shades.
+    pub fn resize(&self, width: usize, height: usize) -> Image {
+        // The pixels of `len` covered by pixel `i` of `new_len`.
+        let span = |i: usize, len: usize, new_len: usize| {
+            let start = i * len / new_len;
+            start..((i + 1) * len / new_len).max(start + 1)
+        };
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let (xs, ys) = (span(x, self.width, width), span(y, self.height, height));
+                let n = (xs.len() * ys.len()) as u64;
+                let mut sum = [0u64; 3];
+                for y in ys {
+                    for x in xs.clone() {
+                        let p = self.pixel(x, y);
+                        (0..3).for_each(|c| sum[c] += p[c] as u64);
+                    }
+                }
+                pixels.push(sum.map(|s| ((s + n / 2) / n) as u8));
+            }
+        }
+        Image::new(width, height, pixels)
+    }
+
+    /// The image scaled to `width` or `height` pixels, keeping its
+    /// aspect ratio when only one is given.
+    pub fn resize_to(&self, width: Option<usize>, height: Option<usize>) -> Image {
+        let scaled = |n: usize, from: usize, to: usize| ((n * to + from / 2) / from.max(1)).max(1);
+        let (width, height) = match (width, height) {
+            (Some(w), Some(h)) => (w, h),
+            (Some(w), None) => (w, scaled(self.height, self.width, w)),
+            (None, Some(h)) => (scaled(self.width, self.height, h), h),
+            (None, None) => return self.clone(),
+        };
+        self.resize(width, height)
+    }
+
+    /// Read a PNM image, `P1` to `P6`.  Samples are scaled to 0–255.
+    pub fn read_pnm(data: &[u8]) -> Result<Image, ImageError> {
+        let mut reader = PnmReader { data, pos: 0 };
+        let kind = match reader.token() {
+            Some(magic) if magic.len() == 2 && magic[0] == b'P' => magic[1],
+            _ => return Err(ImageError::Unsupported(String::from("not a PNM image"))),
+        };
+        if !(b'1'..=b'6').contains(&kind) {
+            return Err(ImageError::Unsupported(format!(
+                "PNM type P{}",
+                kind as char
+            )));
+        }
+        let width = reader.number("width")?;
+        let height = reader.number("height")?;
+        let bitmap = matches!(kind, b'1' | b'4');
+        let maxval = if bitmap {
+            1
+        } else {
+            reader.number("maximum value")?
+        };
+        if !(1..=65535).contains(&maxval) {
+            return Err(malformed("maximum value out of range"));
+        }
+        let channels = if matches!(kind, b'3' | b'6') { 3 } else { 1 };
+        let count = width
+            .checked_mul(height)
+            .and_then(|n| n.checked_mul(channels))
+            .ok_or_else(|| malformed("image too large"))?;
+
+        let samples: Vec<usize> = match kind {
+            // Plain PBM digits need no space between them.
+            b'1' => {
+                let mut ret = Vec::with_capacity(count);
+                while ret.len() < count {
+                    match reader.next_byte() {
+                        Some(b'0') => ret.push(0),
+                        Some(b'1') => ret.push(1),
+                        Some(c) if c.is_ascii_whitespace() => (),
+                        _ => return Err(malformed("bad or missing pixel")),
+                    }
+                }
+                ret
+            }
+            b'2' | b'3' => (0..count)
+                .map(|_| reader.number("sample"))
+                .collect::<Result<_, _>>()?,
+            b'4' => {
+                let raster = reader.raster(height * width.div_ceil(8))?;
+                let row = |y: usize| &raster[y * width.div_ceil(8)..];
+                (0..height)
+                    .flat_map(|y| (0..width).map(move |x| (x, y)))
+                    .map(|(x, y)| ((row(y)[x / 8] >> (7 - x % 8)) & 1) as usize)
+                    .collect()
+            }
+            _ if maxval < 256 => reader.raster(count)?.iter().map(|b| *b as usize).collect(),
+            _ => (reader.raster(2 * count)?.chunks(2))
+                .map(|b| (b[0] as usize) << 8 | b[1] as usize)
+                .collect(),
+        };
+        if samples.iter().any(|s| *s > maxval) {
+            return Err(malformed("sample above the maximum value"));
+        }
+
+        // In PBM, 1 is black.
+        let scale = |s: usize| match bitmap {
+            true => (1 - s) as u8 * 255,
+            false => (s * 255 / maxval) as u8,
+        };
+        let pixels = match channels {
+            3 => (samples.chunks(3))
+                .map(|c| [scale(c[0]), scale(c[1]), scale(c[2])])
+                .collect(),
+            _ => samples.iter().map(|s| [scale(*s); 3]).collect(),
+        };
+        Ok(Image::new(width, height, pixels))
+    }
+
+    /// Read the PNM image at `path`.
+    pub fn read_file(path: &Path) -> Result<Image, ImageError> {
+        Image::read_pnm(&fs::read(path)?)
+    }
+}
+
+/// Reads the header of a PNM file, then its raster.
+struct PnmReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> PnmReader<'a> {
+    fn next_byte(&mut self) -> Option<u8> {
+        let ret = *self.data.get(self.pos)?;
+        self.pos += 1;
+        Some(ret)
+    }
+
+    /// The next word, after whitespace and `#` comments.
+    fn token(&mut self) -> Option<&'a [u8]> {
+        loop {
+            match self.data.get(self.pos)? {
+                b'#' => {
+                    let rest = &self.data[self.pos..];
+                    self.pos += rest.iter().position(|c| *c == b'\n').unwrap_or(rest.len());
+                }
+                c if c.is_ascii_whitespace() => self.pos += 1,
+                _ => break,
+            }
+        }
+        let start = self.pos;
+        while self
+            .data
+            .get(self.pos)
+            .is_some_and(|c| !c.is_ascii_whitespace())
+        {
+            self.pos += 1;
+        }
+        Some(&self.data[start..self.pos])
+    }
+
+    fn number(&mut self, what: &str) -> Result<usize, ImageError> {
+        (self.token())
+            .and_then(|t| std::str::from_utf8(t).ok()?.parse().ok())
+            .ok_or_else(|| malformed(&format!("bad or missing {}", what)))
+    }
+
+    /// The `len` bytes of raster, after the single whitespace that
+    /// ends the header.
+    fn raster(&mut self, len: usize) -> Result<&'a [u8], ImageError> {
+        let start = self.pos + 1;
+        (self.data.get(start..start + len)).ok_or_else(|| malformed("truncated raster"))
+    }
+}
+
+#[derive(Debug)]
+pub enum ImageError {
+    Io(io::Error),
+    /// The file isn't in a format this crate reads.
+    Unsupported(String),
+    /// The file is in a format this crate reads, but broken.
+    Malformed(String),
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageError::Io(_) => f.write_str("Could not read the image."),
+            ImageError::Unsupported(what) => write!(f, "Unsupported image: {}.", what),
+            ImageError::Malformed(what) => write!(f, "Malformed image: {}.", what),
+        }
+    }
+}
+
+impl error::Error for ImageError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ImageError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ImageError {
+    fn from(value: io::Error) -> Self {
+        ImageError::Io(value)
+    }
+}
+
+fn malformed(what: &str) -> ImageError {
+    ImageError::Malformed(what.to_string())
+}
+
+// * Black and white
+
+/// How [to_puzzle] tells filled cells.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Method {
+    /// Pixels darker than this luminance, from 0 to 255, are filled.
+    Threshold(u8),
+    /// Floyd–Steinberg dithering around this threshold: the error of
+    /// each pixel is spread on the next ones, so that grays come out as
+    /// patterns, eg for photographs.
+    Dither(u8),
+}
+
+impl Default for Method {
+    fn default() -> Method {
+        Method::Threshold(128)
+    }
+}
+
+/// The black and white puzzle of `image`, a cell per pixel, with the
+/// picture as goal.
+pub fn to_puzzle(image: &Image, method: Method) -> Puzzle {
+    let (w, h) = (image.width, image.height);
+    let mut levels: Vec<f64> = image.pixels.iter().map(luminance).collect();
+    let threshold = match method {
+        Method::Threshold(t) | Method::Dither(t) => t as f64,
+    };
+    let mut cells = Vec::with_capacity(w * h);
+    for i in 0..levels.len() {
+        let filled = levels[i] < threshold;
+        cells.push(CellState::from(filled));
+        if let Method::Dither(_) = method {
+            let error = levels[i] - if filled { 0.0 } else { 255.0 };
+            let (x, y) = (i % w, i / w);
+            let mut spread = |dx: isize, dy: usize, weight: f64| {
+                let x = x.checked_add_signed(dx).filter(|x| *x < w);
+                if let Some(x) = x.filter(|_| y + dy < h) {
+                    levels[(y + dy) * w + x] += error * weight / 16.0;
+                }
+            };
+            spread(1, 0, 7.0);
+            spread(-1, 1, 3.0);
+            spread(0, 1, 5.0);
+            spread(1, 1, 1.0);
+        }
+    }
+    Puzzle::from_solution(&Grid::from_cells(w, h, cells))
+}
+
+/// The luminance of `color`, from 0 to 255.
+fn luminance(color: &Rgb) -> f64 {
+    (299 * color[0] as u32 + 587 * color[1] as u32 + 114 * color[2] as u32) as f64 / 1000.0
+}
+
+// * Colors
+
+/// Reduce `image` to at most `colors` colors, by median cut: the set
+/// of pixels is split in two around the median of its widest channel,
+/// then the widest of the parts, and so on, and each part is replaced
+/// by its mean color.
+///
+/// This returns the palette, from the lightest color to the darkest,
+/// and the index in the palette of each pixel.
+pub fn quantize(image: &Image, colors: usize) -> (Vec<Rgb>, Vec<usize>) {
+    assert!(colors > 0, "no colors");
+    let spread = |pixels: &[Rgb]| {
+        (0..3)
+            .map(|c| {
+                let values = pixels.iter().map(|p| p[c]);
+                let range = values.clone().max().unwrap_or(0) - values.min().unwrap_or(0);
+                (range, c)
+            })
+            .max()
+            .unwrap_or((0, 0))
+    };
+    let mut boxes = vec![image.pixels.clone()];
+    while boxes.len() < colors {
+        let Some((i, (_, channel))) = (boxes.iter().map(|b| spread(b)).enumerate())
+            .filter(|(_, (range, _))| *range > 0)
+            .max_by_key(|(_, (range, _))| *range)
+        else {
+            break;
+        };
+        let mut pixels = boxes.swap_remove(i);
+        pixels.sort_unstable_by_key(|p| p[channel]);
+        // Split at the median, but keep equal values together.
+        let median = pixels[pixels.len() / 2][channel];
+        let split = match pixels.partition_point(|p| p[channel] < median) {
+            0 => pixels.partition_point(|p| p[channel] <= median),
+            n => n,
+        };
+        let upper = pixels.split_off(split);
+        boxes.push(pixels);
+        boxes.push(upper);
+    }
+
+    let mean = |pixels: &[Rgb]| {
+        let n = pixels.len().max(1) as u64;
+        let sum = |c: usize| pixels.iter().map(|p| p[c] as u64).sum::<u64>();
+        [0, 1, 2].map(|c| ((sum(c) + n / 2) / n) as u8)
+    };
+    let mut palette: Vec<Rgb> = boxes.iter().map(|b| mean(b)).collect();
+    palette.sort_by(|a, b| luminance(b).total_cmp(&luminance(a)));
+    palette.dedup();
+
+    let distance = |a: &Rgb, b: &Rgb| {
+        (0..3)
+            .map(|c| (a[c] as i32 - b[c] as i32).pow(2))
+            .sum::<i32>()
+    };
+    let indices = (image.pixels.iter())
+        .map(|p| {
+            (0..palette.len())
+                .min_by_key(|i| distance(p, &palette[*i]))
+                .unwrap()
+        })
+        .collect();
+    (palette, indices)
+}
+
+// * Puzzles in colors
+
+/// A run of cells of the same color.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ColoredRun {
+    pub len: usize,
+    /// The index of the color in the palette.
+    pub color: usize,
+}
+
+/// A puzzle in colors: each clue lists runs of a color, and two runs
+/// of different colors may touch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColoredPuzzle {
+    width: usize,
+    height: usize,
+    pub title: Option<String>,
+    pub palette: Vec<Rgb>,
+    /// The index in the palette of the color of empty cells.
+    pub background: usize,
+    pub rows: Vec<Vec<ColoredRun>>,
+    pub cols: Vec<Vec<ColoredRun>>,
+    /// The color of each cell, row by row, as indices in the palette.
+    pub goal: Vec<usize>,
+}
+
+impl ColoredPuzzle {
+    /// The puzzle whose solution is `cells`, row by row, as indices in
+    /// `palette`.
+    pub fn from_cells(
+        width: usize,
+        height: usize,
+        palette: Vec<Rgb>,
+        background: usize,
+        cells: Vec<usize>,
+    ) -> ColoredPuzzle {
+        assert_eq!(cells.len(), width * height);
+        let rows = (0..height)
+            .map(|y| runs((0..width).map(|x| cells[y * width + x]), background))
+            .collect();
+        let cols = (0..width)
+            .map(|x| runs((0..height).map(|y| cells[y * width + x]), background))
+            .collect();
+        ColoredPuzzle {
+            width,
+            height,
+            title: None,
+            palette,
+            background,
+            rows,
+            cols,
+            goal: cells,
+        }
+    }
+
+    /// The puzzle of `image` reduced to `colors` colors, see
+    /// [quantize], from 2 to [MAX_COLORS].  Its lightest color is the
+    /// background.
+    pub fn from_image(image: &Image, colors: usize) -> ColoredPuzzle {
+        assert!((2..=MAX_COLORS).contains(&colors), "bad number of colors");
+        let (palette, cells) = quantize(image, colors);
+        ColoredPuzzle::from_cells(image.width(), image.height(), palette, 0, cells)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+}
+
+/// The runs of colors other than `background` in `cells`.
+fn runs(cells: impl Iterator<Item = usize>, background: usize) -> Vec<ColoredRun> {
+    let mut ret: Vec<ColoredRun> = vec![];
+    let mut previous = background;
+    for color in cells {
+        match ret.last_mut() {
+            Some(run) if color == previous && color != background => run.len += 1,
+            _ if color != background => ret.push(ColoredRun { len: 1, color }),
+            _ => (),
+        }
+        previous = color;
+    }
+    ret
+}
@@ -1,7 +1,76 @@
+//! Solving nonograms.
+//!
+//! Without the default feature `std`, this crate is `no_std`, and
+//! only needs `alloc`: it keeps the puzzle and grid types, line logic
+//! and the [Solver], but none of the file formats, rendering or
+//! analysis, which need the standard library.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod analysis;
+#[cfg(feature = "std")]
+pub mod assist;
+pub mod checkpoint;
+#[cfg(feature = "std")]
+pub mod collection;
+pub mod constraint;
+pub mod error;
+#[cfg(feature = "capi")]
+pub mod ffi;
+#[cfg(feature = "std")]
+pub mod formats;
+#[cfg(feature = "std")]
+pub mod generator;
+pub mod history;
+#[cfg(feature = "std")]
+pub mod image;
+pub mod line;
 pub mod nonogram;
+#[cfg(feature = "std")]
+pub mod nonogram3d;
+#[cfg(feature = "std")]
 pub mod parser;
+pub mod play;
+pub mod puzzle;
+#[cfg(feature = "std")]
+pub mod race;
+#[cfg(feature = "std")]
+pub mod render;
+pub mod rle;
+mod rng;
 pub mod solver;
+pub mod storage;
+#[cfg(feature = "testgen")]
+pub mod testgen;
+pub mod trace;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod writer;
 
-pub use nonogram::{CellState,Constraint,Nonogram,NonogramBuilder,BuilderError};
-pub use parser::Parser;
-pub use solver::Solver;
+pub use checkpoint::{CheckpointError, SolverCheckpoint};
+pub use constraint::{Constraint, ConstraintError};
+pub use error::{Error, Result};
+pub use history::{Cells, Change, History, HistoryPoint};
+pub use line::{
+    solve_line, solve_line_with, LineCache, LineConflict, LineMask, LineMaskSet, LineResult,
+};
+pub use nonogram::{
+    BuilderError, CellDiff, CellIter, CellState, ColumnIter, ColumnView, LineRef, LineStatus,
+    Nonogram, NonogramBuilder, Point,
+};
+#[cfg(feature = "std")]
+pub use parser::{Parser, ParserError, ParserOptions};
+pub use play::{Mark, Move, PlayerGrid};
+pub use puzzle::{ClueDiff, Grid, Puzzle, Transform};
+pub use rle::RleError;
+#[cfg(feature = "async")]
+pub use solver::SolveFuture;
+pub use solver::{
+    Decided, GuessStrategy, MemoryEstimate, PassSnapshot, Progress, Schedule, SolveError,
+    SolveOutcome, SolveStats, Solver, SolverConfig, SolverEvent,
+};
+pub use storage::{GridStorage, PackedCells};
+pub use trace::{SolveTrace, TraceParseError};
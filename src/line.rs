@@ -0,0 +1,1197 @@
+//! Single-line deduction, independent of the grid.
+//!
+//! Everything the [crate::Solver] knows about a row or a column lives
+//! here: given a clue and the current state of a line, find every
+//! placement of the clue's runs that fits the line, and keep the
+//! cells on which all those placements agree.
+
+use crate::{CellState, Constraint};
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap as HashMap;
+use alloc::{vec, vec::Vec};
+use core::error;
+use core::fmt;
+use core::iter::once;
+use core::mem;
+use core::ops::{Range, RangeInclusive};
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+// * The line solver
+
+/// What [solve_line] could deduce about a line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LineResult {
+    /// The line, with every cell that could be deduced set.
+    pub cells: Vec<CellState>,
+    /// Indices of the cells that were undecided in the input but are
+    /// decided in [LineResult::cells].
+    pub changed: Vec<usize>,
+}
+
+/// No placement of the clue fits the current state of the line.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LineConflict;
+
+impl fmt::Display for LineConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Clue cannot be placed on line.")
+    }
+}
+
+impl error::Error for LineConflict {}
+
+/// How many candidates [solve_line] lists at most, see
+/// [solve_line_with].
+pub const MAX_LINE_CANDIDATES: usize = 1 << 12;
+
+/// Deduce as much as possible about a line of the grid, given its
+/// clue and its current state.
+///
+/// Cells that are already decided are never changed: they restrict
+/// the placements that are considered instead.  If no placement is
+/// compatible with them, this returns a [LineConflict].
+pub fn solve_line(clue: &Constraint, current: &[CellState]) -> Result<LineResult, LineConflict> {
+    solve_line_with(clue, current, MAX_LINE_CANDIDATES)
+}
+
+/// [solve_line], listing the candidates of the clue only if there are
+/// at most `max_candidates` of them, see [candidate_count].  Above
+/// that, the line is solved by dynamic programming, which gives the
+/// same result without listing anything: a clue like `1,1,1,1` has
+/// tens of thousands of candidates on a line of 40 cells.
+pub fn solve_line_with(
+    clue: &Constraint,
+    current: &[CellState],
+    max_candidates: usize,
+) -> Result<LineResult, LineConflict> {
+    let line = LineMask::from(current);
+    let consensus = match candidate_count(clue, line.len()) <= max_candidates as f64 {
+        true => consensus_of(clue, &line),
+        false => line_consensus(clue, &line),
+    };
+    Ok(line_result(
+        current,
+        consensus.ok_or(LineConflict)?.into_cells(),
+    ))
+}
+
+/// The consensus of every candidate for `clue` that fits `line`, or
+/// None if none fits.
+fn consensus_of(clue: &Constraint, line: &LineMask) -> Option<LineMask> {
+    if clue.is_unknown() {
+        return line_consensus(clue, line);
+    }
+    let mut cands = LineMaskSet::for_clue(clue, line.len());
+    cands.retain_fitting(line);
+    cands.consensus()
+}
+
+/// How many candidates [LineMaskSet::for_clue] lists for `clue` on a
+/// line of `len` cells, as a float since that overflows integers on
+/// long lines.  For plain clues, this is the binomial of the runs and
+/// the free cells, which is cheap; other clues are counted as
+/// [count_placements] does.
+pub fn candidate_count(clue: &Constraint, len: usize) -> f64 {
+    if clue.is_unknown() || clue.has_unknown_lengths() || clue.wraps() {
+        return count_placements(clue, &LineMask::undecided(len)).total;
+    }
+    let Some(free) = len.checked_sub(clue.min_length()) else {
+        return 0.0;
+    };
+    // Choose the places of the runs among the runs and the free cells.
+    (1..=clue.len()).fold(1.0, |count, i| count * (free + i) as f64 / i as f64)
+}
+
+fn line_result(current: &[CellState], cells: Vec<CellState>) -> LineResult {
+    let changed = current
+        .iter()
+        .zip(cells.iter())
+        .enumerate()
+        .filter(|(_, (old, new))| **old != **new)
+        .map(|(i, _)| i)
+        .collect();
+    LineResult { cells, changed }
+}
+
+// * Line masks
+
+/// The state of every cell of a single line.
+///
+/// This is used both for the current state of a row or column of the
+/// grid, where cells may be [CellState::Undecided], and for candidate
+/// placements of a clue, which are fully decided.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct LineMask(Vec<CellState>);
+
+impl LineMask {
+    /// A line of `len` undecided cells.
+    pub fn undecided(len: usize) -> LineMask {
+        LineMask(vec![CellState::Undecided; len])
+    }
+
+    /// Build a fully decided line from the lengths of its runs:
+    /// `empty[0]` empty cells, then `filled[0]` filled cells, then
+    /// `empty[1]` empty cells, and so on, ending with the last
+    /// element of `empty`.
+    pub fn from_runs(empty: &[usize], filled: &Constraint) -> LineMask {
+        assert!(empty.len() == filled.len() + 1);
+        let mut ret = vec![];
+        for (e, f) in empty.iter().zip(filled.iter().chain(once::<&usize>(&0))) {
+            for _ in 0..*e {
+                ret.push(CellState::Empty);
+            }
+            for _ in 0..*f {
+                ret.push(CellState::Filled);
+            }
+        }
+        LineMask(ret)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn cells(&self) -> &[CellState] {
+        &self.0
+    }
+
+    pub fn into_cells(self) -> Vec<CellState> {
+        self.0
+    }
+
+    /// Replace the cells by `cells`, reusing the buffer.
+    pub(crate) fn load(&mut self, cells: impl IntoIterator<Item = CellState>) {
+        self.0.clear();
+        self.0.extend(cells);
+    }
+
+    /// Determine if this mask can be placed over `line`, that is, if
+    /// there are no incompatible Filled/Empty cells between them.
+    /// Undecided cells of `line` accept anything.
+    pub fn fits(&self, line: &LineMask) -> bool {
+        line.0.iter().zip(self.0.iter()).all(|(l, c)| l.accepts(c))
+    }
+
+    /// The common part of two masks: cells where they agree keep
+    /// their value, the others are undecided.
+    pub fn consensus(&self, other: &LineMask) -> LineMask {
+        LineMask(
+            self.0
+                .iter()
+                .zip(other.0.iter())
+                .map(|(a, b)| a.consensus_eq(b))
+                .collect(),
+        )
+    }
+}
+
+impl From<Vec<CellState>> for LineMask {
+    fn from(cells: Vec<CellState>) -> Self {
+        LineMask(cells)
+    }
+}
+
+impl From<&[CellState]> for LineMask {
+    fn from(cells: &[CellState]) -> Self {
+        LineMask(cells.to_vec())
+    }
+}
+
+/// A set of candidate placements for a single line.
+///
+/// The set keeps the filled cells of the candidates as bitsets, so
+/// that filtering them against a line, or finding where they all
+/// agree, takes a few operations per 64 cells rather than one per
+/// cell.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LineMaskSet {
+    masks: Vec<LineMask>,
+    /// The length of the line.
+    capacity: usize,
+    /// The filled cells of each candidate, as in [PackedLine].
+    bits: Vec<u64>,
+}
+
+impl LineMaskSet {
+    /// Generate the full set of candidates for a constraint and a
+    /// given capacity (height or width).  This is empty if the
+    /// constraint can't fit, and the empty line alone for the empty
+    /// constraint.  For an unknown constraint, that's every line of
+    /// `capacity` cells, which only makes sense on short lines.  See
+    /// [candidate_count] for how large this gets.
+    pub fn for_clue(constraint: &Constraint, capacity: usize) -> LineMaskSet {
+        if constraint.is_unknown() || constraint.has_unknown_lengths() || constraint.wraps() {
+            return LineMaskSet::fitting(constraint, &LineMask::undecided(capacity));
+        }
+        let Some(free) = capacity.checked_sub(constraint.min_length()) else {
+            return LineMaskSet::from_masks(capacity, vec![]);
+        };
+        let count = candidate_count(constraint, capacity) as usize;
+        LineMaskSet::from_masks(capacity, place_free_cells(constraint, free, count))
+    }
+
+    /// The candidates for `constraint` that fit `line`, generated
+    /// directly rather than by filtering the full set.
+    pub fn fitting(constraint: &Constraint, line: &LineMask) -> LineMaskSet {
+        let mut masks = vec![];
+        if constraint.is_unknown() {
+            complete(&mut line.0.clone(), 0, &mut masks);
+        } else if constraint.wraps() {
+            for (case, sub) in wrap_cases(constraint, line) {
+                let placed = fitting_runs(case.runs, &sub);
+                masks.extend(placed.iter().map(|m| case.embed(m)));
+            }
+        } else {
+            masks = fitting_runs(constraint, line);
+        }
+        LineMaskSet::from_masks(line.len(), masks)
+    }
+
+    /// Build a set from candidates of length `capacity`.
+    pub fn from_masks(capacity: usize, masks: Vec<LineMask>) -> LineMaskSet {
+        let mut bits = Vec::with_capacity(masks.len() * capacity.div_ceil(64));
+        for mask in masks.iter() {
+            assert!(mask.len() == capacity);
+            bits.extend(PackedLine::new(mask).filled);
+        }
+        LineMaskSet {
+            masks,
+            capacity,
+            bits,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.masks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.masks.is_empty()
+    }
+
+    pub fn iter(&self) -> core::slice::Iter<'_, LineMask> {
+        self.masks.iter()
+    }
+
+    /// How many candidates fill position `i` of the line.
+    pub fn fill_count(&self, i: usize) -> usize {
+        let (word, bit) = (i / 64, 1 << (i % 64));
+        (self.words()).filter(|cand| cand[word] & bit != 0).count()
+    }
+
+    /// Find the intersection of the set, that is, the common part
+    /// of all the masks in it.  This is None if the set is empty.
+    /// The cells filled by all are those of the AND of their bitsets,
+    /// and the cells empty in all those missing from the OR.
+    pub fn consensus(&self) -> Option<LineMask> {
+        if self.masks.is_empty() {
+            return None;
+        }
+        let mut cells = Vec::with_capacity(self.capacity);
+        for word in 0..self.capacity.div_ceil(64) {
+            let (mut all, mut any) = (!0, 0);
+            for cand in self.words() {
+                all &= cand[word];
+                any |= cand[word];
+            }
+            let end = (self.capacity - word * 64).min(64);
+            cells.extend((0..end).map(|i| match ((all >> i) & 1, (any >> i) & 1) {
+                (1, _) => CellState::Filled,
+                (_, 0) => CellState::Empty,
+                _ => CellState::Undecided,
+            }));
+        }
+        Some(LineMask(cells))
+    }
+
+    /// The bitsets of the candidates, one slice of words each.
+    fn words(&self) -> core::slice::ChunksExact<'_, u64> {
+        self.bits.chunks_exact(self.capacity.div_ceil(64).max(1))
+    }
+
+    /// Remove the candidates that don't fit `line`.
+    pub fn retain_fitting(&mut self, line: &LineMask) {
+        self.retain_packed(&PackedLine::new(line), None);
+    }
+
+    /// Remove the candidates that don't fit `line`, packed, and add
+    /// them to `removed` if there's one, to [LineMaskSet::restore]
+    /// them later.
+    pub(crate) fn retain_packed(
+        &mut self,
+        line: &PackedLine,
+        mut removed: Option<&mut RemovedMasks>,
+    ) {
+        let words = line.filled.len();
+        let bits = &mut self.bits;
+        let (mut read, mut kept) = (0, 0);
+        self.masks.retain_mut(|cand| {
+            let fits = line.accepts(&bits[read..read + words]);
+            if fits {
+                bits.copy_within(read..read + words, kept);
+                kept += words;
+            } else if let Some(removed) = &mut removed {
+                removed.masks.push(mem::take(cand));
+                removed.bits.extend_from_slice(&bits[read..read + words]);
+            }
+            read += words;
+            fits
+        });
+        bits.truncate(kept);
+    }
+
+    /// Put back the candidates in `removed`, though not necessarily
+    /// in their place.
+    pub(crate) fn restore(&mut self, removed: RemovedMasks) {
+        self.masks.extend(removed.masks);
+        self.bits.extend(removed.bits);
+    }
+}
+
+/// Candidates removed from a [LineMaskSet] by
+/// [LineMaskSet::retain_packed], with their bitsets, to put them back
+/// without going through their cells.
+#[derive(Debug, Default)]
+pub(crate) struct RemovedMasks {
+    masks: Vec<LineMask>,
+    /// The filled cells of each, as [LineMaskSet] keeps them.
+    bits: Vec<u64>,
+}
+
+impl RemovedMasks {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.masks.is_empty()
+    }
+}
+
+/// A line as bitsets of its filled and of its empty cells, 64 cells
+/// per word, the first cell in the lowest bit.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct PackedLine {
+    filled: Vec<u64>,
+    empty: Vec<u64>,
+}
+
+impl PackedLine {
+    fn new(line: &LineMask) -> PackedLine {
+        let mut ret = PackedLine::default();
+        ret.load(line);
+        ret
+    }
+
+    /// Replace the cells by those of `line`, reusing the buffers.
+    pub(crate) fn load(&mut self, line: &LineMask) {
+        let words = line.len().div_ceil(64);
+        for bits in [&mut self.filled, &mut self.empty] {
+            bits.clear();
+            bits.resize(words, 0);
+        }
+        for (i, cell) in line.cells().iter().enumerate() {
+            let words = match cell {
+                CellState::Filled => &mut self.filled,
+                CellState::Empty => &mut self.empty,
+                CellState::Undecided => continue,
+            };
+            words[i / 64] |= 1 << (i % 64);
+        }
+    }
+
+    /// Whether a decided line whose filled cells are `filled`, in
+    /// words of the same length, can be placed over this one: it
+    /// fills every filled cell, and no empty one.
+    fn accepts(&self, filled: &[u64]) -> bool {
+        (self.filled.iter().zip(&self.empty).zip(filled)).all(|((f, e), c)| (f & !c) | (e & c) == 0)
+    }
+}
+
+/// Every placement of `runs` that fits `line`.
+fn fitting_runs(runs: &[usize], line: &LineMask) -> Vec<LineMask> {
+    let p = Placements::<bool>::new(runs, line);
+    let mut masks = vec![];
+    if p.total() {
+        let mut cells = vec![CellState::Empty; line.len()];
+        place_runs(&p, 0, 0, &mut cells, &mut masks);
+    }
+    masks
+}
+
+/// Place runs from `j` on, at `from` or after, in `cells`, and add
+/// every complete placement to `masks`.  `p` tells which partial
+/// placements can be completed, so there are no dead ends.
+fn place_runs(
+    p: &Placements<bool>,
+    j: usize,
+    from: usize,
+    cells: &mut Vec<CellState>,
+    masks: &mut Vec<LineMask>,
+) {
+    if j == p.clue.len() {
+        masks.push(LineMask(cells.clone()));
+        return;
+    }
+    for start in from..cells.len() {
+        // Cells skipped over stay empty.
+        if start > from && p.cells[start - 1] == CellState::Filled {
+            break;
+        }
+        for len in p.lengths(j) {
+            if !p.after(j, start, len) {
+                continue;
+            }
+            cells[start..start + len].fill(CellState::Filled);
+            place_runs(p, j + 1, start + len + 1, cells, masks);
+            cells[start..start + len].fill(CellState::Empty);
+        }
+    }
+}
+
+/// Add to `masks` every way to decide the undecided cells of `cells`
+/// from `i` on.
+fn complete(cells: &mut [CellState], i: usize, masks: &mut Vec<LineMask>) {
+    match cells.get(i) {
+        None => masks.push(LineMask(cells.to_vec())),
+        Some(CellState::Undecided) => {
+            for value in [CellState::Empty, CellState::Filled] {
+                cells[i] = value;
+                complete(cells, i + 1, masks);
+            }
+            cells[i] = CellState::Undecided;
+        }
+        Some(_) => complete(cells, i + 1, masks),
+    }
+}
+
+/// Every placement of the runs of `clue` with `free` cells besides
+/// the runs and the single empty cell between two runs, of which
+/// there are `count`.
+///
+/// The free cells are distributed over the gaps before, between and
+/// after the runs, like an odometer: `extra[j]` go before run `j`, and
+/// what's left after the last run.  Placements come in the order of
+/// `extra`, the first gap varying the slowest.
+fn place_free_cells(clue: &Constraint, free: usize, count: usize) -> Vec<LineMask> {
+    let mut masks = Vec::with_capacity(count);
+    let mut cells = vec![CellState::Empty; clue.min_length() + free];
+    let mut extra = vec![0; clue.len()];
+    let mut left = free;
+    loop {
+        cells.fill(CellState::Empty);
+        let mut start = 0;
+        for (run, e) in clue.iter().zip(&extra) {
+            start += e;
+            cells[start..start + run].fill(CellState::Filled);
+            start += run + 1;
+        }
+        masks.push(LineMask(cells.clone()));
+
+        if left > 0 && !extra.is_empty() {
+            // Move one free cell from the end into the last gap.
+            *extra.last_mut().unwrap() += 1;
+            left -= 1;
+            continue;
+        }
+        // Move every free cell of the last nonempty gap back to the
+        // end, but one, which goes into the previous gap.
+        match extra.iter().rposition(|e| *e > 0) {
+            Some(j) if j > 0 => {
+                left = extra[j] - 1;
+                extra[j] = 0;
+                extra[j - 1] += 1;
+            }
+            _ => return masks,
+        }
+    }
+}
+
+// * Overlaps
+
+/// What `clue` alone tells of a blank line of `len` cells: the cells
+/// each run covers both when the runs are packed to the start and to
+/// the end of the line, and every other cell empty if the runs fill
+/// the line, or if there are none.  This is the overlap method of
+/// solving by hand, and takes time linear in the line, where
+/// [line_consensus] takes time in the line times the runs, and
+/// listing candidates much more.
+///
+/// Clues that are unknown, that have unknown lengths or that wrap
+/// tell nothing this way, nor do clues that don't fit: the line
+/// solver finds out.
+pub fn clue_overlap(clue: &Constraint, len: usize) -> LineMask {
+    let Some(slack) = len.checked_sub(clue.min_length()) else {
+        return LineMask::undecided(len);
+    };
+    if clue.is_unknown() || clue.has_unknown_lengths() || clue.wraps() {
+        return LineMask::undecided(len);
+    }
+    let mut cells = match slack {
+        _ if clue.runs().is_empty() => vec![CellState::Empty; len],
+        0 => vec![CellState::Empty; len],
+        _ => vec![CellState::Undecided; len],
+    };
+    // Where the run starts when packed to the start of the line.
+    let mut start = 0;
+    for run in clue.runs() {
+        if *run > slack {
+            cells[start + slack..start + run].fill(CellState::Filled);
+        }
+        start += run + 1;
+    }
+    LineMask::from(cells)
+}
+
+// * Edges
+
+/// What the first and last runs of `clue` tell of `line` from its
+/// edges, the border technique of solving by hand.  Past the cells
+/// known empty at the start of the line, a filled cell closer to the
+/// edge than the length of the first run is in that run, which so
+/// goes on at least that far from the edge, and a filled cell right
+/// at the edge starts it, so that the cell after the run is empty.
+/// The same goes from the end of the line, with the last run.
+///
+/// This returns `line` with those cells decided, or None if they
+/// contradict it.  It only looks at a few cells at each end, so it's
+/// much cheaper than [line_consensus], which finds all of it too.
+/// Clues that are unknown or wrap, and ends whose run has an unknown
+/// length, tell nothing this way.
+pub fn edge_logic(clue: &Constraint, line: &LineMask) -> Option<LineMask> {
+    let mut cells = line.cells().to_vec();
+    for (i, value) in edge_cells(clue, line)? {
+        if cells[i] == value.opposite() {
+            return None;
+        }
+        cells[i] = value;
+    }
+    Some(LineMask::from(cells))
+}
+
+/// The cells that [edge_logic] decides, by index, without copying the
+/// line.  The two ends may decide the same cell, or even contradict
+/// each other.
+pub(crate) fn edge_cells(clue: &Constraint, line: &LineMask) -> Option<Vec<(usize, CellState)>> {
+    let cells = line.cells();
+    let len = cells.len();
+    let runs = clue.runs();
+    let mut ret = vec![];
+    if let (Some(first), Some(last), false) = (runs.first(), runs.last(), clue.wraps()) {
+        if *first > 0 {
+            from_edge(*first, cells, |i| i, &mut ret)?;
+        }
+        if *last > 0 {
+            from_edge(*last, cells, |i| len - 1 - i, &mut ret)?;
+        }
+    }
+    Some(ret)
+}
+
+/// Add to `ret` what the run at one end of `cells`, of `run` cells,
+/// decides from that end, see [edge_logic].  `at` turns distances to
+/// that end into indices.
+fn from_edge(
+    run: usize,
+    cells: &[CellState],
+    at: impl Fn(usize) -> usize,
+    ret: &mut Vec<(usize, CellState)>,
+) -> Option<()> {
+    let len = cells.len();
+    let cell = |i: usize| cells[at(i)];
+    let Some(edge) = (0..len).find(|i| cell(*i) != CellState::Empty) else {
+        return Some(());
+    };
+    let end = edge + run;
+    let Some(first) = (edge..end.min(len)).find(|i| cell(*i) == CellState::Filled) else {
+        return Some(());
+    };
+    // The run covers `first`, and starts at the edge or after it.
+    if end > len {
+        return None;
+    }
+    for i in first..end {
+        match cell(i) {
+            CellState::Empty => return None,
+            CellState::Undecided => ret.push((at(i), CellState::Filled)),
+            CellState::Filled => (),
+        }
+    }
+    if first == edge && end < len {
+        match cell(end) {
+            CellState::Filled => return None,
+            CellState::Undecided => ret.push((at(end), CellState::Empty)),
+            CellState::Empty => (),
+        }
+    }
+    Some(())
+}
+
+// * Placements by dynamic programming
+//
+// Listing every placement of a clue gets out of hand on long lines
+// with many runs: there are C(n, k) of them.  The tables below
+// answer the same questions in O(length × runs) instead.
+//
+// `forward[j][i]` weighs the ways to fill the first `i` cells with
+// the first `j` runs, and `backward[j][i]` the ways to fill the
+// cells from `i` on with the runs from `j` on, both compatible with
+// the line.  With boolean weights, that's whether there is a way at
+// all; with floating-point weights, how many there are, roughly.
+//
+// A run of unknown length may have any length that fits the line,
+// which multiplies the work by the length of the line for that run.
+
+/// Weights of ways to place runs.
+trait Weight: Copy + PartialEq {
+    const ZERO: Self;
+    const ONE: Self;
+    fn add(self, other: Self) -> Self;
+    fn mul(self, other: Self) -> Self;
+}
+
+impl Weight for bool {
+    const ZERO: bool = false;
+    const ONE: bool = true;
+    fn add(self, other: bool) -> bool {
+        self || other
+    }
+    fn mul(self, other: bool) -> bool {
+        self && other
+    }
+}
+
+impl Weight for f64 {
+    const ZERO: f64 = 0.0;
+    const ONE: f64 = 1.0;
+    fn add(self, other: f64) -> f64 {
+        self + other
+    }
+    fn mul(self, other: f64) -> f64 {
+        self * other
+    }
+}
+
+struct Placements<'a, W> {
+    clue: &'a [usize],
+    cells: &'a [CellState],
+    /// `empties[i]` counts the empty cells before `i`.
+    empties: Vec<usize>,
+    forward: Vec<Vec<W>>,
+    backward: Vec<Vec<W>>,
+}
+
+impl<'a, W: Weight> Placements<'a, W> {
+    fn new(clue: &'a [usize], line: &'a LineMask) -> Placements<'a, W> {
+        let (cells, n, k) = (line.cells(), line.len(), clue.len());
+        let mut empties = vec![0; n + 1];
+        for (i, cell) in cells.iter().enumerate() {
+            empties[i + 1] = empties[i] + (*cell == CellState::Empty) as usize;
+        }
+        let mut ret = Placements {
+            clue,
+            cells,
+            empties,
+            forward: vec![vec![W::ZERO; n + 1]; k + 1],
+            backward: vec![vec![W::ZERO; n + 1]; k + 1],
+        };
+
+        ret.forward[0][0] = W::ONE;
+        for i in 1..=n {
+            for j in 0..=k {
+                let mut w = W::ZERO;
+                if cells[i - 1] != CellState::Filled {
+                    w = w.add(ret.forward[j][i - 1]);
+                }
+                // Run j - 1 ends on cell i - 1.
+                if j > 0 {
+                    for len in ret.lengths(j - 1).filter(|len| *len <= i) {
+                        w = w.add(ret.before(j - 1, i - len, len));
+                    }
+                }
+                ret.forward[j][i] = w;
+            }
+        }
+        ret.backward[k][n] = W::ONE;
+        for i in (0..n).rev() {
+            for j in (0..=k).rev() {
+                let mut w = W::ZERO;
+                if cells[i] != CellState::Filled {
+                    w = w.add(ret.backward[j][i + 1]);
+                }
+                // Run j starts on cell i.
+                if j < k {
+                    for len in ret.lengths(j) {
+                        w = w.add(ret.after(j, i, len));
+                    }
+                }
+                ret.backward[j][i] = w;
+            }
+        }
+        ret
+    }
+
+    /// The lengths run `j` may have.
+    fn lengths(&self, j: usize) -> RangeInclusive<usize> {
+        match self.clue[j] {
+            0 => 1..=self.cells.len(),
+            len => len..=len,
+        }
+    }
+
+    /// Whether `len` cells from `start` on can all be filled.
+    fn run_fits(&self, start: usize, len: usize) -> bool {
+        let end = start + len;
+        end <= self.cells.len() && self.empties[end] == self.empties[start]
+    }
+
+    /// The ways to place the runs before `j` before `start`, with
+    /// run `j` of `len` cells at `start`.
+    fn before(&self, j: usize, start: usize, len: usize) -> W {
+        if !self.run_fits(start, len) {
+            W::ZERO
+        } else if j == 0 {
+            self.forward[0][start]
+        } else if start > 0 && self.cells[start - 1] != CellState::Filled {
+            self.forward[j][start - 1]
+        } else {
+            W::ZERO
+        }
+    }
+
+    /// The ways to place run `j` of `len` cells at `start` and the
+    /// runs after it after.
+    fn after(&self, j: usize, start: usize, len: usize) -> W {
+        let (n, k) = (self.cells.len(), self.clue.len());
+        let end = start + len;
+        if !self.run_fits(start, len) {
+            W::ZERO
+        } else if j + 1 == k {
+            self.backward[k][end]
+        } else if end < n && self.cells[end] != CellState::Filled {
+            self.backward[j + 1][end + 1]
+        } else {
+            W::ZERO
+        }
+    }
+
+    fn total(&self) -> W {
+        self.backward[0][0]
+    }
+
+    /// The ways to place the whole clue with run `j` of `len` cells
+    /// at `start`.
+    fn with_run_at(&self, j: usize, start: usize, len: usize) -> W {
+        match self.after(j, start, len) {
+            w if w == W::ZERO => W::ZERO,
+            w if j == 0 => self.forward[0][start].mul(w),
+            w if start > 0 && self.cells[start - 1] != CellState::Filled => {
+                self.forward[j][start - 1].mul(w)
+            }
+            _ => W::ZERO,
+        }
+    }
+
+    /// The ways to place the whole clue with cell `i` empty.
+    fn with_empty(&self, i: usize) -> W {
+        if self.cells[i] == CellState::Filled {
+            return W::ZERO;
+        }
+        (0..=self.clue.len()).fold(W::ZERO, |w, j| {
+            w.add(self.forward[j][i].mul(self.backward[j][i + 1]))
+        })
+    }
+}
+
+/// The cells on which every placement of `clue` fitting `line`
+/// agrees, or None if none fits.  This is the consensus of
+/// [LineMaskSet::fitting], without listing placements.
+pub fn line_consensus(clue: &Constraint, line: &LineMask) -> Option<LineMask> {
+    // Any line fits an unknown clue, so nothing new is known.
+    if clue.is_unknown() {
+        return Some(line.clone());
+    } else if clue.wraps() {
+        return wrap_cases(clue, line)
+            .into_iter()
+            .filter_map(|(case, sub)| Some(case.embed(&consensus_runs(case.runs, &sub)?)))
+            .reduce(|a, b| a.consensus(&b));
+    }
+    consensus_runs(clue, line)
+}
+
+fn consensus_runs(clue: &[usize], line: &LineMask) -> Option<LineMask> {
+    let p = Placements::<bool>::new(clue, line);
+    if !p.total() {
+        return None;
+    }
+    // Filled cells: count the placements of runs covering each cell,
+    // as differences from one cell to the next.
+    let mut covered = vec![0isize; line.len() + 1];
+    for j in 0..clue.len() {
+        for len in p.lengths(j) {
+            for start in 0..(line.len() + 1).saturating_sub(len) {
+                if p.with_run_at(j, start, len) {
+                    covered[start] += 1;
+                    covered[start + len] -= 1;
+                }
+            }
+        }
+    }
+    let mut coverage = 0;
+    let cells = (0..line.len())
+        .map(|i| {
+            coverage += covered[i];
+            match (coverage > 0, p.with_empty(i)) {
+                (true, true) => CellState::Undecided,
+                (true, false) => CellState::Filled,
+                _ => CellState::Empty,
+            }
+        })
+        .collect();
+    Some(LineMask(cells))
+}
+
+/// How many placements of a clue fit a line, and fill each cell,
+/// approximately: the counts grow too large for integers on long
+/// lines.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlacementCounts {
+    pub total: f64,
+    pub filled: Vec<f64>,
+}
+
+/// Count the placements of `clue` fitting `line`, without listing
+/// them.
+pub fn count_placements(clue: &Constraint, line: &LineMask) -> PlacementCounts {
+    if clue.is_unknown() {
+        let undecided = line.cells().iter().filter(|c| **c == CellState::Undecided);
+        let total: f64 = undecided.map(|_| 2.0).product();
+        let filled = line
+            .cells()
+            .iter()
+            .map(|cell| match cell {
+                CellState::Filled => total,
+                CellState::Undecided => total / 2.0,
+                CellState::Empty => 0.0,
+            })
+            .collect();
+        return PlacementCounts { total, filled };
+    } else if clue.wraps() {
+        let mut ret = PlacementCounts {
+            total: 0.0,
+            filled: vec![0.0; line.len()],
+        };
+        for (case, sub) in wrap_cases(clue, line) {
+            let counts = count_runs(case.runs, &sub);
+            ret.total += counts.total;
+            for (i, cell) in case.frame.iter().enumerate() {
+                if case.range.contains(&i) {
+                    ret.filled[i] += counts.filled[i - case.range.start];
+                } else if *cell == CellState::Filled {
+                    ret.filled[i] += counts.total;
+                }
+            }
+        }
+        return ret;
+    }
+    count_runs(clue, line)
+}
+
+fn count_runs(clue: &[usize], line: &LineMask) -> PlacementCounts {
+    let p = Placements::<f64>::new(clue, line);
+    let mut covered = vec![0.0; line.len() + 1];
+    for j in 0..clue.len() {
+        for len in p.lengths(j) {
+            for start in 0..(line.len() + 1).saturating_sub(len) {
+                let w = p.with_run_at(j, start, len);
+                covered[start] += w;
+                covered[start + len] -= w;
+            }
+        }
+    }
+    let mut coverage = 0.0;
+    let filled = covered[..line.len()]
+        .iter()
+        .map(|d| {
+            coverage += d;
+            coverage.max(0.0)
+        })
+        .collect();
+    PlacementCounts {
+        total: p.total(),
+        filled,
+    }
+}
+
+// * Wrapping lines
+//
+// When runs may wrap around from the end of a line to its start, as
+// on a torus, the run that wraps is the last of the clue: runs are
+// read from the first one that starts in the line.  So a line with
+// both ends filled either is a single run, or ends with the last run
+// and starts with the rest of it.  This splits placements by what
+// happens at the ends of the line, into cases where the other runs
+// don't wrap, and are placed as usual between the ends.
+
+/// Some placements of a wrapping clue: the cells of `frame` outside
+/// `range` are decided, and `runs` are placed within `range`, where
+/// `frame` may decide some cells too.
+struct WrapCase<'a> {
+    frame: Vec<CellState>,
+    range: Range<usize>,
+    runs: &'a [usize],
+}
+
+impl WrapCase<'_> {
+    fn new(len: usize, range: Range<usize>, runs: &[usize]) -> WrapCase<'_> {
+        WrapCase {
+            frame: vec![CellState::Undecided; len],
+            range,
+            runs,
+        }
+    }
+
+    /// A placement of the whole clue, from one of `runs` over
+    /// `range`.
+    fn embed(&self, placed: &LineMask) -> LineMask {
+        let mut cells = self.frame.clone();
+        cells[self.range.clone()].copy_from_slice(placed.cells());
+        LineMask(cells)
+    }
+}
+
+/// The cases of placements of the wrapping `clue` that may fit
+/// `line`, each with the part of `line` in its range.  No placement
+/// is in two cases.
+fn wrap_cases<'a>(clue: &'a Constraint, line: &LineMask) -> Vec<(WrapCase<'a>, LineMask)> {
+    use CellState::{Empty, Filled};
+    let (n, k) = (line.len(), clue.len());
+    let mut cases = vec![];
+    if n < 2 {
+        cases.push(WrapCase::new(n, 0..n, clue));
+    } else {
+        // The last cell is empty, or filled but not the first one.
+        let mut case = WrapCase::new(n, 0..n - 1, clue);
+        case.frame[n - 1] = Empty;
+        cases.push(case);
+        let mut case = WrapCase::new(n, 1..n, clue);
+        (case.frame[0], case.frame[n - 1]) = (Empty, Filled);
+        cases.push(case);
+    }
+    if n >= 2 && k == 1 && (clue[0] == 0 || clue[0] == n) {
+        let mut case = WrapCase::new(n, n..n, &[]);
+        case.frame.fill(Filled);
+        cases.push(case);
+    }
+    if k > 0 {
+        // The last run wraps around, with `head` cells at the start,
+        // and `tail` at the end.
+        for head in 1..n {
+            for tail in (1..n - head).filter(|t| clue[k - 1] == 0 || head + t == clue[k - 1]) {
+                let mut case =
+                    WrapCase::new(n, head + 1..(n - tail - 1).max(head + 1), &clue[..k - 1]);
+                case.frame[..head].fill(Filled);
+                case.frame[n - tail..].fill(Filled);
+                (case.frame[head], case.frame[n - tail - 1]) = (Empty, Empty);
+                cases.push(case);
+            }
+        }
+    }
+    cases
+        .into_iter()
+        .filter(|case| {
+            case.frame
+                .iter()
+                .zip(line.cells())
+                .all(|(f, l)| *f == CellState::Undecided || l.accepts(f))
+        })
+        .map(|case| {
+            let sub = (case.range.clone())
+                .map(|i| match case.frame[i] {
+                    CellState::Undecided => line.cells()[i],
+                    forced => forced,
+                })
+                .collect::<Vec<_>>();
+            (case, LineMask(sub))
+        })
+        .collect()
+}
+
+// * Memoization
+
+/// The consensus of lines already solved, by clue and line state.
+///
+/// The consensus of a clue on a line only depends on the clue and
+/// the current state of the line, and the same pairs come up again
+/// and again: every pass of the solver looks at lines that didn't
+/// change, and probes and guesses revisit the same states.  The
+/// cache forgets everything once it holds `capacity` lines, which
+/// bounds its memory.
+#[derive(Clone, Debug, Default)]
+pub struct LineCache {
+    /// Consensus by clue, then by packed line, `None` for a conflict.
+    /// Lookups borrow the clue, so that they don't copy it.
+    entries: HashMap<Constraint, HashMap<Vec<u64>, Option<LineMask>>>,
+    /// How many lines `entries` holds.
+    len: usize,
+    capacity: usize,
+    /// The line being looked up, packed.
+    packed: Vec<u64>,
+    hits: usize,
+    misses: usize,
+}
+
+impl LineCache {
+    /// A cache of at most `capacity` lines.  With a capacity of 0,
+    /// the cache is disabled and remembers nothing.
+    pub fn new(capacity: usize) -> LineCache {
+        LineCache {
+            capacity,
+            ..LineCache::default()
+        }
+    }
+
+    /// The consensus of `clue` on `line`, `Some(None)` if they
+    /// conflict, or `None` if this isn't known.
+    pub fn get(&mut self, clue: &Constraint, line: &LineMask) -> Option<Option<LineMask>> {
+        if self.capacity == 0 {
+            return None;
+        }
+        pack(line, &mut self.packed);
+        let found = (self.entries.get(clue))
+            .and_then(|lines| lines.get(&self.packed[..]))
+            .cloned();
+        match found {
+            Some(_) => self.hits += 1,
+            None => self.misses += 1,
+        }
+        found
+    }
+
+    /// Remember that the consensus of `clue` on `line` is
+    /// `consensus`, `None` meaning they conflict.
+    pub fn insert(&mut self, clue: &Constraint, line: &LineMask, consensus: Option<LineMask>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.len >= self.capacity {
+            self.clear();
+        }
+        if !self.entries.contains_key(clue) {
+            self.entries.insert(clue.clone(), HashMap::new());
+        }
+        let mut packed = vec![];
+        pack(line, &mut packed);
+        let lines = self.entries.get_mut(clue).unwrap();
+        if lines.insert(packed, consensus).is_none() {
+            self.len += 1;
+        }
+    }
+
+    /// [solve_line], through the cache.
+    pub fn solve(
+        &mut self,
+        clue: &Constraint,
+        current: &[CellState],
+    ) -> Result<LineResult, LineConflict> {
+        let line = LineMask::from(current);
+        let consensus = match self.get(clue, &line) {
+            Some(consensus) => consensus,
+            None => {
+                let consensus = consensus_of(clue, &line);
+                self.insert(clue, &line, consensus.clone());
+                consensus
+            }
+        };
+        let cells = consensus.ok_or(LineConflict)?.into_cells();
+        Ok(line_result(current, cells))
+    }
+
+    /// How many lines the cache holds.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// How many lookups found, and didn't find, their line.
+    pub fn stats(&self) -> (usize, usize) {
+        (self.hits, self.misses)
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.len = 0;
+    }
+}
+
+/// Pack the cells of `line` two bits each, into `into`.  No cell
+/// packs to 0, so lines of different lengths never pack the same.
+fn pack(line: &LineMask, into: &mut Vec<u64>) {
+    into.clear();
+    into.extend(line.cells().chunks(32).map(|chunk| {
+        chunk.iter().enumerate().fold(0, |word, (i, cell)| {
+            let bits = match cell {
+                CellState::Undecided => 1,
+                CellState::Empty => 2,
+                CellState::Filled => 3,
+            };
+            word | bits << (2 * i)
+        })
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The consensus of `set`, one candidate at a time.
+    fn folded(set: &LineMaskSet) -> Option<LineMask> {
+        set.iter().cloned().reduce(|a, b| a.consensus(&b))
+    }
+
+    #[test]
+    fn packed_consensus_matches_candidates() {
+        // Lines across words, with cells decided on either side of
+        // the first word boundary.
+        let clue = Constraint::new(vec![20, 1, 30]).unwrap();
+        let mut line = LineMask::undecided(70);
+        let mut set = LineMaskSet::for_clue(&clue, line.len());
+        for (i, value) in [
+            (63, CellState::Filled),
+            (64, CellState::Empty),
+            (5, CellState::Empty),
+        ] {
+            assert_eq!(set.consensus(), folded(&set));
+            for i in 0..line.len() {
+                let filling = set.iter().filter(|c| c.cells()[i] == CellState::Filled);
+                assert_eq!(set.fill_count(i), filling.count());
+            }
+            line.0[i] = value;
+            set.retain_fitting(&line);
+            assert!(set.iter().all(|c| c.fits(&line)));
+        }
+        assert_eq!(set.consensus(), folded(&set));
+        set.retain_fitting(&LineMask::from(vec![CellState::Filled; 70]));
+        assert_eq!(set.consensus(), None);
+    }
+
+    #[test]
+    fn restore_puts_back_what_was_removed() {
+        let clue = Constraint::new(vec![3, 40]).unwrap();
+        let full = LineMaskSet::for_clue(&clue, 66);
+        let mut set = full.clone();
+        let mut line = LineMask::undecided(66);
+        line.0[65] = CellState::Empty;
+        let mut removed = RemovedMasks::default();
+        set.retain_packed(&PackedLine::new(&line), Some(&mut removed));
+        assert!(!removed.is_empty() && !set.is_empty());
+        set.restore(removed);
+        assert_eq!(set.len(), full.len());
+        assert_eq!(set.consensus(), full.consensus());
+        assert!(full.iter().all(|c| set.iter().any(|d| c == d)));
+    }
+}
@@ -1,34 +1,22 @@
-use nonograms::Parser;
+mod cli;
+
+use cli::{report_error, Args};
 use std::env::args;
-use std::fs;
-use std::io;
-use std::iter::Iterator;
+use std::process::exit;
 
-fn go(mut r: impl io::Read) {
-    let parser = Parser::new().parse(&mut r);
-    match parser {
-        Ok(mut n) => {
-            println!("Dimensions (w×h) = {}×{}", n.width(), n.height());
-            n.solve();
-            println!("{}", n.as_text());
-        }
-        Err(e) => {
-            println!("Error: {}", e);
+fn main() {
+    let mut argv: Vec<String> = args().skip(1).collect();
+    let run = match argv.first().and_then(|name| cli::command(name)) {
+        Some(run) => {
+            argv.remove(0);
+            run
         }
+        // Solving is the default, so that `nonograms file.non` works.
+        None => cli::solve::run,
     };
-}
 
-fn main() {
-    let args = args().skip(1);
-    if args.len() == 0 {
-        go(std::io::stdin());
-    } else {
-        for (fname, fd) in args.map(|fname| (fname.clone(), fs::File::open(&fname))) {
-            println!("File: {}", fname);
-            match fd {
-                Ok(mut fd) => go(&mut fd),
-                Err(err) => eprintln!("Cannot read {}: {}", fname, err),
-            }
-        }
+    if let Err(e) = run(Args::new(argv)) {
+        report_error(e.as_ref());
+        exit(1);
     }
 }
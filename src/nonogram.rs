@@ -1,10 +1,22 @@
-use crate::Solver;
-use std::ops::{Index, IndexMut};
-use std::fmt;
+#[cfg(feature = "std")]
+use crate::formats::{self, FormatError};
+#[cfg(feature = "std")]
+use crate::render::{self, RenderOptions};
+use crate::rle::{self, RleError};
+use crate::{Constraint, ConstraintError, Grid, SolveOutcome, Solver};
+use alloc::{string::String, vec, vec::Vec};
+use core::cmp::Ordering;
+use core::error;
+use core::fmt;
+use core::iter::{Copied, Enumerate, StepBy};
+use core::ops::{Add, Index, IndexMut, Sub};
+use core::slice;
+#[cfg(feature = "std")]
+use core::str::FromStr;
 
 // * The Nonogram type
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Nonogram {
     // @FIXME All fields should be private.
     width: usize,
@@ -17,7 +29,12 @@ pub struct Nonogram {
 impl Nonogram {
     /// Create a new, unconstrained (and thus unsolvable) nonogram of
     /// dimensions width*height.
-    pub fn new(width: usize, height: usize, cols: Vec<Constraint>, rows: Vec<Constraint>) -> Nonogram {
+    pub fn new(
+        width: usize,
+        height: usize,
+        cols: Vec<Constraint>,
+        rows: Vec<Constraint>,
+    ) -> Nonogram {
         Nonogram {
             width,
             height,
@@ -31,8 +48,8 @@ impl Nonogram {
         NonogramBuilder::new()
     }
 
-    pub fn solve(&mut self) {
-        Solver::new(self).solve();
+    pub fn solve(&mut self) -> SolveOutcome {
+        Solver::new(self).solve()
     }
 
     pub fn width(&self) -> usize {
@@ -43,18 +60,16 @@ impl Nonogram {
         self.height
     }
 
+    /// Return a copy of a column (starting at 0).  See
+    /// [Nonogram::column_view] to read it without copying.
+    pub fn column(&self, x: usize) -> Option<Vec<CellState>> {
+        self.column_view(x).map(|column| column.to_vec())
+    }
+
     /// Return a view into a column (starting at 0).  This can't be
     /// made mutable, since columns aren't internally continuous.
-    pub fn column(&self, x: usize) -> Option<Vec<CellState>> {
-        if x >= self.width {
-            None
-        } else {
-            let mut ret = Vec::with_capacity(self.height);
-            for y in 0..self.height {
-                ret.push(self[(x, y)]);
-            }
-            Some(ret)
-        }
+    pub fn column_view(&self, x: usize) -> Option<ColumnView<'_>> {
+        (x < self.width).then(|| ColumnView::new(&self.cells, self.width, x))
     }
 
     /// Return a view into a row.  Unlike [column], this is a real
@@ -63,8 +78,66 @@ impl Nonogram {
         if y >= self.height {
             None
         } else {
-            return Some(&self.cells[self.xy_to_index(0, y)..self.xy_to_index(self.width, y)]);
+            Some(&self.cells[self.xy_to_index(0, y)..self.xy_to_index(self.width, y)])
+        }
+    }
+
+    /// Every row, from the top.
+    pub fn solution_rows(&self) -> impl Iterator<Item = &[CellState]> + '_ {
+        (0..self.height).map(|y| self.row(y).unwrap())
+    }
+
+    /// The picture, row by row, with true for filled cells, or None if
+    /// some cell is undecided.
+    pub fn solution(&self) -> Option<Vec<Vec<bool>>> {
+        self.solution_rows()
+            .map(|row| {
+                (row.iter())
+                    .map(|cell| match cell {
+                        CellState::Undecided => None,
+                        cell => Some(*cell == CellState::Filled),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The picture as a run-length string per row, eg `3b2w5b`, see
+    /// [crate::rle], or None if some cell is undecided.
+    pub fn solution_rle(&self) -> Option<Vec<String>> {
+        self.solution_rows().map(rle::encode).collect()
+    }
+
+    /// Set every cell from a run-length string per row, as from
+    /// [Nonogram::solution_rle].  On error, the grid is unchanged.
+    pub fn set_solution_rle(&mut self, rows: &[&str]) -> Result<(), RleError> {
+        if rows.len() != self.height {
+            return Err(RleError::WrongRowCount {
+                expected: self.height,
+                got: rows.len(),
+            });
         }
+        let mut cells = Vec::with_capacity(self.cells.len());
+        for (y, row) in rows.iter().enumerate() {
+            let row = rle::decode(row, self.width).map_err(|e| match e {
+                RleError::WrongLength { expected, got, .. } => RleError::WrongLength {
+                    row: y,
+                    expected,
+                    got,
+                },
+                e => e,
+            })?;
+            if row.len() != self.width {
+                return Err(RleError::WrongLength {
+                    row: y,
+                    expected: self.width,
+                    got: row.len(),
+                });
+            }
+            cells.extend(row);
+        }
+        self.cells = cells;
+        Ok(())
     }
 
     #[inline]
@@ -74,45 +147,279 @@ impl Nonogram {
 
     /// Generate a simple representation of this 'gram
     /// using Unicode box-drawing characters.
+    #[cfg(feature = "std")]
     pub fn as_text(&self) -> String {
-        let mut ret = String::new();
-        // let chars = [' ', '▀', '▄', '█'];
-        for y in 0..self.height {
-            for x in 0..self.width {
-                ret.push(match self[(x, y)] {
-                    CellState::Undecided => '?',
-                    CellState::Empty => ' ',
-                    CellState::Filled => '█',
-                })
-            }
-            ret.push('\n')
-        }
-        ret
+        self.as_text_with(&RenderOptions::default())
+    }
+
+    /// Render the grid as text, as described by `options`.
+    #[cfg(feature = "std")]
+    pub fn as_text_with(&self, options: &RenderOptions) -> String {
+        render::text::render(self, options)
+    }
+
+    /// Generate a compact representation of this 'gram, with 2×4
+    /// cells per braille character.  See [crate::render::braille].
+    #[cfg(feature = "std")]
+    pub fn as_braille_text(&self) -> String {
+        render::braille::render(self)
+    }
+
+    /// Render this 'gram as a Markdown table, with its clues.  See
+    /// [crate::render::markdown].
+    #[cfg(feature = "std")]
+    pub fn as_markdown(&self) -> String {
+        render::markdown::render(self)
+    }
+
+    /// Render the grid as a plain PBM bitmap.  See
+    /// [crate::render::bitmap::pbm].
+    #[cfg(feature = "std")]
+    pub fn to_pbm(&self) -> String {
+        render::bitmap::pbm(self)
+    }
+
+    /// Render the grid as an XPM image.  See
+    /// [crate::render::bitmap::xpm].
+    #[cfg(feature = "std")]
+    pub fn to_xpm(&self) -> String {
+        render::bitmap::xpm(self)
+    }
+
+    /// Render the puzzle and its grid as SVG.  See
+    /// [crate::render::svg].
+    #[cfg(feature = "std")]
+    pub fn to_svg(&self) -> String {
+        render::svg::render(self)
     }
 
     pub fn clear_solution(&mut self) {
         self.cells.fill(CellState::Undecided)
     }
+
+    /// Check what [NonogramBuilder::validate] does, and that the row
+    /// clues fill as many cells as the column clues, unless some of
+    /// them are unknown.
+    pub fn validate(&self) -> Result<(), BuilderError> {
+        NonogramBuilder::new()
+            .width(self.width)
+            .height(self.height)
+            .rows(self.rows.clone())
+            .cols(self.cols.clone())
+            .validate()?;
+        let known = |clue: &Constraint| !clue.is_unknown() && !clue.has_unknown_lengths();
+        if self.rows.iter().chain(&self.cols).all(known) {
+            let rows = self.rows.iter().map(Constraint::sum).sum();
+            let cols = self.cols.iter().map(Constraint::sum).sum();
+            if rows != cols {
+                return Err(BuilderError::SumMismatch { rows, cols });
+            }
+        }
+        Ok(())
+    }
+
+    /// Every row, from the top, then every column, from the left.
+    pub fn lines(&self) -> impl Iterator<Item = LineRef> {
+        let rows = (0..self.height).map(LineRef::Row);
+        rows.chain((0..self.width).map(LineRef::Column))
+    }
+
+    /// The clue of `line`.
+    pub fn clue(&self, line: LineRef) -> &Constraint {
+        match line {
+            LineRef::Row(y) => &self.rows[y],
+            LineRef::Column(x) => &self.cols[x],
+        }
+    }
+
+    /// The current cells of `line`, from the left or the top.
+    pub fn line(&self, line: LineRef) -> Vec<CellState> {
+        match line {
+            LineRef::Row(y) => self.row(y).unwrap().to_vec(),
+            LineRef::Column(x) => self.column(x).unwrap(),
+        }
+    }
+
+    /// How the current cells of `line` compare with its clue, eg to
+    /// tell a player as they fill the grid.  See
+    /// [Constraint::line_status].
+    pub fn line_status(&self, line: LineRef) -> LineStatus {
+        self.clue(line).line_status(&self.line(line))
+    }
+
+    /// How each cell compares with `goal`, the intended solution, row
+    /// by row.  Undecided cells of `goal` match anything.
+    pub fn diff_against_goal(&self, goal: &Grid) -> Vec<CellDiff> {
+        assert!(goal.width() == self.width && goal.height() == self.height);
+        (self.cells.iter().zip(goal.cells()))
+            .map(|(cell, goal)| match (cell, goal) {
+                (_, CellState::Undecided) => CellDiff::Match,
+                (CellState::Undecided, _) => CellDiff::Missing,
+                (cell, goal) if cell == goal => CellDiff::Match,
+                (CellState::Filled, _) => CellDiff::WrongFilled,
+                (CellState::Empty, _) => CellDiff::WrongEmpty,
+            })
+            .collect()
+    }
+
+    /// Every cell with its position, row by row: the same as iterating
+    /// over `&nonogram`.
+    pub fn iter(&self) -> CellIter<'_> {
+        CellIter {
+            cells: self.cells.iter().enumerate(),
+            width: self.width,
+        }
+    }
+
+    /// The positions of undecided cells, row by row.
+    pub fn undecided_cells(&self) -> impl Iterator<Item = Point> + '_ {
+        self.iter()
+            .filter(|(_, c)| *c == CellState::Undecided)
+            .map(|(p, _)| p)
+    }
+
+    /// The positions of filled cells, row by row.
+    pub fn filled_cells(&self) -> impl Iterator<Item = Point> + '_ {
+        self.iter()
+            .filter(|(_, c)| *c == CellState::Filled)
+            .map(|(p, _)| p)
+    }
+}
+
+/// The cells of a [Nonogram] with their positions, row by row, see
+/// [Nonogram::iter].
+#[derive(Clone, Debug)]
+pub struct CellIter<'a> {
+    cells: Enumerate<slice::Iter<'a, CellState>>,
+    width: usize,
+}
+
+impl Iterator for CellIter<'_> {
+    type Item = (Point, CellState);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (i, cell) = self.cells.next()?;
+        Some((Point::from_index(i, self.width), *cell))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.cells.size_hint()
+    }
 }
 
-impl Index<(usize, usize)> for Nonogram {
+impl ExactSizeIterator for CellIter<'_> {}
+
+/// A column of a grid, read in place: its cells are a row apart in
+/// the storage, see [Nonogram::column_view] and
+/// [crate::Grid::column_view].
+#[derive(Clone, Copy, Debug)]
+pub struct ColumnView<'a> {
+    /// The cells from the top of the column to the end of the grid.
+    cells: &'a [CellState],
+    width: usize,
+}
+
+impl<'a> ColumnView<'a> {
+    /// Column `x` of the grid `cells`, `width` cells wide.
+    pub(crate) fn new(cells: &'a [CellState], width: usize, x: usize) -> ColumnView<'a> {
+        ColumnView {
+            cells: cells.get(x..).unwrap_or_default(),
+            width,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len().div_ceil(self.width)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// The cell in row `y`.
+    pub fn get(&self, y: usize) -> Option<CellState> {
+        self.cells.get(y * self.width).copied()
+    }
+
+    /// The cells, from the top.
+    pub fn iter(&self) -> ColumnIter<'a> {
+        self.cells.iter().step_by(self.width).copied()
+    }
+
+    pub fn to_vec(&self) -> Vec<CellState> {
+        self.iter().collect()
+    }
+}
+
+/// The cells of a [ColumnView], from the top.
+pub type ColumnIter<'a> = Copied<StepBy<slice::Iter<'a, CellState>>>;
+
+impl<'a> IntoIterator for ColumnView<'a> {
+    type Item = CellState;
+    type IntoIter = ColumnIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl Index<usize> for ColumnView<'_> {
     type Output = CellState;
 
-    fn index(&self, (x, y): (usize, usize)) -> &Self::Output {
-        &self.cells[self.xy_to_index(x, y)]
+    fn index(&self, y: usize) -> &Self::Output {
+        &self.cells[y * self.width]
     }
 }
 
-impl IndexMut<(usize, usize)> for Nonogram {
-    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut Self::Output {
-        let index = self.xy_to_index(x, y);
-        &mut self.cells[index]
+impl<'a> IntoIterator for &'a Nonogram {
+    type Item = (Point, CellState);
+    type IntoIter = CellIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl Index<Point> for Nonogram {
+    type Output = CellState;
+
+    fn index(&self, p: Point) -> &Self::Output {
+        &self.cells[p.index(self.width)]
+    }
+}
+
+impl IndexMut<Point> for Nonogram {
+    fn index_mut(&mut self, p: Point) -> &mut Self::Output {
+        &mut self.cells[p.index(self.width)]
+    }
+}
+
+#[cfg(feature = "std")]
+impl FromStr for Nonogram {
+    type Err = FormatError;
+
+    /// Read a puzzle in the format it looks like, see
+    /// [formats::detect], or else in the format of [formats::non].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let format = formats::detect(s).unwrap_or(&formats::non::Non);
+        Ok(Nonogram::from(format.parse(s)?))
     }
 }
 
-pub type Constraint = Vec<usize>;
+/// How a cell compares with the goal, see
+/// [Nonogram::diff_against_goal].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CellDiff {
+    Match,
+    /// Filled, but empty in the goal.
+    WrongFilled,
+    /// Empty, but filled in the goal.
+    WrongEmpty,
+    /// Undecided.
+    Missing,
+}
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum CellState {
     Undecided,
     Empty,
@@ -142,6 +449,16 @@ impl CellState {
         }
     }
 
+    /// The other decided value: Filled for Empty and Empty for Filled.
+    /// Undecided stays undecided.
+    pub fn opposite(&self) -> CellState {
+        match self {
+            CellState::Undecided => CellState::Undecided,
+            CellState::Empty => CellState::Filled,
+            CellState::Filled => CellState::Empty,
+        }
+    }
+
     /// Determine if other can be applied over self.  This is an
     /// helper to determine if a candidate is compatible with the
     /// grid.
@@ -151,30 +468,273 @@ impl CellState {
     /// are the same value.
     pub fn accepts(&self, other: &CellState) -> bool {
         *self == CellState::Undecided || self == other
-        // println!("{:?} accepts {:?}? {:?}", self, other, ret);
+    }
+
+    /// The character for this state in cell strings: '1' for
+    /// filled, '0' for empty and '?' for undecided.
+    pub fn as_char(&self) -> char {
+        match self {
+            CellState::Filled => '1',
+            CellState::Empty => '0',
+            CellState::Undecided => '?',
+        }
+    }
+
+    /// The state for a character of a cell string, the reverse of
+    /// [CellState::as_char].
+    pub fn from_char(c: char) -> Option<CellState> {
+        match c {
+            '1' => Some(CellState::Filled),
+            '0' => Some(CellState::Empty),
+            '?' => Some(CellState::Undecided),
+            _ => None,
+        }
     }
 }
 
+/// Builds a [Nonogram] in a single expression, eg:
+///
+/// <pre>
+/// let nono = Nonogram::builder()
+///     .width(2)
+///     .height(1)
+///     .row([2])
+///     .col([1])
+///     .col([1])
+///     .build()?;
+/// </pre>
+///
+/// Setters take and return the builder, and don't fail: the first
+/// mistake is kept, and [NonogramBuilder::build] returns it.
 pub struct NonogramBuilder {
     pub width: Option<usize>,
     pub height: Option<usize>,
     rows: Vec<Constraint>,
     cols: Vec<Constraint>,
+    /// The first mistake of a setter.
+    error: Option<BuilderError>,
+}
+
+/// A row or a column of a grid, by index from 0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LineRef {
+    Row(usize),
+    Column(usize),
+}
+
+impl LineRef {
+    /// The cell at `index` along this line, from the left or the top.
+    pub const fn cell(self, index: usize) -> Point {
+        match self {
+            LineRef::Row(y) => Point::new(index, y),
+            LineRef::Column(x) => Point::new(x, index),
+        }
+    }
+}
+
+impl fmt::Display for LineRef {
+    /// As `row 3` or `column 3`, counting from 1, as in puzzle files.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LineRef::Row(y) => write!(f, "row {}", y + 1),
+            LineRef::Column(x) => write!(f, "column {}", x + 1),
+        }
+    }
+}
+
+/// The position of a cell, from `(0, 0)` at the top left, with `x`
+/// counting columns and `y` rows.  Grids are indexed by points, as
+/// `nono[Point::new(x, y)]`.
+///
+/// Points are ordered row by row, as cells are stored.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Point {
+    pub x: usize,
+    pub y: usize,
+}
+
+impl Point {
+    pub const fn new(x: usize, y: usize) -> Point {
+        Point { x, y }
+    }
+
+    /// The point of the cell at `index` in a grid `width` cells wide,
+    /// where cells are stored row by row.
+    pub const fn from_index(index: usize, width: usize) -> Point {
+        Point::new(index % width, index / width)
+    }
+
+    /// The index of this cell in a grid `width` cells wide, the
+    /// reverse of [Point::from_index].
+    pub const fn index(self, width: usize) -> usize {
+        self.y * width + self.x
+    }
+
+    /// Whether this is a cell of a grid of `width`×`height`.
+    pub const fn within(self, width: usize, height: usize) -> bool {
+        self.x < width && self.y < height
+    }
+
+    /// The point `dx` columns right and `dy` rows down, or None if that
+    /// would be left of or above the grid.
+    pub fn offset(self, dx: isize, dy: isize) -> Option<Point> {
+        Some(Point::new(
+            self.x.checked_add_signed(dx)?,
+            self.y.checked_add_signed(dy)?,
+        ))
+    }
+
+    /// The up to eight cells around this one, in a grid of
+    /// `width`×`height`.
+    pub fn neighbours(self, width: usize, height: usize) -> impl Iterator<Item = Point> {
+        (-1..=1)
+            .flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+            .filter(|d| *d != (0, 0))
+            .filter_map(move |(dx, dy)| self.offset(dx, dy))
+            .filter(move |p| p.within(width, height))
+    }
+
+    /// The same point with `x` and `y` swapped, as in the transposed
+    /// grid.
+    pub const fn transposed(self) -> Point {
+        Point::new(self.y, self.x)
+    }
+
+    /// The row of this cell.
+    pub const fn row(self) -> LineRef {
+        LineRef::Row(self.y)
+    }
+
+    /// The column of this cell.
+    pub const fn column(self) -> LineRef {
+        LineRef::Column(self.x)
+    }
+}
+
+impl Ord for Point {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.y, self.x).cmp(&(other.y, other.x))
+    }
 }
 
-#[derive(Debug)]
+impl PartialOrd for Point {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Add for Point {
+    type Output = Point;
+
+    fn add(self, other: Point) -> Point {
+        Point::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl Sub for Point {
+    type Output = Point;
+
+    /// Panics, as for `usize`, if `other` is right of or below `self`.
+    fn sub(self, other: Point) -> Point {
+        Point::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl From<(usize, usize)> for Point {
+    fn from((x, y): (usize, usize)) -> Point {
+        Point::new(x, y)
+    }
+}
+
+impl fmt::Display for Point {
+    /// As `(x, y)`, counting from 0.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+/// How the current cells of a line compare with its clue, see
+/// [Nonogram::line_status].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LineStatus {
+    /// The line is decided, and fits its clue.
+    Satisfied,
+    /// The filled and empty cells of the line contradict its clue.
+    Violated,
+    /// The line may still be completed to fit its clue.
+    Incomplete,
+}
+
+#[derive(Clone, Debug)]
 pub enum BuilderError {
-    Invalid,
+    /// The width or the height wasn't set, nor inferred.
+    MissingDimension,
     WidthAlreadySet,
     HeightAlreadySet,
+    /// There are `got` row clues for a height of `expected`.
+    RowCountMismatch {
+        expected: usize,
+        got: usize,
+    },
+    /// There are `got` column clues for a width of `expected`.
+    ColumnCountMismatch {
+        expected: usize,
+        got: usize,
+    },
+    /// The clue of `line` needs `needed` cells, but the line only has
+    /// `capacity`.
+    ConstraintTooLarge {
+        line: LineRef,
+        needed: usize,
+        capacity: usize,
+    },
+    /// The runs given to [NonogramBuilder::row] or
+    /// [NonogramBuilder::col] aren't a clue.
+    Constraint(ConstraintError),
+    /// The row clues fill `rows` cells, and the column clues `cols`,
+    /// see [Nonogram::validate].
+    SumMismatch {
+        rows: usize,
+        cols: usize,
+    },
 }
 
 impl fmt::Display for BuilderError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            BuilderError::Invalid => f.write_str("Invalid builder."),
+            BuilderError::MissingDimension => f.write_str("Width or height is missing."),
             BuilderError::WidthAlreadySet => f.write_str("Width was already set"),
             BuilderError::HeightAlreadySet => f.write_str("Height was already set."),
+            BuilderError::RowCountMismatch { expected, got } => {
+                write!(f, "Expected {} rows, got {}.", expected, got)
+            }
+            BuilderError::ColumnCountMismatch { expected, got } => {
+                write!(f, "Expected {} columns, got {}.", expected, got)
+            }
+            BuilderError::ConstraintTooLarge {
+                line,
+                needed,
+                capacity,
+            } => write!(
+                f,
+                "The clue of {} needs {} cells, but there are {}.",
+                line, needed, capacity
+            ),
+            BuilderError::Constraint(_) => f.write_str("Invalid clue."),
+            BuilderError::SumMismatch { rows, cols } => write!(
+                f,
+                "The row clues fill {} cells, but the column clues {}.",
+                rows, cols
+            ),
+        }
+    }
+}
+
+impl error::Error for BuilderError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            BuilderError::Constraint(e) => Some(e),
+            _ => None,
         }
     }
 }
@@ -188,64 +748,136 @@ impl NonogramBuilder {
             height: None,
             rows: vec![],
             cols: vec![],
+            error: None,
         }
     }
 
-    #[must_use]
-    pub fn width(&mut self, width: usize) -> BuilderResult<&mut Self> {
+    /// Keep `error`, unless there was one already.
+    fn fail(mut self, error: BuilderError) -> Self {
+        self.error.get_or_insert(error);
+        self
+    }
+
+    pub fn width(mut self, width: usize) -> Self {
         match self.width {
-            Some(_) => Err(BuilderError::WidthAlreadySet),
+            Some(_) => self.fail(BuilderError::WidthAlreadySet),
             None => {
                 self.width = Some(width);
-                Ok(self)
+                self
             }
         }
     }
 
-    pub fn height(&mut self, height: usize) -> BuilderResult<&mut Self> {
+    pub fn height(mut self, height: usize) -> Self {
         match self.height {
-            Some(_) => Err(BuilderError::HeightAlreadySet),
+            Some(_) => self.fail(BuilderError::HeightAlreadySet),
             None => {
                 self.height = Some(height);
-                Ok(self)
+                self
             }
         }
     }
 
-    pub fn push_row_constraint(&mut self, constraint: Constraint) -> &mut Self {
+    /// Add a row whose clue has runs of lengths `runs`, eg `[1, 2]`,
+    /// or `[]` for an empty row.
+    pub fn row(self, runs: impl IntoIterator<Item = usize>) -> Self {
+        match Constraint::new(runs.into_iter().collect()) {
+            Ok(clue) => self.push_row_constraint(clue),
+            Err(e) => self.fail(BuilderError::Constraint(e)),
+        }
+    }
+
+    /// Add a column whose clue has runs of lengths `runs`, as in
+    /// [NonogramBuilder::row].
+    pub fn col(self, runs: impl IntoIterator<Item = usize>) -> Self {
+        match Constraint::new(runs.into_iter().collect()) {
+            Ok(clue) => self.push_col_constraint(clue),
+            Err(e) => self.fail(BuilderError::Constraint(e)),
+        }
+    }
+
+    pub fn push_row_constraint(mut self, constraint: Constraint) -> Self {
         self.rows.push(constraint);
         self
     }
 
-    pub fn push_col_constraint(&mut self, constraint: Constraint) -> &mut Self {
+    pub fn push_col_constraint(mut self, constraint: Constraint) -> Self {
         self.cols.push(constraint);
         self
     }
 
-    pub fn validate(&self) -> BuilderResult<()> {
-        if self.width.is_none()
-            || self.height.is_none()
-            || self.height.unwrap() != self.rows.len()
-            || self.width.unwrap() != self.cols.len()
-        {
-            Err(BuilderError::Invalid)
-        } else {
-            Ok(())
+    /// Set every row constraint at once, replacing those pushed
+    /// before.
+    pub fn rows(mut self, rows: Vec<Constraint>) -> Self {
+        self.rows = rows;
+        self
+    }
+
+    /// Set every column constraint at once, replacing those pushed
+    /// before.
+    pub fn cols(mut self, cols: Vec<Constraint>) -> Self {
+        self.cols = cols;
+        self
+    }
+
+    /// Set the width and height, unless they were set already, to the
+    /// numbers of column and row constraints.  Without any
+    /// constraints, there's nothing to infer from, and this leaves the
+    /// dimensions unset.
+    pub fn infer_dimensions(mut self) -> Self {
+        if !self.rows.is_empty() || !self.cols.is_empty() {
+            self.width.get_or_insert(self.cols.len());
+            self.height.get_or_insert(self.rows.len());
         }
+        self
     }
 
+    /// Check the first mistake of the setters, then that the
+    /// dimensions are set and match the clues, and that every clue
+    /// fits its line.
+    pub fn validate(&self) -> BuilderResult<()> {
+        if let Some(e) = &self.error {
+            return Err(e.clone());
+        }
+        let (Some(width), Some(height)) = (self.width, self.height) else {
+            return Err(BuilderError::MissingDimension);
+        };
+        if self.rows.len() != height {
+            return Err(BuilderError::RowCountMismatch {
+                expected: height,
+                got: self.rows.len(),
+            });
+        }
+        if self.cols.len() != width {
+            return Err(BuilderError::ColumnCountMismatch {
+                expected: width,
+                got: self.cols.len(),
+            });
+        }
+        let rows = (self.rows.iter().enumerate()).map(|(y, clue)| (LineRef::Row(y), clue, width));
+        let cols =
+            (self.cols.iter().enumerate()).map(|(x, clue)| (LineRef::Column(x), clue, height));
+        for (line, clue, capacity) in rows.chain(cols) {
+            if !clue.fits(capacity) {
+                return Err(BuilderError::ConstraintTooLarge {
+                    line,
+                    needed: clue.min_length(),
+                    capacity,
+                });
+            }
+        }
+        Ok(())
+    }
 
     pub fn build(self) -> BuilderResult<Nonogram> {
         self.validate()?;
-        Ok (
-            Nonogram::new(
-                self.width.unwrap(),
-                self.height.unwrap(),
-                self.cols,
-                self.rows)
-        )
+        Ok(Nonogram::new(
+            self.width.unwrap(),
+            self.height.unwrap(),
+            self.cols,
+            self.rows,
+        ))
     }
-
 }
 
 impl Default for NonogramBuilder {
@@ -0,0 +1,285 @@
+//! Nonograms in three dimensions.
+//!
+//! A [Puzzle3d] is a block of `width × height × depth` voxels, with a
+//! clue for every rod of voxels along each of the three axes.  Most
+//! such puzzles only give some of those clues: the others are
+//! [Constraint::unknown].
+//!
+//! [Puzzle3d::solve] is line logic, as in [crate::line], applied to
+//! rods in the three directions: whenever a rod changes, the rods
+//! crossing it are solved again.  There is no search, so puzzles that
+//! need guesses stall.  [render] draws the block slice by slice.
+
+use crate::line::{line_consensus, LineMask};
+use crate::render::RenderOptions;
+use crate::{CellState, Constraint, SolveOutcome};
+
+use std::collections::VecDeque;
+use std::ops::{Index, IndexMut};
+
+// * Puzzles
+
+/// A direction of rods.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Axis {
+    /// Rods along the width, one per `(y, z)`.
+    X,
+    /// Rods along the height, one per `(x, z)`.
+    Y,
+    /// Rods along the depth, one per `(x, y)`.
+    Z,
+}
+
+impl Axis {
+    pub const ALL: [Axis; 3] = [Axis::X, Axis::Y, Axis::Z];
+}
+
+/// The definition of a 3D nonogram.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Puzzle3d {
+    width: usize,
+    height: usize,
+    depth: usize,
+    /// Clues by [Axis], then by rod, see [Puzzle3d::rod].
+    clues: [Vec<Constraint>; 3],
+}
+
+impl Puzzle3d {
+    /// A puzzle of size `width × height × depth`.  There must be one
+    /// clue per rod along each axis, in the order of [Puzzle3d::rod]:
+    /// `x` has `height × depth` clues, by `z` then `y`, and so on.
+    pub fn new(
+        width: usize,
+        height: usize,
+        depth: usize,
+        x: Vec<Constraint>,
+        y: Vec<Constraint>,
+        z: Vec<Constraint>,
+    ) -> Puzzle3d {
+        assert!(x.len() == height * depth && y.len() == width * depth && z.len() == width * height);
+        Puzzle3d {
+            width,
+            height,
+            depth,
+            clues: [x, y, z],
+        }
+    }
+
+    /// The puzzle whose solution is `grid`, with every clue given.
+    pub fn from_solution(grid: &Grid3d) -> Puzzle3d {
+        let mut ret = Puzzle3d {
+            width: grid.width,
+            height: grid.height,
+            depth: grid.depth,
+            clues: Default::default(),
+        };
+        for axis in Axis::ALL {
+            ret.clues[axis as usize] = (0..ret.rod_count(axis))
+                .map(|i| {
+                    let cells: Vec<CellState> = ret.rod(axis, i).map(|c| grid.cells[c]).collect();
+                    Constraint::of_line(&cells)
+                })
+                .collect();
+        }
+        ret
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// An undecided grid for this puzzle.
+    pub fn grid(&self) -> Grid3d {
+        Grid3d::new(self.width, self.height, self.depth)
+    }
+
+    /// How many rods there are along `axis`.
+    pub fn rod_count(&self, axis: Axis) -> usize {
+        let (w, h, d) = (self.width, self.height, self.depth);
+        match axis {
+            Axis::X => h * d,
+            Axis::Y => w * d,
+            Axis::Z => w * h,
+        }
+    }
+
+    /// The clue of rod `i` along `axis`.
+    pub fn clue(&self, axis: Axis, i: usize) -> &Constraint {
+        &self.clues[axis as usize][i]
+    }
+
+    pub fn set_clue(&mut self, axis: Axis, i: usize, clue: Constraint) {
+        self.clues[axis as usize][i] = clue;
+    }
+
+    /// The indices in [Grid3d::cells] of the voxels of rod `i` along
+    /// `axis`, in order.  Rod `y + height × z` is along X, `x + width
+    /// × z` along Y, and `x + width × y` along Z.
+    pub fn rod(&self, axis: Axis, i: usize) -> impl Iterator<Item = usize> {
+        let (w, h, d) = (self.width, self.height, self.depth);
+        let (start, step, len) = match axis {
+            Axis::X => (i * w, 1, w),
+            Axis::Y => (i % w + (i / w) * w * h, w, h),
+            Axis::Z => (i, w * h, d),
+        };
+        (0..len).map(move |k| start + k * step)
+    }
+
+    /// The rod along `axis` through the voxel at `index`.
+    fn rod_through(&self, axis: Axis, index: usize) -> usize {
+        let (w, h) = (self.width, self.height);
+        let (x, y, z) = (index % w, index / w % h, index / (w * h));
+        match axis {
+            Axis::X => y + h * z,
+            Axis::Y => x + w * z,
+            Axis::Z => x + w * y,
+        }
+    }
+
+    /// Solve this puzzle by line logic.
+    pub fn solve(&self) -> (Grid3d, SolveOutcome) {
+        let mut grid = self.grid();
+        let outcome = self.solve_into(&mut grid);
+        (grid, outcome)
+    }
+
+    /// Deduce what line logic can on `grid`, from what it holds.
+    pub fn solve_into(&self, grid: &mut Grid3d) -> SolveOutcome {
+        assert!(grid.width == self.width && grid.height == self.height && grid.depth == self.depth);
+        let mut queued: [Vec<bool>; 3] = Axis::ALL.map(|axis| vec![true; self.rod_count(axis)]);
+        let mut queue: VecDeque<(Axis, usize)> = Axis::ALL
+            .into_iter()
+            .flat_map(|axis| (0..self.rod_count(axis)).map(move |i| (axis, i)))
+            .collect();
+
+        while let Some((axis, i)) = queue.pop_front() {
+            queued[axis as usize][i] = false;
+            let indices: Vec<usize> = self.rod(axis, i).collect();
+            let line = LineMask::from(indices.iter().map(|c| grid.cells[*c]).collect::<Vec<_>>());
+            let Some(consensus) = line_consensus(self.clue(axis, i), &line) else {
+                return SolveOutcome::Contradiction;
+            };
+            for (index, value) in indices.iter().zip(consensus.cells()) {
+                if grid.cells[*index] != *value {
+                    grid.cells[*index] = *value;
+                    for other in Axis::ALL.into_iter().filter(|a| *a != axis) {
+                        let j = self.rod_through(other, *index);
+                        if !queued[other as usize][j] {
+                            queued[other as usize][j] = true;
+                            queue.push_back((other, j));
+                        }
+                    }
+                }
+            }
+        }
+
+        if grid.is_complete() {
+            SolveOutcome::Solved
+        } else {
+            SolveOutcome::Stalled
+        }
+    }
+}
+
+// * Grids
+
+/// The voxels of a 3D nonogram.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Grid3d {
+    width: usize,
+    height: usize,
+    depth: usize,
+    cells: Vec<CellState>,
+}
+
+impl Grid3d {
+    /// A grid of `width × height × depth` undecided voxels.
+    pub fn new(width: usize, height: usize, depth: usize) -> Grid3d {
+        Grid3d {
+            width,
+            height,
+            depth,
+            cells: vec![CellState::Undecided; width * height * depth],
+        }
+    }
+
+    /// A grid of voxels listed slice by slice, then row by row.
+    pub fn from_cells(width: usize, height: usize, depth: usize, cells: Vec<CellState>) -> Grid3d {
+        assert!(cells.len() == width * height * depth);
+        Grid3d {
+            width,
+            height,
+            depth,
+            cells,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Every voxel, slice by slice, then row by row.
+    pub fn cells(&self) -> &[CellState] {
+        &self.cells
+    }
+
+    /// True if no voxel is undecided.
+    pub fn is_complete(&self) -> bool {
+        !self.cells.contains(&CellState::Undecided)
+    }
+}
+
+impl Index<(usize, usize, usize)> for Grid3d {
+    type Output = CellState;
+
+    fn index(&self, (x, y, z): (usize, usize, usize)) -> &Self::Output {
+        &self.cells[(z * self.height + y) * self.width + x]
+    }
+}
+
+impl IndexMut<(usize, usize, usize)> for Grid3d {
+    fn index_mut(&mut self, (x, y, z): (usize, usize, usize)) -> &mut Self::Output {
+        &mut self.cells[(z * self.height + y) * self.width + x]
+    }
+}
+
+// * Rendering
+
+/// Draw `grid` as text, one slice of constant `z` after the other,
+/// with the characters of `options` for voxels.
+pub fn render(grid: &Grid3d, options: &RenderOptions) -> String {
+    let mut ret = String::new();
+    for z in 0..grid.depth {
+        if z > 0 {
+            ret.push('\n');
+        }
+        ret.push_str(&format!("z = {}\n", z));
+        for y in 0..grid.height {
+            for x in 0..grid.width {
+                ret.push(match grid[(x, y, z)] {
+                    CellState::Filled => options.filled,
+                    CellState::Empty => options.empty,
+                    CellState::Undecided => options.undecided,
+                });
+            }
+            ret.push('\n');
+        }
+    }
+    ret
+}
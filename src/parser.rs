@@ -1,9 +1,14 @@
-use crate::{Nonogram,NonogramBuilder,BuilderError};
+use crate::constraint::parse_length;
+use crate::{
+    BuilderError, CellState, Constraint, ConstraintError, Grid, Nonogram, NonogramBuilder, Puzzle,
+};
 
-use std::io;
-use std::fmt::Display;
+use std::error;
 use std::fmt;
+use std::fmt::Display;
+use std::io;
 use std::io::Read;
+use std::mem;
 use std::num::ParseIntError;
 
 #[derive(Default)]
@@ -11,26 +16,50 @@ pub struct Parser {
     builder: NonogramBuilder,
     line: usize,
     mode: ParserMode,
+    options: ParserOptions,
+    title: Option<String>,
+    author: Option<String>,
+    toroidal: bool,
+    /// The goal as written, to read once the dimensions are known.
+    goal: Option<String>,
 }
 
-enum ParserMode {
-    Main,
-    Cols,
-    Rows,
+/// How a [Parser] reads puzzles.
+#[derive(Clone, Debug)]
+pub struct ParserOptions {
+    /// Accept runs separated by spaces or tabs, eg `3 1 2`, as well
+    /// as by commas.
+    pub whitespace_separators: bool,
 }
 
-impl Default for ParserMode {
+impl Default for ParserOptions {
     fn default() -> Self {
-        ParserMode::Main
+        ParserOptions {
+            whitespace_separators: true,
+        }
     }
 }
 
+#[derive(Default)]
+enum ParserMode {
+    #[default]
+    Main,
+    Cols,
+    Rows,
+}
+
 #[derive(Debug)]
 pub enum ParserError {
     InternalError,
     ParseIntError,
     IOError(io::Error),
-    BuilderError(BuilderError)
+    BuilderError(BuilderError),
+    ConstraintError(ConstraintError),
+    /// The input isn't UTF-8; this is the offset of the first byte
+    /// that isn't.
+    NotUtf8(usize),
+    /// The goal isn't made of a `0` or `1` for each cell.
+    BadGoal,
 }
 
 impl From<io::Error> for ParserError {
@@ -45,6 +74,12 @@ impl From<BuilderError> for ParserError {
     }
 }
 
+impl From<ConstraintError> for ParserError {
+    fn from(value: ConstraintError) -> Self {
+        ParserError::ConstraintError(value)
+    }
+}
+
 impl From<ParseIntError> for ParserError {
     fn from(_: ParseIntError) -> Self {
         Self::ParseIntError
@@ -56,11 +91,26 @@ impl Display for ParserError {
         match self {
             ParserError::InternalError => write!(f, "Internal error (parser is borken)"),
             ParserError::ParseIntError => write!(f, "Cannot parse int value."),
-            ParserError::IOError(e) => e.fmt(f),
-            ParserError::BuilderError(e) => e.fmt(f),
+            ParserError::IOError(_) => write!(f, "Cannot read input."),
+            ParserError::BuilderError(_) => write!(f, "Invalid nonogram definition."),
+            ParserError::ConstraintError(_) => write!(f, "Invalid clue."),
+            ParserError::NotUtf8(offset) => {
+                write!(f, "Input is not UTF-8 text (at byte {}).", offset)
+            }
+            ParserError::BadGoal => write!(f, "The goal does not match the grid."),
+        }
+    }
+}
+
+impl error::Error for ParserError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ParserError::IOError(e) => Some(e),
+            ParserError::BuilderError(e) => Some(e),
+            ParserError::ConstraintError(e) => Some(e),
+            _ => None,
         }
     }
-    
 }
 
 impl Parser {
@@ -68,14 +118,26 @@ impl Parser {
         Parser::default()
     }
 
+    pub fn with_options(options: ParserOptions) -> Parser {
+        Parser {
+            options,
+            ..Parser::default()
+        }
+    }
+
     /// Parse a nonogram using the format of
     /// <https://github.com/mikix/nonogram-db/>
-    pub fn parse(mut self, f: &mut impl Read) -> Result<Nonogram, ParserError> {
-        let mut source: String = String::default();
+    ///
+    /// The input must be UTF-8, with or without a byte order mark,
+    /// and lines may end in LF, CRLF or CR.
+    pub fn parse(self, f: &mut impl Read) -> Result<Nonogram, ParserError> {
+        Ok(Nonogram::from(self.parse_puzzle(f)?))
+    }
 
-        // Read input
-        f.read_to_string(&mut source)
-            .map_err(|e| ParserError::IOError(e))?;
+    /// Like [Parser::parse], but keep the title and author of the
+    /// puzzle.
+    pub fn parse_puzzle(mut self, f: &mut impl Read) -> Result<Puzzle, ParserError> {
+        let source = read_text(f)?;
 
         for line in source.lines() {
             self.line += 1;
@@ -86,76 +148,163 @@ impl Parser {
             }
         }
 
-        Ok(self.builder.build()?)
+        // Files may leave out the dimensions, which are then the numbers
+        // of clues.
+        let (mut puzzle, _) = self.builder.infer_dimensions().build()?.into_parts();
+        puzzle.title = self.title;
+        puzzle.author = self.author;
+        puzzle.set_toroidal(self.toroidal);
+        if let Some(goal) = self.goal {
+            puzzle.goal = Some(read_goal(&goal, puzzle.width(), puzzle.height())?);
+        }
+        Ok(puzzle)
     }
 
     fn parse_header_line(&mut self, line: &str) -> Result<(), ParserError> {
-        let (command, args) = line.split_at(line.find(' ').unwrap_or(line.len()));
+        let (command, args) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let args = args.trim();
         match command {
             "columns" => self.mode = ParserMode::Cols,
             "rows" => self.mode = ParserMode::Rows,
             "height" => {
-                self.builder.height(
-                    args.trim()
-                        .parse::<usize>()?)?;
-            },
-            "width" => {
-                self.builder.width(
-                    args.trim()
-                        .parse::<usize>()?)?;
+                let height = args.parse::<usize>()?;
+                self.builder = mem::take(&mut self.builder).height(height);
             }
-            "goal" => {
-                println!("Skipping goal for now");
-                // self.ensure_nono()?;
-                // self.nono.as_mut().unwrap().cells = unquote(args.trim())
-                //     .chars()
-                //     .map(|c| match c {
-                //         '0' => Ok(CellState::Empty),
-                //         '1' => Ok(CellState::Filled),
-                //         _ => Err(self.err("Cannot parse goal")),
-                //     })
-                //     .collect::<Result<Vec<CellState>, ParserError>>()?;
+            "width" => {
+                let width = args.parse::<usize>()?;
+                self.builder = mem::take(&mut self.builder).width(width);
             }
+            "title" => self.title = Some(unquote(args)),
+            "by" => self.author = Some(unquote(args)),
+            "toroidal" => self.toroidal = true,
+            "goal" => self.goal = Some(unquote(args)),
             _ => (),
         }
         Ok(())
     }
 
-    ///
+    /// Parse a line of comma-separated clues, or return to header
+    /// mode if the line isn't one.  A clue of `0` is an empty line.
+    /// Per [ParserOptions::whitespace_separators], clues may also be
+    /// separated by spaces and tabs.
     fn parse_constraint_line(&mut self, line: &str) -> Result<(), ParserError> {
         let line = line.trim();
         if line.is_empty() {
             self.mode = ParserMode::Main;
             return Ok(());
         }
-        let parsed = line
-            .split(",")
-            .map(str::trim)
-            .map(str::parse::<usize>)
-            .collect::<Result<Vec<usize>, _>>();
-
-        if let Ok(vec) = parsed {
-            match &self.mode {
-                ParserMode::Rows => self.builder.push_row_constraint(vec),
-                ParserMode::Cols => self.builder.push_col_constraint(vec),
-                ParserMode::Main => return Err(ParserError::InternalError),
-            };
-        } else {
-            self.mode = ParserMode::Main;
-            // Because there may not be a blank line after the last column or row.
-            return self.parse_header_line(line);
-        };
+        match self.parse_clue(line) {
+            Ok(clue) => {
+                let builder = mem::take(&mut self.builder);
+                self.builder = match &self.mode {
+                    ParserMode::Rows => builder.push_row_constraint(clue),
+                    ParserMode::Cols => builder.push_col_constraint(clue),
+                    ParserMode::Main => return Err(ParserError::InternalError),
+                };
+            }
+            // A run of zero cells, other than the clue of an empty line.
+            Err(e @ ConstraintError::ZeroLengthRun) => return Err(e.into()),
+            Err(ConstraintError::NotANumber(_)) => {
+                self.mode = ParserMode::Main;
+                // Because there may not be a blank line after the last column or row.
+                return self.parse_header_line(line);
+            }
+        }
         Ok(())
     }
+
+    /// Parse a clue per the options.
+    fn parse_clue(&self, line: &str) -> Result<Constraint, ConstraintError> {
+        if line.trim() == "?" {
+            return Ok(Constraint::unknown());
+        } else if !self.options.whitespace_separators {
+            return line.parse();
+        }
+        let lengths = line
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|n| !n.is_empty())
+            .map(parse_length)
+            .collect::<Result<Vec<Option<usize>>, _>>()?;
+        Constraint::with_unknowns(lengths)
+    }
 }
 
 // ** Parser utilities
 
+/// Read all of `f` as UTF-8 text, without byte order mark, with lines
+/// ending in LF.
+pub fn read_text(f: &mut impl Read) -> Result<String, ParserError> {
+    let mut bytes = vec![];
+    f.read_to_end(&mut bytes)?;
+    let source =
+        String::from_utf8(bytes).map_err(|e| ParserError::NotUtf8(e.utf8_error().valid_up_to()))?;
+    let source = source.strip_prefix('\u{feff}').unwrap_or(&source);
+    Ok(source.replace("\r\n", "\n").replace('\r', "\n"))
+}
+
+/// Read a goal of `width` by `height` cells, written row by row as
+/// `1` for filled and `0` for empty.
+fn read_goal(goal: &str, width: usize, height: usize) -> Result<Grid, ParserError> {
+    let cells = goal
+        .chars()
+        .map(|c| match c {
+            '1' => Ok(CellState::Filled),
+            '0' => Ok(CellState::Empty),
+            _ => Err(ParserError::BadGoal),
+        })
+        .collect::<Result<Vec<CellState>, ParserError>>()?;
+    if cells.len() != width * height {
+        return Err(ParserError::BadGoal);
+    }
+    Ok(Grid::from_cells(width, height, cells))
+}
+
 /// Remove surrounding quotes from a strin.
 fn unquote(s: &str) -> String {
-    if s.starts_with("\"") && s.ends_with("\"") {
+    if s.len() >= 2 && s.starts_with("\"") && s.ends_with("\"") {
         s[1..s.len() - 1].to_string()
     } else {
         s.to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUZZLE: &str = "title \"Corner\"\nby \"Someone\"\nwidth 3\nheight 2\n\nrows\n2\n1 1\n\ncolumns\n2\n1\n1\n\ngoal \"110101\"\n";
+
+    fn parse(text: &[u8]) -> Result<Puzzle, ParserError> {
+        Parser::new().parse_puzzle(&mut &text[..])
+    }
+
+    #[test]
+    fn tabs_byte_order_marks_and_line_endings_read_the_same() {
+        let expected = parse(PUZZLE.as_bytes()).unwrap();
+        assert_eq!(expected.title.as_deref(), Some("Corner"));
+        assert_eq!((expected.width(), expected.height()), (3, 2));
+        assert!(expected.goal.is_some());
+
+        let tabs = PUZZLE.replace(' ', "\t");
+        let bom = format!("\u{feff}{}", PUZZLE);
+        let variants = [
+            tabs.clone(),
+            tabs.replace("\t\"", " \t\""),
+            bom.clone(),
+            bom.replace('\n', "\r\n"),
+            PUZZLE.replace('\n', "\r"),
+            format!("\u{feff}{}", tabs.replace('\n', "\r\n")),
+        ];
+        for text in variants {
+            let puzzle = parse(text.as_bytes()).unwrap_or_else(|e| panic!("{:?}: {}", text, e));
+            assert_eq!(puzzle, expected, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn text_must_be_utf8() {
+        let mut text = PUZZLE.as_bytes().to_vec();
+        text.insert(5, 0xff);
+        assert!(matches!(parse(&text), Err(ParserError::NotUtf8(5))));
+    }
+}
@@ -0,0 +1,191 @@
+//! Grids filled in by a player, rather than deduced by the solver.
+//!
+//! A player's grid tells apart cells they marked from cells they did
+//! not touch yet, where [Nonogram::cells] only knows what the solver
+//! proved.  On top of the marks, a layer of pencil marks holds
+//! tentative moves, which the player later commits or erases as a
+//! whole, eg to try out a hypothesis.
+
+use crate::{CellState, Nonogram, Point};
+use alloc::{vec, vec::Vec};
+use core::ops::{Index, IndexMut};
+
+// * Marks
+
+/// What a player put in a cell.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Mark {
+    Filled,
+    /// Marked as empty, usually drawn as a cross.
+    Crossed,
+}
+
+impl Mark {
+    /// The cell state this mark claims.
+    pub fn state(self) -> CellState {
+        match self {
+            Mark::Crossed => CellState::Empty,
+            Mark::Filled => CellState::Filled,
+        }
+    }
+
+    /// The mark claiming `state`, if it is decided.
+    pub fn from_state(state: CellState) -> Option<Mark> {
+        match state {
+            CellState::Undecided => None,
+            CellState::Empty => Some(Mark::Crossed),
+            CellState::Filled => Some(Mark::Filled),
+        }
+    }
+}
+
+/// A single move of the player: putting `mark` in `cell`, or erasing
+/// it with `None`, as a pencil mark or not.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Move {
+    pub cell: Point,
+    pub mark: Option<Mark>,
+    pub pencil: bool,
+}
+
+// * The player's grid
+
+/// The marks and pencil marks of a player on a grid.  Indexing by a
+/// [Point] reaches the committed marks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlayerGrid {
+    width: usize,
+    height: usize,
+    marks: Vec<Option<Mark>>,
+    pencil: Vec<Option<Mark>>,
+}
+
+impl PlayerGrid {
+    /// An untouched grid of `width` by `height` cells.
+    pub fn new(width: usize, height: usize) -> PlayerGrid {
+        PlayerGrid {
+            width,
+            height,
+            marks: vec![None; width * height],
+            pencil: vec![None; width * height],
+        }
+    }
+
+    /// An untouched grid for playing `nono`.
+    pub fn for_puzzle(nono: &Nonogram) -> PlayerGrid {
+        PlayerGrid::new(nono.width(), nono.height())
+    }
+
+    /// A grid with the decided cells of `nono` marked, eg to let a
+    /// player finish a partial solve.
+    pub fn from_grid(nono: &Nonogram) -> PlayerGrid {
+        let mut ret = PlayerGrid::for_puzzle(nono);
+        for (mark, state) in ret.marks.iter_mut().zip(&nono.cells) {
+            *mark = Mark::from_state(*state);
+        }
+        ret
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Index of the mark, or pencil mark, of `cell` for a
+    /// [crate::History].
+    pub fn cell_index(&self, cell: Point, pencil: bool) -> usize {
+        let layer = if pencil { self.marks.len() } else { 0 };
+        layer + cell.index(self.width)
+    }
+
+    pub(crate) fn index_to_cell(&self, index: usize) -> (Point, bool) {
+        let size = self.marks.len();
+        (Point::from_index(index % size, self.width), index >= size)
+    }
+
+    /// The pencil mark of `cell`.
+    pub fn pencil(&self, cell: Point) -> Option<Mark> {
+        self.pencil[cell.index(self.width)]
+    }
+
+    /// Put, or with `None` erase, a pencil mark in `cell`.
+    pub fn set_pencil(&mut self, cell: Point, mark: Option<Mark>) {
+        self.pencil[cell.index(self.width)] = mark;
+    }
+
+    /// Make a move.
+    pub fn play(&mut self, m: Move) {
+        if m.pencil {
+            self.set_pencil(m.cell, m.mark);
+        } else {
+            self[m.cell] = m.mark;
+        }
+    }
+
+    pub fn has_pencil_marks(&self) -> bool {
+        self.pencil.iter().any(Option::is_some)
+    }
+
+    /// Turn every pencil mark into a mark.
+    pub fn commit_pencil_marks(&mut self) {
+        for (mark, pencil) in self.marks.iter_mut().zip(&mut self.pencil) {
+            if let Some(p) = pencil.take() {
+                *mark = Some(p);
+            }
+        }
+    }
+
+    /// Erase every pencil mark.
+    pub fn clear_pencil_marks(&mut self) {
+        self.pencil.fill(None)
+    }
+
+    /// Erase every mark and pencil mark.
+    pub fn clear(&mut self) {
+        self.marks.fill(None);
+        self.clear_pencil_marks();
+    }
+
+    /// The state the player claims for `cell`: its pencil mark if
+    /// `with_pencil` and there is one, else its mark.
+    pub fn state(&self, cell: Point, with_pencil: bool) -> CellState {
+        let index = cell.index(self.width);
+        let mark = match self.pencil[index] {
+            Some(p) if with_pencil => Some(p),
+            _ => self.marks[index],
+        };
+        mark.map_or(CellState::Undecided, Mark::state)
+    }
+
+    /// The states the player claims, row by row, with pencil marks
+    /// if `with_pencil`.  Unmarked cells are undecided.
+    pub fn to_cells(&self, with_pencil: bool) -> Vec<CellState> {
+        (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| self.state(Point::new(x, y), with_pencil)))
+            .collect()
+    }
+
+    /// Copy [PlayerGrid::to_cells] into the grid of `nono`, eg to
+    /// render the player's grid or to solve from there.
+    pub fn apply_to(&self, nono: &mut Nonogram, with_pencil: bool) {
+        assert!(nono.width() == self.width && nono.height() == self.height);
+        nono.cells = self.to_cells(with_pencil);
+    }
+}
+
+impl Index<Point> for PlayerGrid {
+    type Output = Option<Mark>;
+
+    fn index(&self, cell: Point) -> &Self::Output {
+        &self.marks[cell.index(self.width)]
+    }
+}
+
+impl IndexMut<Point> for PlayerGrid {
+    fn index_mut(&mut self, cell: Point) -> &mut Self::Output {
+        &mut self.marks[cell.index(self.width)]
+    }
+}
@@ -0,0 +1,459 @@
+//! Puzzles and grids, apart.
+//!
+//! A [Puzzle] is the immutable definition of a nonogram: its size and
+//! clues, and what is known about it.  A [Grid] is the state of its
+//! cells, which solvers and players change.  Keeping them apart lets
+//! any number of grids share a puzzle, eg when solving it in parallel
+//! or checking several players' grids, without copying the clues.
+//!
+//! [Nonogram] holds both, for code that doesn't care.
+
+use crate::{
+    CellState, ColumnView, Constraint, LineRef, Nonogram, Point, SolveError, Solver, SolverConfig,
+};
+use alloc::{string::String, vec, vec::Vec};
+use core::fmt;
+use core::ops::{Index, IndexMut};
+
+// * Puzzles
+
+/// The definition of a nonogram.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Puzzle {
+    width: usize,
+    height: usize,
+    pub rows: Vec<Constraint>,
+    pub cols: Vec<Constraint>,
+    pub title: Option<String>,
+    pub author: Option<String>,
+    /// The intended solution, if the puzzle gives it.
+    pub goal: Option<Grid>,
+}
+
+impl Puzzle {
+    /// A puzzle of size `width` by `height`, without metadata.  There
+    /// must be one clue per row and per column.
+    pub fn new(
+        width: usize,
+        height: usize,
+        cols: Vec<Constraint>,
+        rows: Vec<Constraint>,
+    ) -> Puzzle {
+        assert!(cols.len() == width && rows.len() == height);
+        Puzzle {
+            width,
+            height,
+            rows,
+            cols,
+            title: None,
+            author: None,
+            goal: None,
+        }
+    }
+
+    /// The puzzle whose goal is `goal`, which must be complete, with
+    /// the clues of its lines.
+    pub fn from_solution(goal: &Grid) -> Puzzle {
+        assert!(goal.is_complete());
+        let rows = (0..goal.height).map(|y| Constraint::of_line(goal.row(y).unwrap()));
+        let cols = (0..goal.width).map(|x| Constraint::of_line(&goal.column(x).unwrap()));
+        let mut ret = Puzzle::new(goal.width, goal.height, cols.collect(), rows.collect());
+        ret.goal = Some(goal.clone());
+        ret
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Whether runs wrap around every line, as if the grid were a
+    /// torus, see [Constraint::wraps].
+    pub fn is_toroidal(&self) -> bool {
+        self.rows.iter().chain(&self.cols).any(Constraint::wraps)
+    }
+
+    /// Make runs wrap around every line, or none.
+    pub fn set_toroidal(&mut self, toroidal: bool) {
+        (self.rows.iter_mut().chain(&mut self.cols)).for_each(|c| c.set_wraps(toroidal));
+    }
+
+    /// An undecided grid for this puzzle.
+    pub fn grid(&self) -> Grid {
+        Grid::new(self.width, self.height)
+    }
+
+    /// Solve this puzzle with the default [SolverConfig].
+    pub fn solve(&self) -> Result<Grid, SolveError> {
+        Solver::solve_puzzle(self, SolverConfig::default())
+    }
+
+    /// A hash of the clues, equal for puzzles with the same clues
+    /// whatever their metadata, source or format.  This is stable
+    /// across runs and versions of this crate.
+    pub fn canonical_hash(&self) -> u64 {
+        fnv1a(&clue_key(&self.rows, &self.cols))
+    }
+
+    /// Like [Puzzle::canonical_hash], but also equal for puzzles that
+    /// are rotations or reflections of each other.  Toroidal puzzles
+    /// are only compared with their transpositions.
+    pub fn canonical_hash_up_to_symmetry(&self) -> u64 {
+        fnv1a(&symmetric_clue_key(&self.rows, &self.cols))
+    }
+}
+
+// * Canonical hashes
+
+/// The clues as bytes: the dimensions, then the lengths and runs of
+/// the rows and of the columns, with a length of `u64::MAX` for an
+/// unknown clue, then which clues wrap if any do.
+fn clue_key(rows: &[Constraint], cols: &[Constraint]) -> Vec<u8> {
+    let mut ret = vec![];
+    let mut push = |n: usize| ret.extend_from_slice(&(n as u64).to_le_bytes());
+    push(cols.len());
+    push(rows.len());
+    for clue in rows.iter().chain(cols) {
+        push(if clue.is_unknown() {
+            usize::MAX
+        } else {
+            clue.len()
+        });
+        clue.iter().for_each(|n| push(*n));
+    }
+    if rows.iter().chain(cols).any(Constraint::wraps) {
+        for clue in rows.iter().chain(cols) {
+            push(clue.wraps() as usize);
+        }
+    }
+    ret
+}
+
+/// The smallest [clue_key] of the eight rotations and reflections of
+/// the grid.
+fn symmetric_clue_key(rows: &[Constraint], cols: &[Constraint]) -> Vec<u8> {
+    (Transform::all(rows, cols).into_iter())
+        .map(|t| {
+            let (rows, cols) = t.apply(rows, cols);
+            clue_key(&rows, &cols)
+        })
+        .min()
+        .unwrap()
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+// * Equivalence
+
+/// A rotation or reflection of a grid: transposing it, then mirroring
+/// it left to right, top to bottom, or both, which is a half turn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Transform {
+    pub transpose: bool,
+    pub flip_x: bool,
+    pub flip_y: bool,
+}
+
+impl Transform {
+    /// The eight transforms, from the identity, except that puzzles
+    /// with clues that wrap are only transposed, since reversing its
+    /// runs doesn't mirror a line that wraps.
+    fn all(rows: &[Constraint], cols: &[Constraint]) -> Vec<Transform> {
+        let wraps = rows.iter().chain(cols).any(Constraint::wraps);
+        let mut ret = vec![];
+        for transpose in [false, true] {
+            for (flip_x, flip_y) in [(false, false), (false, true), (true, false), (true, true)] {
+                if !wraps || (!flip_x && !flip_y) {
+                    ret.push(Transform {
+                        transpose,
+                        flip_x,
+                        flip_y,
+                    });
+                }
+            }
+        }
+        ret
+    }
+
+    /// The clues of the rows and columns of the transformed grid.
+    fn apply(
+        &self,
+        rows: &[Constraint],
+        cols: &[Constraint],
+    ) -> (Vec<Constraint>, Vec<Constraint>) {
+        let (rows, cols) = match self.transpose {
+            true => (cols, rows),
+            false => (rows, cols),
+        };
+        // Mirroring left to right reverses rows, and the order of
+        // columns; top to bottom, the other way around.
+        let transform = |clues: &[Constraint], reverse_each: bool, reverse_order: bool| {
+            let mut ret: Vec<Constraint> = (clues.iter())
+                .map(|c| {
+                    if reverse_each {
+                        c.reversed()
+                    } else {
+                        c.clone()
+                    }
+                })
+                .collect();
+            if reverse_order {
+                ret.reverse();
+            }
+            ret
+        };
+        (
+            transform(rows, self.flip_x, self.flip_y),
+            transform(cols, self.flip_y, self.flip_x),
+        )
+    }
+}
+
+impl fmt::Display for Transform {
+    /// As `transposed, mirrored left to right`, or `unchanged`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = vec![];
+        if self.transpose {
+            parts.push("transposed");
+        }
+        match (self.flip_x, self.flip_y) {
+            (true, true) => parts.push("turned half a turn"),
+            (true, false) => parts.push("mirrored left to right"),
+            (false, true) => parts.push("mirrored top to bottom"),
+            (false, false) => (),
+        }
+        match parts.is_empty() {
+            true => f.write_str("unchanged"),
+            false => f.write_str(&parts.join(", ")),
+        }
+    }
+}
+
+/// How the clues of two puzzles differ, see
+/// [Nonogram::equivalent_to].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ClueDiff {
+    /// The puzzles are `ours` and `theirs` cells, as `(width, height)`.
+    Size {
+        ours: (usize, usize),
+        theirs: (usize, usize),
+    },
+    /// The puzzles have the same size, but these lines have other
+    /// clues.
+    Lines(Vec<LineRef>),
+}
+
+impl fmt::Display for ClueDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClueDiff::Size { ours, theirs } => write!(
+                f,
+                "The sizes differ: {}×{} and {}×{}.",
+                ours.0, ours.1, theirs.0, theirs.1
+            ),
+            ClueDiff::Lines(lines) => {
+                write!(f, "The clues of {} lines differ", lines.len())?;
+                for (i, line) in lines.iter().enumerate() {
+                    write!(f, "{}{}", if i == 0 { ": " } else { ", " }, line)?;
+                }
+                f.write_str(".")
+            }
+        }
+    }
+}
+
+/// Compare the clues `rows` and `cols` with `theirs`.
+fn clue_diff(
+    (rows, cols): (&[Constraint], &[Constraint]),
+    theirs: (&[Constraint], &[Constraint]),
+) -> Result<(), ClueDiff> {
+    if (cols.len(), rows.len()) != (theirs.1.len(), theirs.0.len()) {
+        return Err(ClueDiff::Size {
+            ours: (cols.len(), rows.len()),
+            theirs: (theirs.1.len(), theirs.0.len()),
+        });
+    }
+    let rows = (rows.iter().zip(theirs.0).enumerate()).map(|(y, c)| (LineRef::Row(y), c));
+    let cols = (cols.iter().zip(theirs.1).enumerate()).map(|(x, c)| (LineRef::Column(x), c));
+    let lines: Vec<LineRef> = (rows.chain(cols))
+        .filter(|(_, (ours, theirs))| ours != theirs)
+        .map(|(line, _)| line)
+        .collect();
+    match lines.is_empty() {
+        true => Ok(()),
+        false => Err(ClueDiff::Lines(lines)),
+    }
+}
+
+// * Grids
+
+/// The state of the cells of a puzzle.  Indexing by a [Point] reaches
+/// a single cell.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Grid {
+    width: usize,
+    height: usize,
+    cells: Vec<CellState>,
+}
+
+impl Grid {
+    /// A grid of `width` by `height` undecided cells.
+    pub fn new(width: usize, height: usize) -> Grid {
+        Grid {
+            width,
+            height,
+            cells: vec![CellState::Undecided; width * height],
+        }
+    }
+
+    /// A grid of `width` by `height` cells, listed row by row.
+    pub fn from_cells(width: usize, height: usize, cells: Vec<CellState>) -> Grid {
+        assert!(cells.len() == width * height);
+        Grid {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Every cell, row by row.
+    pub fn cells(&self) -> &[CellState] {
+        &self.cells
+    }
+
+    pub fn into_cells(self) -> Vec<CellState> {
+        self.cells
+    }
+
+    pub(crate) fn cells_mut(&mut self) -> &mut Vec<CellState> {
+        &mut self.cells
+    }
+
+    /// Return a copy of a column (starting at 0).
+    pub fn column(&self, x: usize) -> Option<Vec<CellState>> {
+        self.column_view(x).map(|column| column.to_vec())
+    }
+
+    /// Return a view into a column (starting at 0), without copying.
+    pub fn column_view(&self, x: usize) -> Option<ColumnView<'_>> {
+        (x < self.width).then(|| ColumnView::new(&self.cells, self.width, x))
+    }
+
+    /// Return a row (starting at 0).
+    pub fn row(&self, y: usize) -> Option<&[CellState]> {
+        (y < self.height).then(|| &self.cells[y * self.width..(y + 1) * self.width])
+    }
+
+    /// True if no cell is undecided.
+    pub fn is_complete(&self) -> bool {
+        !self.cells.contains(&CellState::Undecided)
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.fill(CellState::Undecided)
+    }
+}
+
+impl Index<Point> for Grid {
+    type Output = CellState;
+
+    fn index(&self, p: Point) -> &Self::Output {
+        &self.cells[p.index(self.width)]
+    }
+}
+
+impl IndexMut<Point> for Grid {
+    fn index_mut(&mut self, p: Point) -> &mut Self::Output {
+        &mut self.cells[p.index(self.width)]
+    }
+}
+
+// * Nonograms, as a puzzle and a grid
+
+impl Nonogram {
+    /// Put `puzzle` and `grid` together.  Metadata of the puzzle is
+    /// lost.
+    pub fn from_parts(puzzle: Puzzle, grid: Grid) -> Nonogram {
+        assert!(puzzle.width == grid.width && puzzle.height == grid.height);
+        let mut ret = Nonogram::new(puzzle.width, puzzle.height, puzzle.cols, puzzle.rows);
+        ret.cells = grid.cells;
+        ret
+    }
+
+    /// Take this nonogram apart.
+    pub fn into_parts(self) -> (Puzzle, Grid) {
+        let grid = Grid::from_cells(self.width(), self.height(), self.cells);
+        let puzzle = Puzzle::new(grid.width, grid.height, self.cols, self.rows);
+        (puzzle, grid)
+    }
+
+    /// A copy of the definition of this nonogram.
+    pub fn puzzle(&self) -> Puzzle {
+        Puzzle::new(
+            self.width(),
+            self.height(),
+            self.cols.clone(),
+            self.rows.clone(),
+        )
+    }
+
+    /// See [Puzzle::canonical_hash].
+    pub fn canonical_hash(&self) -> u64 {
+        fnv1a(&clue_key(&self.rows, &self.cols))
+    }
+
+    /// See [Puzzle::canonical_hash_up_to_symmetry].
+    pub fn canonical_hash_up_to_symmetry(&self) -> u64 {
+        fnv1a(&symmetric_clue_key(&self.rows, &self.cols))
+    }
+
+    /// Whether `other` has the same clues, whatever its grid or
+    /// metadata, eg to check a puzzle converted to another format.
+    /// The error tells the lines that differ.
+    pub fn equivalent_to(&self, other: &Nonogram) -> Result<(), ClueDiff> {
+        clue_diff((&self.rows, &self.cols), (&other.rows, &other.cols))
+    }
+
+    /// Like [Nonogram::equivalent_to], but also true for rotations and
+    /// reflections: this is how to transform this grid into `other`.
+    /// The error compares the puzzles as they are.
+    pub fn equivalent_up_to_symmetry(&self, other: &Nonogram) -> Result<Transform, ClueDiff> {
+        let found = Transform::all(&self.rows, &self.cols)
+            .into_iter()
+            .find(|t| {
+                let (rows, cols) = t.apply(&self.rows, &self.cols);
+                clue_diff((&rows, &cols), (&other.rows, &other.cols)).is_ok()
+            });
+        match found {
+            Some(transform) => Ok(transform),
+            None => self.equivalent_to(other).map(|_| Transform::default()),
+        }
+    }
+
+    /// A copy of the grid of this nonogram.
+    pub fn grid(&self) -> Grid {
+        Grid::from_cells(self.width(), self.height(), self.cells.clone())
+    }
+}
+
+impl From<Puzzle> for Nonogram {
+    fn from(puzzle: Puzzle) -> Self {
+        let grid = puzzle.grid();
+        Nonogram::from_parts(puzzle, grid)
+    }
+}
@@ -0,0 +1,86 @@
+//! Racing solvers: solve a puzzle with several configurations of the
+//! [Solver] at once, each on a thread of its own, keep whichever
+//! finishes first, and cancel the others.
+//!
+//! Search is where solves go wrong: the guess order that is quickest
+//! on most puzzles can backtrack for minutes on some that another
+//! order solves at once, and there is no telling beforehand.  A race
+//! takes about as long as its quickest racer, as long as there are
+//! cores for all of them.
+
+use crate::{Grid, GuessStrategy, Puzzle, SolveOutcome, SolveStats, Solver, SolverConfig};
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+
+/// The first racer to finish.
+#[derive(Clone, Debug)]
+pub struct Race {
+    /// The index of its configuration.
+    pub winner: usize,
+    pub outcome: SolveOutcome,
+    /// The grid it filled, solved as far as it got.
+    pub grid: Grid,
+    /// How many propagation passes it took.
+    pub passes: usize,
+    /// How many guesses it made, undone or not.
+    pub guesses: usize,
+}
+
+/// The configurations that [race] usually races: the default one,
+/// with each [GuessStrategy].
+pub fn racers() -> Vec<SolverConfig> {
+    let strategies = [
+        GuessStrategy::MostConstrainedLine,
+        GuessStrategy::FewestCandidates,
+        GuessStrategy::BorderFirst,
+    ];
+    (strategies.into_iter())
+        .map(|guess_strategy| SolverConfig {
+            guess_strategy,
+            ..SolverConfig::default()
+        })
+        .collect()
+}
+
+/// Solve `puzzle` with each of `configs` at once, and return the
+/// first to finish, whether it solved the puzzle or not, once the
+/// others have stopped.
+///
+/// # Panics
+///
+/// If `configs` is empty.
+pub fn race(puzzle: &Puzzle, configs: &[SolverConfig]) -> Race {
+    assert!(!configs.is_empty(), "A race needs racers");
+    let cancel = Arc::new(AtomicBool::new(false));
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        for (winner, config) in configs.iter().enumerate() {
+            let sender = sender.clone();
+            let cancel = Arc::clone(&cancel);
+            scope.spawn(move || {
+                let mut grid = puzzle.grid();
+                let mut solver = Solver::for_grid(puzzle, &mut grid, config.clone());
+                solver.set_cancel_flag(cancel);
+                let outcome = solver.solve();
+                let SolveStats { passes, guesses } = solver.stats();
+                drop(solver);
+                // The race may be over already.
+                let _ = sender.send(Race {
+                    winner,
+                    outcome,
+                    grid,
+                    passes,
+                    guesses,
+                });
+            });
+        }
+        drop(sender);
+        // Only the racers that panicked send nothing, and the scope
+        // passes their panics on.
+        let first = receiver.recv().expect("Every racer panicked");
+        cancel.store(true, Ordering::Relaxed);
+        first
+    })
+}
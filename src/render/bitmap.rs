@@ -0,0 +1,51 @@
+//! Plain-text bitmap formats: PBM and XPM.
+//!
+//! Both formats are one pixel per cell; scale the picture in an image
+//! viewer or editor.
+
+use crate::{CellState, Nonogram};
+
+/// Render the grid of `nono` as a plain (`P1`) PBM bitmap.  PBM is
+/// black and white only, so undecided cells are white.
+pub fn pbm(nono: &Nonogram) -> String {
+    let mut ret = format!("P1\n{} {}\n", nono.width(), nono.height());
+    for y in 0..nono.height() {
+        // Lines of a PBM file should not exceed 70 characters.
+        let row = nono.row(y).unwrap();
+        for chunk in row.chunks(35) {
+            let bits: Vec<&str> = chunk
+                .iter()
+                .map(|c| if *c == CellState::Filled { "1" } else { "0" })
+                .collect();
+            ret.push_str(&bits.join(" "));
+            ret.push('\n');
+        }
+    }
+    ret
+}
+
+/// Render the grid of `nono` as an XPM image, with undecided cells in
+/// gray.
+pub fn xpm(nono: &Nonogram) -> String {
+    let mut ret = String::from("/* XPM */\nstatic char *nonogram[] = {\n");
+    ret.push_str(&format!("\"{} {} 3 1\",\n", nono.width(), nono.height()));
+    ret.push_str("\"  c #FFFFFF\",\n");
+    ret.push_str("\"# c #000000\",\n");
+    ret.push_str("\"? c #C0C0C0\",\n");
+    for y in 0..nono.height() {
+        let row: String = nono
+            .row(y)
+            .unwrap()
+            .iter()
+            .map(|c| match c {
+                CellState::Undecided => '?',
+                CellState::Empty => ' ',
+                CellState::Filled => '#',
+            })
+            .collect();
+        let sep = if y + 1 == nono.height() { "" } else { "," };
+        ret.push_str(&format!("\"{}\"{}\n", row, sep));
+    }
+    ret.push_str("};\n");
+    ret
+}
@@ -0,0 +1,32 @@
+//! Ultra-compact text rendering with Unicode braille patterns.
+//!
+//! Each braille character packs a block of 2×4 cells, so a 100×100
+//! puzzle fits in 50 columns and 25 lines of text.  Only filled cells
+//! show: undecided and empty cells are both blank dots.
+
+use crate::{CellState, Nonogram, Point};
+
+/// Bit of the braille pattern for the cell at (dx, dy) in a 2×4
+/// block, indexed by `[dy][dx]`.
+const DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// Render the grid of `nono` in braille patterns.
+pub fn render(nono: &Nonogram) -> String {
+    let mut ret = String::new();
+    for by in (0..nono.height()).step_by(4) {
+        for bx in (0..nono.width()).step_by(2) {
+            let mut pattern = 0;
+            for (dy, dots) in DOTS.iter().enumerate() {
+                for (dx, dot) in dots.iter().enumerate() {
+                    let p = Point::new(bx + dx, by + dy);
+                    if p.within(nono.width(), nono.height()) && nono[p] == CellState::Filled {
+                        pattern |= dot;
+                    }
+                }
+            }
+            ret.push(char::from_u32(0x2800 + pattern).unwrap());
+        }
+        ret.push('\n');
+    }
+    ret
+}
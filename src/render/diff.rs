@@ -0,0 +1,41 @@
+//! Text pictures of a grid against its goal, with mistakes in red,
+//! see [crate::Nonogram::diff_against_goal].
+//!
+//! Cells are drawn with the characters of [RenderOptions], one per
+//! cell, without clues or gridlines.  Cells that don't match the goal
+//! are red, with ANSI escapes: wrongly filled or empty cells as they
+//! are in the grid, and undecided ones as they are in the goal.
+
+use super::RenderOptions;
+use crate::{CellDiff, CellState, Grid, Nonogram};
+
+const RED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[0m";
+
+/// Draw `nono` against `goal`, which has the same dimensions.
+pub fn render(nono: &Nonogram, goal: &Grid, options: &RenderOptions) -> String {
+    let diff = nono.diff_against_goal(goal);
+    let mut ret = String::new();
+    for (i, (cell, diff)) in nono.cells.iter().zip(diff).enumerate() {
+        let shown = match diff {
+            CellDiff::Missing => goal.cells()[i],
+            _ => *cell,
+        };
+        let c = match shown {
+            CellState::Filled => options.filled,
+            CellState::Empty => options.empty,
+            CellState::Undecided => options.undecided,
+        };
+        if diff == CellDiff::Match {
+            ret.push(c);
+        } else {
+            ret.push_str(RED);
+            ret.push(c);
+            ret.push_str(RESET);
+        }
+        if (i + 1) % nono.width() == 0 {
+            ret.push('\n');
+        }
+    }
+    ret
+}
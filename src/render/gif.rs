@@ -0,0 +1,218 @@
+//! Animated GIF of a solve.
+//!
+//! Frames are rebuilt from a [SolveTrace]: the first frame is the
+//! blank grid, and each following frame adds the deductions of one
+//! more propagation pass.  Record the trace with
+//! [crate::SolverConfig::trace].
+
+use super::{rasterize, PALETTE};
+use crate::trace::SolveTrace;
+use crate::{CellState, Nonogram};
+
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+
+/// How the animation looks.
+#[derive(Clone, Debug)]
+pub struct GifOptions {
+    /// Side of a cell, in pixels, including its gridline.
+    pub cell_size: usize,
+    /// Delay between frames, in hundredths of a second.
+    pub frame_delay: u16,
+    /// How long the solved grid stays on screen before looping, in
+    /// hundredths of a second.
+    pub final_delay: u16,
+}
+
+impl Default for GifOptions {
+    fn default() -> Self {
+        GifOptions {
+            cell_size: 8,
+            frame_delay: 20,
+            final_delay: 300,
+        }
+    }
+}
+
+/// The picture would be wider or taller than the 65535 pixels a GIF
+/// can hold.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GifTooLarge {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl fmt::Display for GifTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "A {}x{} pixels animation is too large for a GIF.",
+            self.width, self.height
+        )
+    }
+}
+
+impl error::Error for GifTooLarge {}
+
+/// Render the solve recorded in `trace` as an animated GIF of the grid
+/// of `nono`.
+pub fn animate(
+    nono: &Nonogram,
+    trace: &SolveTrace,
+    options: &GifOptions,
+) -> Result<Vec<u8>, GifTooLarge> {
+    let cell = options.cell_size.max(2);
+    let width = nono.width().saturating_mul(cell).saturating_add(1);
+    let height = nono.height().saturating_mul(cell).saturating_add(1);
+    let too_large = || GifTooLarge { width, height };
+    let mut gif = GifWriter::new(
+        u16::try_from(width).map_err(|_| too_large())?,
+        u16::try_from(height).map_err(|_| too_large())?,
+    );
+
+    let mut cells = vec![CellState::Undecided; nono.width() * nono.height()];
+    let mut deductions = trace.deductions.iter().peekable();
+    loop {
+        let pass = deductions.peek().map(|d| d.pass);
+        while let Some(d) = deductions.next_if(|d| Some(d.pass) == pass) {
+            cells[d.cell.index(nono.width())] = d.value;
+        }
+        let delay = if pass.is_none() {
+            options.final_delay
+        } else {
+            options.frame_delay
+        };
+        gif.frame(&rasterize(&cells, nono.width(), cell), delay);
+        if pass.is_none() {
+            break;
+        }
+    }
+    Ok(gif.finish())
+}
+
+// * GIF encoding
+
+/// A minimal GIF89a encoder for full-size, 4-color, looping
+/// animations.
+struct GifWriter {
+    out: Vec<u8>,
+    width: u16,
+    height: u16,
+}
+
+/// Smallest LZW code size GIF allows, enough for our 4 colors.
+const MIN_CODE_SIZE: u8 = 2;
+
+impl GifWriter {
+    fn new(width: u16, height: u16) -> GifWriter {
+        let mut out = b"GIF89a".to_vec();
+        // Logical screen descriptor: global color table of 2^(1+1)
+        // entries, no background, no aspect ratio.
+        out.extend(width.to_le_bytes());
+        out.extend(height.to_le_bytes());
+        out.extend([0b1000_0001, 0, 0]);
+        for color in PALETTE {
+            out.extend(color);
+        }
+        // NETSCAPE2.0 application extension: loop forever.
+        out.extend([0x21, 0xff, 0x0b]);
+        out.extend(b"NETSCAPE2.0");
+        out.extend([0x03, 0x01, 0x00, 0x00, 0x00]);
+        GifWriter { out, width, height }
+    }
+
+    /// Add a frame of `width * height` palette indices.
+    fn frame(&mut self, pixels: &[u8], delay: u16) {
+        // Graphic control extension: no disposal, no transparency.
+        self.out.extend([0x21, 0xf9, 0x04, 0x00]);
+        self.out.extend(delay.to_le_bytes());
+        self.out.extend([0x00, 0x00]);
+        // Image descriptor: full screen, no local color table.
+        self.out.push(0x2c);
+        self.out.extend([0, 0, 0, 0]);
+        self.out.extend(self.width.to_le_bytes());
+        self.out.extend(self.height.to_le_bytes());
+        self.out.push(0);
+        // Image data, in sub-blocks of at most 255 bytes.
+        self.out.push(MIN_CODE_SIZE);
+        for block in lzw_compress(pixels).chunks(255) {
+            self.out.push(block.len() as u8);
+            self.out.extend(block);
+        }
+        self.out.push(0);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        self.out.push(0x3b);
+        self.out
+    }
+}
+
+/// Compress palette indices with GIF's variant of LZW.
+fn lzw_compress(pixels: &[u8]) -> Vec<u8> {
+    let clear: u16 = 1 << MIN_CODE_SIZE;
+    let end = clear + 1;
+
+    let mut bits = BitWriter::default();
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut code_size = MIN_CODE_SIZE + 1;
+    let mut next = end + 1;
+
+    bits.write(clear, code_size);
+    let Some((&first, rest)) = pixels.split_first() else {
+        bits.write(end, code_size);
+        return bits.finish();
+    };
+    let mut prefix = first as u16;
+    for &pixel in rest {
+        if let Some(&code) = table.get(&(prefix, pixel)) {
+            prefix = code;
+            continue;
+        }
+        bits.write(prefix, code_size);
+        table.insert((prefix, pixel), next);
+        next += 1;
+        if next > 1 << code_size {
+            code_size += 1;
+        }
+        if next == 4096 {
+            // The table is full: start over.
+            bits.write(clear, code_size);
+            table.clear();
+            code_size = MIN_CODE_SIZE + 1;
+            next = end + 1;
+        }
+        prefix = pixel as u16;
+    }
+    bits.write(prefix, code_size);
+    bits.write(end, code_size);
+    bits.finish()
+}
+
+/// Packs codes least significant bit first, as GIF wants.
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    count: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u8) {
+        self.buffer |= (code as u32) << self.count;
+        self.count += size;
+        while self.count >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
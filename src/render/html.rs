@@ -0,0 +1,97 @@
+//! Standalone HTML page of a puzzle.
+//!
+//! The page holds a single `<table>`: column clues along the top, row
+//! clues along the left, and the grid.  Hovering a clue, or a cell,
+//! highlights its row or column.  Styles and the highlighting script
+//! are inline, so the page can be embedded or shared as a single file.
+
+use crate::{CellState, Nonogram, Point};
+
+use std::fmt::Write;
+
+const STYLE: &str = "\
+table.nonogram { border-collapse: collapse; font-family: sans-serif; font-size: 12px; }
+table.nonogram th { font-weight: normal; color: #444; padding: 0 3px; }
+table.nonogram th.col { vertical-align: bottom; text-align: center; line-height: 1.2; }
+table.nonogram th.row { text-align: right; white-space: nowrap; }
+table.nonogram td { width: 16px; height: 16px; border: 1px solid #999; padding: 0; }
+table.nonogram td.filled { background: #222; }
+table.nonogram td.empty { background: #fff; }
+table.nonogram td.undecided { background: #ccc; }
+table.nonogram th.hl, table.nonogram td.hl { background: #fe9; }
+table.nonogram td.filled.hl { background: #b80; }
+";
+
+const SCRIPT: &str = "\
+document.querySelectorAll('table.nonogram [data-row], table.nonogram [data-col]').forEach(function (el) {
+  function lines(on) {
+    ['row', 'col'].forEach(function (axis) {
+      var index = el.dataset[axis];
+      if (index === undefined) return;
+      document.querySelectorAll('table.nonogram [data-' + axis + '=\"' + index + '\"]')
+        .forEach(function (other) { other.classList.toggle('hl', on); });
+    });
+  }
+  el.addEventListener('mouseenter', function () { lines(true); });
+  el.addEventListener('mouseleave', function () { lines(false); });
+});
+";
+
+/// Render `nono`, with its current grid, as a complete HTML page.
+pub fn render(nono: &Nonogram) -> String {
+    let mut ret = String::new();
+    ret.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    ret.push_str("<title>Nonogram</title>\n<style>\n");
+    ret.push_str(STYLE);
+    ret.push_str("</style>\n</head>\n<body>\n");
+    ret.push_str(&table(nono));
+    ret.push_str("<script>\n");
+    ret.push_str(SCRIPT);
+    ret.push_str("</script>\n</body>\n</html>\n");
+    ret
+}
+
+/// Render only the `<table>` of `nono`, to embed in another page.
+/// Highlighting then needs the page to include the script of
+/// [render].
+pub fn table(nono: &Nonogram) -> String {
+    let mut ret = String::from("<table class=\"nonogram\">\n<tr><th></th>");
+    for (x, clue) in nono.cols.iter().enumerate() {
+        let numbers = clue.numbers();
+        write!(
+            ret,
+            "<th class=\"col\" data-col=\"{}\">{}</th>",
+            x,
+            numbers.join("<br>")
+        )
+        .unwrap();
+    }
+    ret.push_str("</tr>\n");
+
+    for (y, clue) in nono.rows.iter().enumerate() {
+        let numbers = clue.numbers();
+        write!(
+            ret,
+            "<tr><th class=\"row\" data-row=\"{}\">{}</th>",
+            y,
+            numbers.join("&nbsp;")
+        )
+        .unwrap();
+        for x in 0..nono.width() {
+            let class = match nono[Point::new(x, y)] {
+                CellState::Undecided => "undecided",
+                CellState::Empty => "empty",
+                CellState::Filled => "filled",
+            };
+            write!(
+                ret,
+                "<td class=\"{}\" data-row=\"{}\" data-col=\"{}\"></td>",
+                class, y, x
+            )
+            .unwrap();
+        }
+        ret.push_str("</tr>\n");
+    }
+    ret.push_str("</table>\n");
+    ret
+}
@@ -0,0 +1,60 @@
+//! A Markdown table of a puzzle, to paste in issues and wikis.
+//!
+//! The header holds the column clues, a number per line, the first
+//! column the row clues, and the other cells are `⬛` when filled,
+//! `⬜` when empty and `❔` when undecided, all emoji so that cells
+//! line up in the source:
+//!
+//! <pre>
+//! |     | 1 | 1 | 2 |
+//! | --: |:-:|:-:|:-:|
+//! |   2 | ❔ | ❔ | ❔ |
+//! | 1 1 | ❔ | ❔ | ❔ |
+//! </pre>
+//!
+//! The numbers of column clues are separated by `<br>`, which GitHub,
+//! and most forges and wikis, read in tables.
+
+use crate::writer::{self, ClueStyle};
+use crate::{CellState, Nonogram, Point};
+
+use std::fmt::Write;
+
+/// A number per line, in a table header.
+const COLUMN_CLUES: ClueStyle = ClueStyle {
+    separator: "<br>",
+    ..ClueStyle::compact()
+};
+
+/// Render `nono`, with its current grid, as a Markdown table.
+pub fn render(nono: &Nonogram) -> String {
+    let row_clues = writer::lines(&nono.rows, &ClueStyle::printed());
+    // Some renderers need three dashes to tell the delimiter row.
+    let width = (row_clues.iter().map(|c| c.chars().count()))
+        .max()
+        .unwrap_or(0)
+        .max(3);
+
+    let mut ret = format!("| {:width$} |", "");
+    for clue in &nono.cols {
+        write!(ret, " {} |", writer::clue(clue, &COLUMN_CLUES)).unwrap();
+    }
+    // Row clues to the right, cells centered.
+    write!(ret, "\n| {}: |", "-".repeat(width - 1)).unwrap();
+    ret.push_str(&":-:|".repeat(nono.width()));
+    ret.push('\n');
+
+    for (y, clue) in row_clues.iter().enumerate() {
+        write!(ret, "| {:>width$} |", clue).unwrap();
+        for x in 0..nono.width() {
+            let cell = match nono[Point::new(x, y)] {
+                CellState::Undecided => "❔",
+                CellState::Empty => "⬜",
+                CellState::Filled => "⬛",
+            };
+            write!(ret, " {} |", cell).unwrap();
+        }
+        ret.push('\n');
+    }
+    ret
+}
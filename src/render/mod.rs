@@ -0,0 +1,62 @@
+//! Renderers producing pictures of nonograms in various formats.
+//!
+//! Most are also available as methods of [crate::Nonogram], like
+//! [crate::Nonogram::as_text] for [text].
+
+pub mod bitmap;
+pub mod braille;
+pub mod diff;
+#[cfg(feature = "animate")]
+pub mod gif;
+pub mod html;
+pub mod markdown;
+#[cfg(feature = "pdf")]
+pub mod pdf;
+pub mod svg;
+pub mod terminal;
+pub mod text;
+pub mod tikz;
+
+pub use text::{Charset, RenderOptions};
+
+use crate::CellState;
+
+// * Rasterization, shared by the image renderers
+
+/// Palette indices of [rasterize].
+pub(crate) const UNDECIDED: u8 = 0;
+pub(crate) const EMPTY: u8 = 1;
+pub(crate) const FILLED: u8 = 2;
+pub(crate) const GRIDLINE: u8 = 3;
+
+/// RGB colors of the palette indices of [rasterize].
+pub(crate) const PALETTE: [[u8; 3]; 4] = [
+    [0xc0, 0xc0, 0xc0],
+    [0xff, 0xff, 0xff],
+    [0x00, 0x00, 0x00],
+    [0x80, 0x80, 0x80],
+];
+
+/// Draw each cell as a `cell`-pixel square with a gridline on its top
+/// and left edges, plus a closing gridline at the bottom and right.
+/// The result has `(columns * cell + 1) * (rows * cell + 1)` palette
+/// indices, row by row.
+pub(crate) fn rasterize(cells: &[CellState], columns: usize, cell: usize) -> Vec<u8> {
+    let rows = cells.len() / columns;
+    let width = columns * cell + 1;
+    let height = rows * cell + 1;
+    let mut pixels = vec![GRIDLINE; width * height];
+    for py in 0..height {
+        for px in 0..width {
+            if px % cell == 0 || py % cell == 0 {
+                continue;
+            }
+            pixels[py * width + px] = match cells[(py / cell) * columns + px / cell] {
+                CellState::Undecided => UNDECIDED,
+                CellState::Empty => EMPTY,
+                CellState::Filled => FILLED,
+            };
+        }
+    }
+    pixels
+}
@@ -0,0 +1,410 @@
+//! PDF documents of puzzles, for printing, with feature `pdf`.
+//!
+//! [render] draws a single puzzle on a page as large as the picture
+//! plus a margin, so print it scaled to fit the paper.  [book] lays
+//! out many puzzles on pages of a given size, with titles and
+//! optionally their solutions, for booklets.
+//!
+//! Everything is vector graphics, and the text uses Helvetica, which
+//! PDF viewers provide, so documents embed no font.  Titles only keep
+//! their ASCII characters, since that's all Helvetica is sure to
+//! have without an encoding.
+
+use crate::{CellState, Constraint, Nonogram, Puzzle, Solver, SolverConfig};
+
+use std::fmt::Write;
+
+/// How [render] draws a puzzle.
+#[derive(Clone, Debug)]
+pub struct PdfOptions {
+    /// Side of a cell, in points (1/72 of an inch).
+    pub cell_size: f64,
+    /// Draw the filled cells of the grid.  Without this, the document
+    /// is the blank puzzle.
+    pub show_solution: bool,
+    /// Draw a heavier gridline every that many cells; 0 for none.
+    pub thick_every: usize,
+}
+
+impl Default for PdfOptions {
+    fn default() -> Self {
+        PdfOptions {
+            cell_size: 14.0,
+            show_solution: true,
+            thick_every: 5,
+        }
+    }
+}
+
+/// How [book] lays out puzzles.
+#[derive(Clone, Debug)]
+pub struct BookOptions {
+    /// Size of the pages, in points.  The default is A4.
+    pub page_width: f64,
+    pub page_height: f64,
+    /// Blank space around the pages, and between puzzles, in points.
+    pub margin: f64,
+    /// The largest side of a cell, in points.  Puzzles too large for
+    /// a page at that size are drawn smaller, and solutions at half
+    /// that size.
+    pub cell_size: f64,
+    /// Draw a heavier gridline every that many cells; 0 for none.
+    pub thick_every: usize,
+    /// Add pages with the solutions of the puzzles, after all of the
+    /// puzzles.  These are the goals of the puzzles that have one,
+    /// and what the solver finds for the others.
+    pub solutions: bool,
+}
+
+impl Default for BookOptions {
+    fn default() -> Self {
+        BookOptions {
+            page_width: 595.0,
+            page_height: 842.0,
+            margin: 36.0,
+            cell_size: 12.0,
+            thick_every: 5,
+            solutions: false,
+        }
+    }
+}
+
+/// Width of the digits and `?` of Helvetica, in ems.
+const DIGIT_WIDTH: f64 = 0.556;
+
+/// Font size of titles, in points.
+const TITLE_SIZE: f64 = 10.0;
+
+/// Render `nono` as a PDF document.
+pub fn render(nono: &Nonogram, options: &PdfOptions) -> Vec<u8> {
+    let cell = options.cell_size;
+    let figure = Figure {
+        rows: &nono.rows,
+        cols: &nono.cols,
+        clues: true,
+        cells: options.show_solution.then_some(&nono.cells[..]),
+        thick_every: options.thick_every,
+    };
+    let (w, h) = figure.size();
+    let mut page = Page::new(cell * (w + 2.0), cell * (h + 2.0));
+    figure.draw(&mut page, cell, cell, cell);
+    document(&[page])
+}
+
+/// Lay out `puzzles` blank, in this order, as a PDF document.  Each
+/// puzzle is titled with its number from 1, and its title if it has
+/// one.
+pub fn book(puzzles: &[Puzzle], options: &BookOptions) -> Vec<u8> {
+    let titles: Vec<String> = (puzzles.iter().enumerate())
+        .map(|(i, puzzle)| match &puzzle.title {
+            Some(title) => format!("{}. {}", i + 1, title),
+            None => format!("{}.", i + 1),
+        })
+        .collect();
+
+    let mut layout = Layout::new(options);
+    for (puzzle, title) in puzzles.iter().zip(&titles) {
+        let figure = Figure {
+            rows: &puzzle.rows,
+            cols: &puzzle.cols,
+            clues: true,
+            cells: None,
+            thick_every: options.thick_every,
+        };
+        layout.place(title, &figure, options.cell_size);
+    }
+
+    if options.solutions {
+        layout.break_page();
+        for (puzzle, title) in puzzles.iter().zip(&titles) {
+            let solution = match &puzzle.goal {
+                Some(goal) => Some(goal.clone()),
+                None => Solver::solve_puzzle(puzzle, SolverConfig::default()).ok(),
+            };
+            let figure = Figure {
+                rows: &puzzle.rows,
+                cols: &puzzle.cols,
+                clues: false,
+                cells: solution.as_ref().map(|grid| grid.cells()),
+                thick_every: options.thick_every,
+            };
+            let title = match solution {
+                Some(_) => title.clone(),
+                None => format!("{} (no solution)", title),
+            };
+            layout.place(&title, &figure, options.cell_size / 2.0);
+        }
+    }
+    document(&layout.finish())
+}
+
+// * Drawing
+
+/// A page being drawn, in points from its top left corner.
+struct Page {
+    width: f64,
+    height: f64,
+    content: String,
+}
+
+impl Page {
+    fn new(width: f64, height: f64) -> Page {
+        Page {
+            width,
+            height,
+            content: String::from("0 g\n"),
+        }
+    }
+
+    fn fill(&mut self, x: f64, y: f64, width: f64, height: f64) {
+        let y = self.height - y - height;
+        writeln!(
+            self.content,
+            "{:.2} {:.2} {:.2} {:.2} re f",
+            x, y, width, height
+        )
+        .unwrap();
+    }
+
+    fn line(&mut self, heavy: bool, (x1, y1): (f64, f64), (x2, y2): (f64, f64)) {
+        let (gray, width) = if heavy { (0.0, 1.2) } else { (0.5, 0.4) };
+        let (y1, y2) = (self.height - y1, self.height - y2);
+        writeln!(
+            self.content,
+            "{} G {} w {:.2} {:.2} m {:.2} {:.2} l S",
+            gray, width, x1, y1, x2, y2
+        )
+        .unwrap();
+    }
+
+    /// Write `text` with its baseline at `y`, starting at `x`.
+    fn text(&mut self, x: f64, y: f64, size: f64, text: &str) {
+        let mut escaped = String::new();
+        for c in text.chars() {
+            match c {
+                '(' | ')' | '\\' => {
+                    escaped.push('\\');
+                    escaped.push(c);
+                }
+                ' '..='~' => escaped.push(c),
+                _ => escaped.push('?'),
+            }
+        }
+        writeln!(
+            self.content,
+            "BT /F1 {:.2} Tf {:.2} {:.2} Td ({}) Tj ET",
+            size,
+            x,
+            self.height - y,
+            escaped
+        )
+        .unwrap();
+    }
+}
+
+/// A puzzle to draw: its grid, with its clues on the top and the left.
+struct Figure<'a> {
+    rows: &'a [Constraint],
+    cols: &'a [Constraint],
+    clues: bool,
+    /// The cells to fill, row by row, or None for a blank grid.
+    cells: Option<&'a [CellState]>,
+    thick_every: usize,
+}
+
+impl Figure<'_> {
+    /// How many cells the clues take on the left and on the top.
+    fn clue_margins(&self) -> (usize, usize) {
+        if !self.clues {
+            return (0, 0);
+        }
+        let longest = |clues: &[Constraint]| clues.iter().map(|c| c.numbers().len()).max();
+        (
+            longest(self.rows).unwrap_or(0),
+            longest(self.cols).unwrap_or(0),
+        )
+    }
+
+    /// The width and height, in cells.
+    fn size(&self) -> (f64, f64) {
+        let (left, top) = self.clue_margins();
+        (
+            (left + self.cols.len()) as f64,
+            (top + self.rows.len()) as f64,
+        )
+    }
+
+    /// Draw this with its top left corner at `(x, y)`.
+    fn draw(&self, page: &mut Page, x: f64, y: f64, cell: f64) {
+        let (w, h) = (self.cols.len(), self.rows.len());
+        let (left, top) = self.clue_margins();
+        let px = |col: f64| x + cell * (left as f64 + col);
+        let py = |row: f64| y + cell * (top as f64 + row);
+
+        if let Some(cells) = self.cells {
+            for (i, _) in (cells.iter().enumerate()).filter(|(_, c)| **c == CellState::Filled) {
+                let (col, row) = ((i % w) as f64, (i / w) as f64);
+                page.fill(px(col), py(row), cell, cell);
+            }
+        }
+
+        let heavy = |i: usize, len: usize| i == len || i.is_multiple_of(self.thick_every);
+        for pass in [false, true] {
+            for col in (0..=w).filter(|&col| heavy(col, w) == pass) {
+                let col = px(col as f64);
+                page.line(pass, (col, py(0.0)), (col, py(h as f64)));
+            }
+            for row in (0..=h).filter(|&row| heavy(row, h) == pass) {
+                let row = py(row as f64);
+                page.line(pass, (px(0.0), row), (px(w as f64), row));
+            }
+        }
+
+        if !self.clues {
+            return;
+        }
+        let size = cell * 0.6;
+        let mut number = |col: f64, row: f64, n: &str| {
+            let x = px(col + 0.5) - n.len() as f64 * DIGIT_WIDTH * size / 2.0;
+            page.text(x, py(row + 0.75), size, n);
+        };
+        for (col, clue) in self.cols.iter().enumerate() {
+            let numbers = clue.numbers();
+            for (i, n) in numbers.iter().enumerate() {
+                number(col as f64, i as f64 - numbers.len() as f64, n);
+            }
+        }
+        for (row, clue) in self.rows.iter().enumerate() {
+            let numbers = clue.numbers();
+            for (i, n) in numbers.iter().enumerate() {
+                number(i as f64 - numbers.len() as f64, row as f64, n);
+            }
+        }
+    }
+}
+
+// * Layout
+
+/// Places titled figures left to right, then top to bottom, on pages
+/// of a [BookOptions].
+struct Layout<'a> {
+    options: &'a BookOptions,
+    pages: Vec<Page>,
+    page: Page,
+    /// Where the next figure goes, if it fits.
+    x: f64,
+    y: f64,
+    /// The height of the tallest figure on the current line.
+    line_height: f64,
+}
+
+impl<'a> Layout<'a> {
+    fn new(options: &'a BookOptions) -> Layout<'a> {
+        Layout {
+            options,
+            pages: vec![],
+            page: Page::new(options.page_width, options.page_height),
+            x: options.margin,
+            y: options.margin,
+            line_height: 0.0,
+        }
+    }
+
+    /// Draw `figure` under `title`, with cells of side at most `cell`,
+    /// where there's space.
+    fn place(&mut self, title: &str, figure: &Figure, cell: f64) {
+        let margin = self.options.margin;
+        let (right, bottom) = (self.page.width - margin, self.page.height - margin);
+        let title_height = TITLE_SIZE * 1.5;
+        let (w, h) = figure.size();
+        let cell = cell
+            .min((right - margin) / w.max(1.0))
+            .min((bottom - margin - title_height) / h.max(1.0))
+            .max(0.0);
+        let (width, height) = (cell * w, title_height + cell * h);
+
+        if self.x > margin && self.x + width > right {
+            self.x = margin;
+            self.y += self.line_height + margin;
+            self.line_height = 0.0;
+        }
+        if self.y > margin && self.y + height > bottom {
+            self.break_page();
+        }
+        self.page
+            .text(self.x, self.y + TITLE_SIZE, TITLE_SIZE, title);
+        figure.draw(&mut self.page, self.x, self.y + title_height, cell);
+        self.x += width + margin;
+        self.line_height = self.line_height.max(height);
+    }
+
+    /// Go on with a new page, unless the current one is still blank.
+    fn break_page(&mut self) {
+        if self.x == self.options.margin && self.y == self.options.margin {
+            return;
+        }
+        let page = Page::new(self.options.page_width, self.options.page_height);
+        self.pages.push(std::mem::replace(&mut self.page, page));
+        (self.x, self.y) = (self.options.margin, self.options.margin);
+        self.line_height = 0.0;
+    }
+
+    fn finish(mut self) -> Vec<Page> {
+        self.pages.push(self.page);
+        self.pages
+    }
+}
+
+// * Document
+
+/// The PDF document of `pages`.
+fn document(pages: &[Page]) -> Vec<u8> {
+    // The catalog, the page tree and the font come first, then each
+    // page and its contents.
+    let kids: Vec<String> = (0..pages.len())
+        .map(|i| format!("{} 0 R", 4 + 2 * i))
+        .collect();
+    let mut objects = vec![
+        String::from("<< /Type /Catalog /Pages 2 0 R >>"),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            pages.len()
+        ),
+        String::from("<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>"),
+    ];
+    for (i, page) in pages.iter().enumerate() {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] \
+             /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+            page.width,
+            page.height,
+            5 + 2 * i
+        ));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            page.content.len(),
+            page.content
+        ));
+    }
+
+    let mut ret = String::from("%PDF-1.4\n");
+    let mut offsets = vec![];
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(ret.len());
+        writeln!(ret, "{} 0 obj\n{}\nendobj", i + 1, object).unwrap();
+    }
+    let xref = ret.len();
+    writeln!(ret, "xref\n0 {}\n0000000000 65535 f ", objects.len() + 1).unwrap();
+    for offset in offsets {
+        writeln!(ret, "{:010} 00000 n ", offset).unwrap();
+    }
+    writeln!(
+        ret,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF",
+        objects.len() + 1,
+        xref
+    )
+    .unwrap();
+    ret.into_bytes()
+}
@@ -0,0 +1,92 @@
+//! SVG picture of a puzzle, with its clues and current grid.
+//!
+//! The picture is a standalone `<svg>` element, which browsers show
+//! as is and which can be inlined into HTML.
+
+use crate::{CellState, Nonogram, Point};
+
+use std::fmt::Write;
+
+/// How the picture looks.
+#[derive(Clone, Debug)]
+pub struct SvgOptions {
+    /// Side of a cell, in pixels.
+    pub cell_size: usize,
+    /// Draw the grid.  Without this, every cell is white, which is
+    /// the blank puzzle.
+    pub show_solution: bool,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        SvgOptions {
+            cell_size: 20,
+            show_solution: true,
+        }
+    }
+}
+
+/// Render `nono`, with its current grid.  Undecided cells are grey,
+/// and its clues go along the top and the left.
+pub fn render(nono: &Nonogram) -> String {
+    render_with(nono, &SvgOptions::default())
+}
+
+/// Render `nono` as `options` say.
+pub fn render_with(nono: &Nonogram, options: &SvgOptions) -> String {
+    let cell = options.cell_size;
+    let (w, h) = (nono.width(), nono.height());
+    let col_clues: Vec<Vec<String>> = nono.cols.iter().map(|c| c.numbers()).collect();
+    let row_clues: Vec<Vec<String>> = nono.rows.iter().map(|c| c.numbers()).collect();
+    let top = cell * col_clues.iter().map(Vec::len).max().unwrap_or(0);
+    let left = cell * row_clues.iter().map(Vec::len).max().unwrap_or(0);
+
+    let mut ret = String::new();
+    writeln!(
+        ret,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+         font-family=\"sans-serif\" font-size=\"{}\" text-anchor=\"middle\">",
+        left + cell * w + 1,
+        top + cell * h + 1,
+        cell * 3 / 5
+    )
+    .unwrap();
+
+    for (x, clue) in col_clues.iter().enumerate() {
+        for (i, n) in clue.iter().enumerate() {
+            let y = top - cell * (clue.len() - i) + cell * 3 / 4;
+            let x = left + cell * x + cell / 2;
+            writeln!(ret, "<text x=\"{}\" y=\"{}\">{}</text>", x, y, n).unwrap();
+        }
+    }
+    for (y, clue) in row_clues.iter().enumerate() {
+        for (i, n) in clue.iter().enumerate() {
+            let x = left - cell * (clue.len() - i) + cell / 2;
+            let y = top + cell * y + cell * 3 / 4;
+            writeln!(ret, "<text x=\"{}\" y=\"{}\">{}</text>", x, y, n).unwrap();
+        }
+    }
+
+    for y in 0..h {
+        for x in 0..w {
+            let fill = match nono[Point::new(x, y)] {
+                _ if !options.show_solution => "#fff",
+                CellState::Filled => "#222",
+                CellState::Empty => "#fff",
+                CellState::Undecided => "#ccc",
+            };
+            writeln!(
+                ret,
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" stroke=\"#999\"/>",
+                left + cell * x,
+                top + cell * y,
+                cell,
+                cell,
+                fill
+            )
+            .unwrap();
+        }
+    }
+    ret.push_str("</svg>\n");
+    ret
+}
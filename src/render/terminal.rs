@@ -0,0 +1,119 @@
+//! Inline images for terminals with graphics support.
+//!
+//! [sixel] works in terminals implementing DEC sixel graphics (xterm
+//! with `-ti vt340`, mlterm, foot, WezTerm...), and [kitty] in those
+//! implementing the Kitty graphics protocol (kitty, WezTerm, Konsole).
+//! Both return escape sequences to print as is.
+
+use super::{rasterize, PALETTE};
+use crate::Nonogram;
+
+/// Render the grid of `nono` as a sixel image, with cells of
+/// `cell_size` pixels.
+pub fn sixel(nono: &Nonogram, cell_size: usize) -> String {
+    let cell = cell_size.max(2);
+    let width = nono.width() * cell + 1;
+    let height = nono.height() * cell + 1;
+    let pixels = rasterize(&nono.cells, nono.width(), cell);
+
+    // Enter sixel mode with square pixels, and declare the palette
+    // in RGB percentages.
+    let mut ret = format!("\x1bPq\"1;1;{};{}", width, height);
+    for (i, [r, g, b]) in PALETTE.iter().enumerate() {
+        let pct = |c: &u8| *c as u32 * 100 / 255;
+        ret.push_str(&format!("#{};2;{};{};{}", i, pct(r), pct(g), pct(b)));
+    }
+
+    // Each band is six rows of pixels, drawn once per color.
+    for band in (0..height).step_by(6) {
+        for color in 0..PALETTE.len() as u8 {
+            ret.push_str(&format!("#{}", color));
+            let sixels = (0..width).map(|x| {
+                let mut bits = 0;
+                for dy in 0..6 {
+                    let y = band + dy;
+                    if y < height && pixels[y * width + x] == color {
+                        bits |= 1 << dy;
+                    }
+                }
+                (63 + bits) as u8 as char
+            });
+            push_run_length(&mut ret, sixels);
+            // Back to the start of the band for the next color.
+            ret.push('$');
+        }
+        ret.push('-');
+    }
+    ret.push_str("\x1b\\");
+    ret
+}
+
+/// Append `chars` to `out` with sixel run-length encoding.
+fn push_run_length(out: &mut String, chars: impl Iterator<Item = char>) {
+    let mut chars = chars.peekable();
+    while let Some(c) = chars.next() {
+        let mut count = 1;
+        while chars.next_if_eq(&c).is_some() {
+            count += 1;
+        }
+        if count > 3 {
+            out.push_str(&format!("!{}{}", count, c));
+        } else {
+            (0..count).for_each(|_| out.push(c));
+        }
+    }
+}
+
+/// Render the grid of `nono` as an image for the Kitty graphics
+/// protocol, with cells of `cell_size` pixels.
+pub fn kitty(nono: &Nonogram, cell_size: usize) -> String {
+    let cell = cell_size.max(2);
+    let width = nono.width() * cell + 1;
+    let height = nono.height() * cell + 1;
+    let rgb: Vec<u8> = rasterize(&nono.cells, nono.width(), cell)
+        .iter()
+        .flat_map(|p| PALETTE[*p as usize])
+        .collect();
+    let payload = base64(&rgb);
+
+    // The payload is sent in chunks of at most 4096 bytes; m=1 means
+    // more chunks follow.
+    let mut ret = String::new();
+    let chunks: Vec<&[u8]> = payload.as_bytes().chunks(4096).collect();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = if i + 1 < chunks.len() { 1 } else { 0 };
+        if i == 0 {
+            ret.push_str(&format!(
+                "\x1b_Ga=T,f=24,s={},v={},m={};",
+                width, height, more
+            ));
+        } else {
+            ret.push_str(&format!("\x1b_Gm={};", more));
+        }
+        ret.push_str(std::str::from_utf8(chunk).unwrap());
+        ret.push_str("\x1b\\");
+    }
+    ret.push('\n');
+    ret
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut ret = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                ret.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                ret.push('=');
+            }
+        }
+    }
+    ret
+}
@@ -0,0 +1,351 @@
+//! Plain text pictures of a puzzle, one character (or a few, when
+//! clues are shown) per cell.
+//!
+//! [RenderOptions::default] reproduces [crate::Nonogram::as_text];
+//! [RenderOptions::ascii] sticks to ASCII, for dumb terminals and
+//! logs, and [RenderOptions::printed] looks like puzzles in print.
+
+use crate::writer::{self, ClueStyle};
+use crate::{CellState, Constraint, Nonogram, Point};
+
+/// Characters used to draw gridlines.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Charset {
+    /// `|`, `-` and `+`, with `=` for heavy horizontal lines.
+    Ascii,
+    /// Box-drawing characters.
+    #[default]
+    Unicode,
+}
+
+/// Box-drawing junctions, by position (top, middle, bottom rows of
+/// left, middle, right) then by weight (light, heavy vertical, heavy
+/// horizontal, heavy).
+const JUNCTIONS: [[char; 4]; 9] = [
+    ['┌', '┎', '┍', '┏'],
+    ['┬', '┰', '┯', '┳'],
+    ['┐', '┒', '┑', '┓'],
+    ['├', '┠', '┝', '┣'],
+    ['┼', '╂', '┿', '╋'],
+    ['┤', '┨', '┥', '┫'],
+    ['└', '┖', '┕', '┗'],
+    ['┴', '┸', '┷', '┻'],
+    ['┘', '┚', '┙', '┛'],
+];
+
+impl Charset {
+    fn vertical(self, heavy: bool) -> char {
+        match (self, heavy) {
+            (Charset::Ascii, _) => '|',
+            (Charset::Unicode, false) => '│',
+            (Charset::Unicode, true) => '┃',
+        }
+    }
+
+    fn horizontal(self, heavy: bool) -> char {
+        match (self, heavy) {
+            (Charset::Ascii, false) => '-',
+            (Charset::Ascii, true) => '=',
+            (Charset::Unicode, false) => '─',
+            (Charset::Unicode, true) => '━',
+        }
+    }
+
+    /// Where a vertical and a horizontal line cross.
+    fn junction(self, vertical: Line, horizontal: Line) -> char {
+        match self {
+            Charset::Ascii => '+',
+            Charset::Unicode => {
+                let weight = vertical.heavy as usize + 2 * horizontal.heavy as usize;
+                JUNCTIONS[3 * horizontal.edge + vertical.edge][weight]
+            }
+        }
+    }
+}
+
+/// How the text looks.
+#[derive(Clone, Debug)]
+pub struct RenderOptions {
+    pub charset: Charset,
+    pub filled: char,
+    pub empty: char,
+    pub undecided: char,
+    /// Draw a gridline every that many cells; 0 for none.  With
+    /// [RenderOptions::boxed], these are the heavier lines.
+    pub gridlines: usize,
+    /// Draw a line around every cell, and a border around the grid.
+    pub boxed: bool,
+    /// Number rows and columns, from 0 as in `nono[Point::new(x, y)]`.
+    pub coordinates: bool,
+    /// Number every fifth row and column from 1, or every
+    /// [RenderOptions::gridlines], along the bottom and right edges.
+    pub rulers: bool,
+    /// Print row clues on the left and column clues on top.
+    pub clues: bool,
+    /// Strike through the numbers of the clues whose runs are in
+    /// place in the grid, see [Constraint::satisfied_runs], with ANSI
+    /// escapes.
+    pub strike_clues: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            charset: Charset::Unicode,
+            filled: '█',
+            empty: ' ',
+            undecided: '?',
+            gridlines: 0,
+            boxed: false,
+            coordinates: false,
+            rulers: false,
+            clues: false,
+            strike_clues: false,
+        }
+    }
+}
+
+impl RenderOptions {
+    /// Only ASCII characters, with visible empty cells.
+    pub fn ascii() -> Self {
+        RenderOptions {
+            charset: Charset::Ascii,
+            filled: '#',
+            empty: '.',
+            undecided: '?',
+            ..RenderOptions::default()
+        }
+    }
+
+    /// Laid out like a printed puzzle: clues, a box around every
+    /// cell, heavier lines every five cells and rulers.
+    pub fn printed() -> Self {
+        RenderOptions {
+            gridlines: 5,
+            boxed: true,
+            rulers: true,
+            clues: true,
+            ..RenderOptions::default()
+        }
+    }
+
+    fn cell(&self, state: CellState) -> char {
+        match state {
+            CellState::Undecided => self.undecided,
+            CellState::Empty => self.empty,
+            CellState::Filled => self.filled,
+        }
+    }
+}
+
+/// A line of text cells: either a line of the grid, or a gridline
+/// between two of them.
+#[derive(Clone, Copy)]
+enum Slot {
+    Cell(usize),
+    Line(Line),
+}
+
+#[derive(Clone, Copy)]
+struct Line {
+    heavy: bool,
+    /// 0 on the first edge of the grid, 1 inside, 2 on the last edge.
+    edge: usize,
+}
+
+/// The slots across a grid of `len` cells.
+fn slots(len: usize, options: &RenderOptions) -> Vec<Slot> {
+    let interval = options.gridlines;
+    let every = |i: usize| interval > 0 && i.is_multiple_of(interval);
+    let line = |heavy, edge| Slot::Line(Line { heavy, edge });
+    let mut ret = vec![];
+    for i in 0..len {
+        if options.boxed {
+            ret.push(line(i == 0 || every(i), if i == 0 { 0 } else { 1 }));
+        } else if i > 0 && every(i) {
+            ret.push(line(false, 1));
+        }
+        ret.push(Slot::Cell(i));
+    }
+    if options.boxed {
+        ret.push(line(true, 2));
+    }
+    ret
+}
+
+/// Render `nono`, with its current grid, as text.
+pub fn render(nono: &Nonogram, options: &RenderOptions) -> String {
+    let (w, h) = (nono.width(), nono.height());
+    let charset = options.charset;
+    let columns = slots(w, options);
+
+    // Cells are as wide as the widest column clue number, so that
+    // column clues line up with their column.
+    let col_clues: Vec<Vec<String>> = nono.cols.iter().map(numbers).collect();
+    let struck = |clue: &Constraint, line: &[CellState]| match options.strike_clues {
+        true => clue.satisfied_runs(line),
+        false => vec![false; clue.len().max(1)],
+    };
+    let col_struck: Vec<Vec<bool>> = (nono.cols.iter().enumerate())
+        .map(|(x, clue)| struck(clue, &nono.column(x).unwrap()))
+        .collect();
+    let cell_width = if options.clues {
+        col_clues
+            .iter()
+            .flatten()
+            .map(String::len)
+            .max()
+            .unwrap_or(1)
+    } else {
+        1
+    };
+
+    // The left margin holds row coordinates, then row clues.
+    let coord_width = digits(h.saturating_sub(1));
+    let row_clues: Vec<String> = (nono.rows.iter())
+        .map(|c| writer::clue(c, &ClueStyle::printed()))
+        .collect();
+    let clue_width = row_clues.iter().map(String::len).max().unwrap_or(0);
+    let row_clues: Vec<String> = (nono.rows.iter().enumerate())
+        .map(|(y, clue)| {
+            let struck = struck(clue, nono.row(y).unwrap());
+            let numbers: Vec<String> = (numbers(clue).iter().zip(struck))
+                .map(|(n, struck)| strike(n, struck))
+                .collect();
+            let plain = row_clues[y].len();
+            format!("{}{}", " ".repeat(clue_width - plain), numbers.join(" "))
+        })
+        .collect();
+    let margin = |y: Option<usize>| {
+        let mut ret = String::new();
+        if options.coordinates {
+            match y {
+                Some(y) => ret.push_str(&format!("{:>1$} ", y, coord_width)),
+                None => ret.push_str(&" ".repeat(coord_width + 1)),
+            }
+        }
+        if options.clues {
+            match y {
+                Some(y) => ret.push_str(&row_clues[y]),
+                None => ret.push_str(&" ".repeat(clue_width)),
+            }
+            ret.push(' ');
+        }
+        ret
+    };
+
+    // A line of text, with `cell(x)` for each column of the grid and
+    // `line(l)` for each vertical gridline.
+    let text = |y: Option<usize>, cell: &dyn Fn(usize) -> String, line: &dyn Fn(Line) -> char| {
+        let mut ret = margin(y);
+        for slot in &columns {
+            match *slot {
+                Slot::Cell(x) => ret.push_str(&cell(x)),
+                Slot::Line(l) => ret.push(line(l)),
+            }
+        }
+        ret
+    };
+
+    let mut ret = String::new();
+    let mut header = |cell: &dyn Fn(usize) -> String| {
+        ret.push_str(text(None, cell, &|_| ' ').trim_end());
+        ret.push('\n');
+    };
+    if options.coordinates {
+        for d in (0..digits(w.saturating_sub(1))).rev() {
+            header(&|x| match x.to_string().chars().rev().nth(d) {
+                Some(c) => format!("{:>1$}", c, cell_width),
+                None => " ".repeat(cell_width),
+            });
+        }
+    }
+    if options.clues {
+        let depth = col_clues.iter().map(Vec::len).max().unwrap_or(0);
+        for i in 0..depth {
+            header(&|x| {
+                let clue = &col_clues[x];
+                match (i + clue.len()).checked_sub(depth) {
+                    Some(i) => {
+                        let pad = " ".repeat(cell_width - clue[i].len());
+                        format!("{}{}", pad, strike(&clue[i], col_struck[x][i]))
+                    }
+                    None => " ".repeat(cell_width),
+                }
+            });
+        }
+    }
+
+    let period = if options.gridlines > 0 {
+        options.gridlines
+    } else {
+        5
+    };
+    let ruled = |i: usize| options.rulers && (i + 1).is_multiple_of(period);
+    for slot in slots(h, options) {
+        match slot {
+            Slot::Cell(y) => {
+                let cell = |x| {
+                    options
+                        .cell(nono[Point::new(x, y)])
+                        .to_string()
+                        .repeat(cell_width)
+                };
+                let line = |l: Line| charset.vertical(l.heavy);
+                ret.push_str(&text(Some(y), &cell, &line));
+                if ruled(y) {
+                    ret.push_str(&format!(" {}", y + 1));
+                }
+                ret.push('\n');
+            }
+            Slot::Line(row) => {
+                let cell = |_| charset.horizontal(row.heavy).to_string().repeat(cell_width);
+                let line = |column: Line| charset.junction(column, row);
+                ret.push_str(&text(None, &cell, &line));
+                ret.push('\n');
+            }
+        }
+    }
+
+    if options.rulers {
+        let mut ruler: Vec<char> = margin(None).chars().collect();
+        for slot in &columns {
+            match *slot {
+                Slot::Line(_) => ruler.push(' '),
+                Slot::Cell(x) => {
+                    ruler.extend(std::iter::repeat_n(' ', cell_width));
+                    let number = (x + 1).to_string();
+                    // Right-aligned on the cell, unless it would run
+                    // into the previous number.
+                    let free = ruler.iter().rev().take_while(|c| **c == ' ').count();
+                    if ruled(x) && free > number.len() {
+                        let start = ruler.len() - number.len();
+                        for (i, c) in number.chars().enumerate() {
+                            ruler[start + i] = c;
+                        }
+                    }
+                }
+            }
+        }
+        ret.push_str(ruler.iter().collect::<String>().trim_end());
+        ret.push('\n');
+    }
+    ret
+}
+
+/// The numbers of `clue` as text, with an empty clue written `0`.
+fn numbers(clue: &Constraint) -> Vec<String> {
+    writer::numbers(clue, &ClueStyle::printed())
+}
+
+/// `number`, struck through if `struck`.
+fn strike(number: &str, struck: bool) -> String {
+    match struck {
+        true => format!("\x1b[2;9m{}\x1b[22;29m", number),
+        false => String::from(number),
+    }
+}
+
+fn digits(n: usize) -> usize {
+    n.to_string().len()
+}
@@ -0,0 +1,89 @@
+//! TikZ picture of a puzzle, for LaTeX documents.
+//!
+//! The output is a bare `tikzpicture` environment, to `\input` or
+//! paste into a document that loads the `tikz` package.
+
+use crate::{CellState, Nonogram, Point};
+
+use std::fmt::Write;
+
+/// How the picture looks.
+#[derive(Clone, Debug)]
+pub struct TikzOptions {
+    /// Side of a cell, in centimeters.
+    pub cell_size: f64,
+    /// Font command for the clues, eg `\small` or `\sffamily\tiny`.
+    pub clue_font: String,
+    /// Draw the filled cells of the grid.  Without this, the picture
+    /// is the blank puzzle.
+    pub show_solution: bool,
+    /// Draw a heavier gridline every that many cells; 0 for none.
+    pub thick_every: usize,
+}
+
+impl Default for TikzOptions {
+    fn default() -> Self {
+        TikzOptions {
+            cell_size: 0.5,
+            clue_font: String::from("\\small"),
+            show_solution: true,
+            thick_every: 5,
+        }
+    }
+}
+
+/// Render `nono` as a TikZ picture.
+pub fn render(nono: &Nonogram, options: &TikzOptions) -> String {
+    let (w, h) = (nono.width(), nono.height());
+    let mut ret = String::new();
+    writeln!(
+        ret,
+        "\\begin{{tikzpicture}}[x={0}cm, y=-{0}cm, clue/.style={{font={1}, inner sep=0pt}}]",
+        options.cell_size, options.clue_font
+    )
+    .unwrap();
+
+    if options.show_solution {
+        ret.push_str("  % Solution\n");
+        for y in 0..h {
+            for x in 0..w {
+                if nono[Point::new(x, y)] == CellState::Filled {
+                    writeln!(ret, "  \\fill[black] ({},{}) rectangle +(1,1);", x, y).unwrap();
+                }
+            }
+        }
+    }
+
+    ret.push_str("  % Grid\n");
+    writeln!(ret, "  \\draw[gray, very thin] (0,0) grid ({},{});", w, h).unwrap();
+    if options.thick_every > 0 {
+        for x in (options.thick_every..w).step_by(options.thick_every) {
+            writeln!(ret, "  \\draw[thick] ({},0) -- ({},{});", x, x, h).unwrap();
+        }
+        for y in (options.thick_every..h).step_by(options.thick_every) {
+            writeln!(ret, "  \\draw[thick] (0,{}) -- ({},{});", y, w, y).unwrap();
+        }
+    }
+    writeln!(ret, "  \\draw[thick] (0,0) rectangle ({},{});", w, h).unwrap();
+
+    ret.push_str("  % Column clues\n");
+    for (x, clue) in nono.cols.iter().enumerate() {
+        let numbers = clue.numbers();
+        for (i, n) in numbers.iter().enumerate() {
+            let y = i as f64 - numbers.len() as f64 + 0.5;
+            writeln!(ret, "  \\node[clue] at ({}.5,{}) {{{}}};", x, y, n).unwrap();
+        }
+    }
+
+    ret.push_str("  % Row clues\n");
+    for (y, clue) in nono.rows.iter().enumerate() {
+        let numbers = clue.numbers();
+        for (i, n) in numbers.iter().enumerate() {
+            let x = i as f64 - numbers.len() as f64 + 0.5;
+            writeln!(ret, "  \\node[clue] at ({},{}.5) {{{}}};", x, y, n).unwrap();
+        }
+    }
+
+    ret.push_str("\\end{tikzpicture}\n");
+    ret
+}
@@ -0,0 +1,210 @@
+//! Run-length encoding of rows of decided cells, eg to store goals
+//! compactly or to compare solutions with other tools.
+//!
+//! A row is a sequence of runs, each a count then `b` for black
+//! (filled) cells or `w` for white (empty) ones: `3b2w5b` is three
+//! filled cells, two empty ones and five filled ones.  A count of 1
+//! may be left out, so `b2wb` is `1b2w1b`.  An empty row is the empty
+//! string.
+
+use crate::CellState;
+
+use alloc::string::String;
+use alloc::{vec, vec::Vec};
+use core::error;
+use core::fmt;
+use core::fmt::Write;
+
+/// Why a run-length string can't be read.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RleError {
+    /// A character other than a digit, `b` or `w`.
+    BadChar(char),
+    /// The string ends with a count and no cell.
+    MissingCell,
+    /// A run of no cells.
+    ZeroCount,
+    /// Row `row`, from 0, has `got` cells instead of `expected`.
+    WrongLength {
+        row: usize,
+        expected: usize,
+        got: usize,
+    },
+    /// There are `got` rows instead of `expected`.
+    WrongRowCount { expected: usize, got: usize },
+}
+
+impl fmt::Display for RleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RleError::BadChar(c) => write!(f, "Unexpected {:?} in a run-length row.", c),
+            RleError::MissingCell => f.write_str("A run-length row ends with a count."),
+            RleError::ZeroCount => f.write_str("A run-length row has a run of 0 cells."),
+            RleError::WrongLength { row, expected, got } => write!(
+                f,
+                "Row {} has {} cells instead of {}.",
+                row + 1,
+                got,
+                expected
+            ),
+            RleError::WrongRowCount { expected, got } => {
+                write!(f, "Expected {} rows, got {}.", expected, got)
+            }
+        }
+    }
+}
+
+impl error::Error for RleError {}
+
+/// Encode `row`, or None if a cell is undecided.
+pub fn encode(row: &[CellState]) -> Option<String> {
+    let mut ret = String::new();
+    for run in row.chunk_by(|a, b| a == b) {
+        let cell = match run[0] {
+            CellState::Filled => 'b',
+            CellState::Empty => 'w',
+            CellState::Undecided => return None,
+        };
+        write!(ret, "{}{}", run.len(), cell).unwrap();
+    }
+    Some(ret)
+}
+
+/// Decode the row `s`, of at most `max_len` cells.  A longer row is
+/// [RleError::WrongLength], of row 0, before its cells are expanded,
+/// so that huge counts fail rather than exhaust memory.
+pub fn decode(s: &str, max_len: usize) -> Result<Vec<CellState>, RleError> {
+    let mut ret = vec![];
+    let mut count: Option<usize> = None;
+    for c in s.chars() {
+        let cell = match c {
+            'b' => CellState::Filled,
+            'w' => CellState::Empty,
+            c => match c.to_digit(10) {
+                Some(d) => {
+                    let n = count.unwrap_or(0);
+                    count = Some(n.saturating_mul(10).saturating_add(d as usize));
+                    continue;
+                }
+                None => return Err(RleError::BadChar(c)),
+            },
+        };
+        let n = match count.take() {
+            Some(0) => return Err(RleError::ZeroCount),
+            n => n.unwrap_or(1),
+        };
+        if n > max_len - ret.len() {
+            return Err(RleError::WrongLength {
+                row: 0,
+                expected: max_len,
+                got: ret.len().saturating_add(n),
+            });
+        }
+        ret.extend(core::iter::repeat_n(cell, n));
+    }
+    match count {
+        Some(_) => Err(RleError::MissingCell),
+        None => Ok(ret),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+    use crate::Nonogram;
+
+    fn row(s: &str) -> Vec<CellState> {
+        s.chars()
+            .map(|c| CellState::from_char(c).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn decoding_reverses_encoding() {
+        assert_eq!(encode(&row("1110011111")).unwrap(), "3b2w5b");
+        assert_eq!(encode(&[]).unwrap(), "");
+        assert_eq!(encode(&row("10?1")), None);
+        assert_eq!(decode("b2wb", 4), Ok(row("1001")));
+        assert_eq!(decode("", 0), Ok(vec![]));
+
+        let mut rng = Rng::new(865);
+        for _ in 0..500 {
+            let len = rng.below(40);
+            let cells: Vec<_> = (0..len)
+                .map(|_| CellState::from(rng.below(3) == 0))
+                .collect();
+            let encoded = encode(&cells).unwrap();
+            assert_eq!(decode(&encoded, len), Ok(cells.clone()), "{}", encoded);
+            assert_eq!(decode(&encoded, len + 5), Ok(cells), "{}", encoded);
+        }
+    }
+
+    #[test]
+    fn malformed_rows_are_errors() {
+        assert_eq!(decode("3b2x", 10), Err(RleError::BadChar('x')));
+        assert_eq!(decode("3b?", 10), Err(RleError::BadChar('?')));
+        assert_eq!(decode("-1b", 10), Err(RleError::BadChar('-')));
+        assert_eq!(decode("3b2", 10), Err(RleError::MissingCell));
+        assert_eq!(decode("3b0w", 10), Err(RleError::ZeroCount));
+        let too_long = |got| {
+            Err(RleError::WrongLength {
+                row: 0,
+                expected: 4,
+                got,
+            })
+        };
+        assert_eq!(decode("3b2w", 4), too_long(5));
+        assert_eq!(decode("bwbwb", 4), too_long(5));
+        // Huge counts saturate instead of overflowing or allocating.
+        assert_eq!(decode("99999999999999999999999b", 4), too_long(usize::MAX));
+    }
+
+    #[test]
+    fn solutions_read_back_or_stay_unchanged() {
+        let mut nono = Nonogram::builder()
+            .width(3)
+            .height(2)
+            .row([2])
+            .row([1, 1])
+            .col([2])
+            .col([1])
+            .col([1])
+            .build()
+            .unwrap();
+        nono.set_solution_rle(&["2bw", "bwb"]).unwrap();
+        assert_eq!(nono.solution_rle().unwrap(), ["2b1w", "1b1w1b"]);
+
+        let before = nono.cells.clone();
+        let errors = [
+            (
+                vec!["3w"],
+                RleError::WrongRowCount {
+                    expected: 2,
+                    got: 1,
+                },
+            ),
+            (
+                vec!["3w", "2w"],
+                RleError::WrongLength {
+                    row: 1,
+                    expected: 3,
+                    got: 2,
+                },
+            ),
+            (
+                vec!["3w", "4w"],
+                RleError::WrongLength {
+                    row: 1,
+                    expected: 3,
+                    got: 4,
+                },
+            ),
+            (vec!["3w", "w2"], RleError::MissingCell),
+        ];
+        for (rows, error) in errors {
+            assert_eq!(nono.set_solution_rle(&rows), Err(error));
+            assert_eq!(nono.cells, before);
+        }
+    }
+}
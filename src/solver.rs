@@ -1,5 +1,29 @@
-use crate::{CellState,Constraint,Nonogram};
-use std::iter::once;
+use crate::history::{Cells, History, HistoryPoint};
+use crate::line::{
+    clue_overlap, count_placements, edge_cells, line_consensus, LineCache, LineMask, LineMaskSet,
+    PackedLine, PlacementCounts, RemovedMasks,
+};
+use crate::rng::Rng;
+use crate::storage::GridStorage;
+use crate::trace::{Deduction, SolveTrace, Source};
+use crate::{
+    CellState, CheckpointError, Constraint, Grid, LineRef, Nonogram, Point, Puzzle,
+    SolverCheckpoint,
+};
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::{vec, vec::Vec};
+use core::cmp::Reverse;
+use core::error;
+use core::fmt;
+#[cfg(feature = "async")]
+use core::future::Future;
+use core::mem;
+#[cfg(feature = "async")]
+use core::pin::Pin;
+use core::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "async")]
+use core::task::{Context, Poll};
 
 // * A solver
 
@@ -30,184 +54,2066 @@ use std::iter::once;
 ///  2. Filter out from that list the sequences that don't match with
 ///     what we know of the grid, eg those with a filled square where
 ///     we know there must be an empty square, and so on.
+///
+/// Lists of candidates can be huge on long lines with many runs, so
+/// they are only built once they fit in
+/// [SolverConfig::candidate_memory], taking the grid into account.
+/// Until then, the line is solved by dynamic programming over the
+/// runs, see [crate::line::line_consensus], which gives the same
+/// consensus without listing anything.
+///
+/// When this stops making progress, the solver can probe: assume a
+/// value for an undecided cell, and propagate.  If that leads to a
+/// contradiction, the cell must have the other value.  See
+/// [SolverConfig::probe_depth].
+///
+/// If even probing is not enough, the solver searches: it guesses the
+/// value of a cell chosen by [SolverConfig::guess_strategy], and
+/// backtracks if the guess leads to a contradiction.
+///
+/// The grid is a `Vec<CellState>` by default, that of a [Nonogram] or
+/// a [Grid], or else any [GridStorage], eg for puzzles too big for
+/// memory: see [Solver::for_storage].
+pub struct Solver<'a, S = Vec<CellState>> {
+    rows: Vec<LineState>,
+    cols: Vec<LineState>,
+    board: Board<'a, S>,
+    config: SolverConfig,
+    cancel: Option<Arc<AtomicBool>>,
+    /// Propagation passes so far.
+    pass: usize,
+    /// See [Solver::stats].
+    stats: SolveStats,
+    trace: Option<SolveTrace>,
+    /// Changes to the grid, to backtrack without copying it.
+    history: History<CellState>,
+    /// Changes to the candidates since the oldest [Snapshot] that may
+    /// still be rewound to, to backtrack without copying them.
+    journal: Vec<CandidateChange>,
+    /// How many [Snapshot]s may still be rewound to: until there's
+    /// one, nothing needs journaling.
+    open_snapshots: usize,
+    cache: LineCache,
+    /// What is left of [SolverConfig::candidate_memory].
+    budget: usize,
+    /// [SolverConfig::candidate_memory], within
+    /// [SolverConfig::max_memory_bytes].
+    candidate_memory: usize,
+    /// How many guesses the search is currently under.
+    guesses: usize,
+    /// How each cell was decided, for [Solver::guess_map].
+    decided_by: Vec<Decided>,
+    /// The line being filtered, to reuse its buffer.
+    line: LineMask,
+    /// The same, as bitsets, to filter candidates against it.
+    packed: PackedLine,
+    /// Where to report [SolverEvent]s, see [Solver::set_logger].
+    logger: Option<Logger>,
+    /// Breaks ties between guesses, from [SolverConfig::seed].
+    rng: Option<Rng>,
+    /// See [SolverConfig::snapshot_every].
+    snapshots: Vec<PassSnapshot>,
+    /// The pass before which to stop as if cancelled, for
+    /// [Solver::solve_async].
+    pause_at: Option<usize>,
+    /// Raised when another branch of a parallel search above this
+    /// one solved the puzzle, see [SolverConfig::threads].
+    stops: Vec<Arc<AtomicBool>>,
+}
+
+/// Tuning knobs for the [Solver].
+#[derive(Clone, Debug)]
+pub struct SolverConfig {
+    /// How deep probes may nest.  At depth 0, the solver uses line
+    /// logic only.  At depth 1, it probes each undecided cell with
+    /// line logic when stalled.  At depth n, probes themselves probe
+    /// at depth n-1, which gets expensive quickly.
+    pub probe_depth: usize,
+    /// Whether to guess and backtrack when deduction stalls.  Without
+    /// search, [Solver::solve] may return [SolveOutcome::Stalled].
+    pub search: bool,
+    /// How search picks the cell to guess.
+    pub guess_strategy: GuessStrategy,
+    /// Whether to record every deduction into a [SolveTrace].
+    pub trace: bool,
+    /// How many line states to remember the consensus of, to save
+    /// solving them again; 0 to disable the [LineCache].
+    pub line_cache: usize,
+    /// How many bytes the candidates of all lines may take.  Lines
+    /// list their candidates once they fit in what's left, and until
+    /// then are solved with a slower method that needs no candidates.
+    /// Listing happens while solving rather than beforehand, so
+    /// candidates that clues already ruled out are never built.
+    pub candidate_memory: usize,
+    /// In which order propagation goes through lines.
+    pub schedule: Schedule,
+    /// Whether each propagation pass starts with the border technique
+    /// on every line, see [crate::line::edge_logic].  Solving lines
+    /// finds the same cells, but later and at more cost, and a trace
+    /// tells these apart, as [Source::Edge], eg to grade puzzles by
+    /// the techniques they take.
+    pub edge_logic: bool,
+    /// Roughly how many bytes the solver may take, or None for no
+    /// limit.  Within it, the solver lists fewer candidates, caches
+    /// fewer lines, and drops the candidates it listed rather than
+    /// keep them through deep searches, all of which only makes it
+    /// slower.
+    /// What it can't do without, see [Solver::estimate_memory], is
+    /// taken anyway.
+    pub max_memory_bytes: Option<usize>,
+    /// A seed to break ties between the cells [GuessStrategy] rates
+    /// the same, or None to guess the first of them, row by row.
+    /// Either way the search is deterministic: the same puzzle,
+    /// configuration and seed always give the same guesses, events
+    /// and statistics.  Other seeds only explore other search orders,
+    /// eg to compare versions of the solver over several of them.
+    pub seed: Option<u64>,
+    /// Copy the grid every this many propagation passes, see
+    /// [Solver::pass_snapshots], eg to see how a solve goes, or
+    /// compare it between versions of the solver; 0 to never.  Each
+    /// copy takes a byte per cell.
+    pub snapshot_every: usize,
+    /// How many threads search may use.  With more than one, the two
+    /// values of the first guesses are tried at once, on threads of
+    /// their own, until as many branches as threads run, and the
+    /// first branch to solve the puzzle stops the others.  Then
+    /// which solution is found, of puzzles with several, and the
+    /// guesses made may change from a solve to the next, and
+    /// branches report no [SolverEvent]s.  Without feature `std`,
+    /// search uses a single thread.
+    pub threads: usize,
+}
+
+impl Default for SolverConfig {
+    fn default() -> Self {
+        SolverConfig {
+            probe_depth: 1,
+            search: true,
+            guess_strategy: GuessStrategy::default(),
+            trace: false,
+            line_cache: 1 << 14,
+            candidate_memory: 1 << 20,
+            schedule: Schedule::default(),
+            edge_logic: true,
+            max_memory_bytes: None,
+            seed: None,
+            snapshot_every: 0,
+            threads: 1,
+        }
+    }
+}
+
+/// How the search picks the next cell to guess.  This has much more
+/// effect on search time than anything else.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GuessStrategy {
+    /// Guess in the line with the fewest candidates left, where a
+    /// guess eliminates the largest share of them.
+    #[default]
+    MostConstrainedLine,
+    /// Guess the cell whose row and column have the fewest
+    /// candidates left between them.
+    FewestCandidates,
+    /// Guess the undecided cell closest to the border of the grid,
+    /// where clues tend to be the most decisive.
+    BorderFirst,
+}
+
+/// In which order propagation goes through lines, and applies what it
+/// deduces from them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Schedule {
+    /// Solve every row and every column, then mark what they deduced,
+    /// and start over until nothing changes.
+    #[default]
+    RowsThenColumns,
+    /// Solve first the lines with the most cells decided since they
+    /// were last solved, the fullest first at the start, and mark
+    /// what each deduces at once, so that lines crossing it use it in
+    /// the same pass.  Lines where nothing changed are skipped, which
+    /// saves passes and line solves on large puzzles.
+    MostChangedFirst,
+}
+
+/// How a call to [Solver::solve] ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SolveOutcome {
+    /// Every cell is decided.
+    Solved,
+    /// The solver could not deduce anything more, but some cells are
+    /// still undecided.
+    Stalled,
+    /// The clues contradict each other: the puzzle has no solution.
+    Contradiction,
+    /// The cancel flag was raised.  The grid holds what was deduced
+    /// before any guess.
+    Cancelled,
+}
+
+/// How a cell was decided, see [Solver::guess_map].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Decided {
+    /// By deduction from the clues alone, or before solving.
+    Deduced,
+    /// The search guessed it.
+    Guessed,
+    /// By deduction, but while some guess was in effect.
+    AfterGuess,
+}
+
+/// Why [Solver::solve_puzzle] returned no solution.
+#[derive(Debug)]
+pub enum SolveError {
+    /// Deduction stalled with search disabled; this is how far it
+    /// got.
+    Stalled(Grid),
+    /// The puzzle has no solution.
+    Contradiction,
+    /// The cancel flag was set.
+    Cancelled,
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolveError::Stalled(_) => f.write_str("Could not solve completely."),
+            SolveError::Contradiction => f.write_str("This puzzle has no solution."),
+            SolveError::Cancelled => f.write_str("Solving was interrupted."),
+        }
+    }
+}
+
+impl error::Error for SolveError {}
+
+/// How much work a solve took, see [Solver::stats].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SolveStats {
+    /// Propagation passes, including those of probes, of guesses that
+    /// were undone, and of every branch of a parallel search.
+    pub passes: usize,
+    /// Guesses, undone or not, on every branch.
+    pub guesses: usize,
+}
+
+impl core::ops::AddAssign for SolveStats {
+    fn add_assign(&mut self, other: SolveStats) {
+        self.passes += other.passes;
+        self.guesses += other.guesses;
+    }
+}
+
+/// How far a solve got, see [Solver::progress].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Progress {
+    /// Decided cells.
+    pub decided: usize,
+    /// Cells in the grid.
+    pub total: usize,
+    /// Rows and columns with undecided cells left.
+    pub active_lines: usize,
+    /// How many placements of its clue fit each row, then each
+    /// column.  These are counted as floats since they overflow
+    /// integers on large puzzles, and 1 for decided lines.
+    pub candidate_counts: Vec<f64>,
+}
+
+impl Progress {
+    /// The fraction of cells decided, from 0 to 1.
+    pub fn fraction(&self) -> f64 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.decided as f64 / self.total as f64
+        }
+    }
+}
+
+/// The grid at the end of a propagation pass, see
+/// [SolverConfig::snapshot_every].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PassSnapshot {
+    /// The number of the pass, counting from 1.
+    pub pass: usize,
+    pub grid: Grid,
+}
+
+/// What the solver is doing, for diagnostics, see
+/// [Solver::set_logger].  This displays as a line of text, eg to pass
+/// on to a logging crate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SolverEvent {
+    /// A propagation pass starts.
+    Pass(usize),
+    /// The line now lists its candidates, of which `count` fit it.
+    Listed { line: LineRef, count: usize },
+    /// The clue of the line no longer fits it.
+    Conflict(LineRef),
+    /// Probing found that `cell` must be `value`.
+    Probed { cell: Point, value: CellState },
+    /// The search guesses `value` for `cell`, under `depth` other
+    /// guesses.
+    Guess {
+        cell: Point,
+        value: CellState,
+        depth: usize,
+    },
+    /// The guess at `cell` led to a contradiction.
+    Backtrack { cell: Point, depth: usize },
+    /// Candidates were dropped, to stay within
+    /// [SolverConfig::max_memory_bytes].
+    Shed,
+}
+
+impl fmt::Display for SolverEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolverEvent::Pass(pass) => write!(f, "pass {}", pass),
+            SolverEvent::Listed { line, count } => write!(f, "{}: {} candidates", line, count),
+            SolverEvent::Conflict(line) => write!(f, "{}: conflict", line),
+            SolverEvent::Probed { cell, value } => write!(f, "probed {}: {:?}", cell, value),
+            SolverEvent::Guess { cell, value, depth } => {
+                write!(f, "guess {:?} at {}, depth {}", value, cell, depth)
+            }
+            SolverEvent::Backtrack { cell, depth } => {
+                write!(f, "backtrack from {}, depth {}", cell, depth)
+            }
+            SolverEvent::Shed => f.write_str("dropped candidates to save memory"),
+        }
+    }
+}
+
+type Logger = Box<dyn FnMut(&SolverEvent) + Send>;
+
+/// Why propagation stopped early.
+enum Stop {
+    /// Some line can't be satisfied.
+    Contradiction,
+    /// The cancel flag was raised.
+    Cancelled,
+}
+
+type Propagation<T> = Result<T, Stop>;
+
+/// The solver state, to return to after a probe or a failed guess:
+/// the grid and the candidates as points in their journals, and a
+/// copy of the rest.
+struct Snapshot {
+    cells: HistoryPoint,
+    /// The length of [Solver::journal].
+    candidates: usize,
+    /// The lines, without their candidates.
+    rows: Vec<LineState>,
+    cols: Vec<LineState>,
+    trace_len: usize,
+    budget: usize,
+    guesses: usize,
+}
+
+/// A change to the candidates of a line, which [Solver::rewind]
+/// undoes.
+enum CandidateChange {
+    /// These candidates were filtered out.
+    Removed(LineRef, RemovedMasks),
+    /// The candidates were listed.
+    Listed(LineRef),
+    /// Every line dropped its candidates, see
+    /// [Solver::shed_candidates], and the journal what it kept of
+    /// them.  This isn't undone: lines solve the same without
+    /// candidates.
+    Shed,
+}
+
+/// What the solver knows about a single row or column.
+#[derive(Clone)]
+struct LineState {
+    /// The candidates fitting the line, once they fit in
+    /// [SolverConfig::candidate_memory].  Until then, the line is
+    /// solved without listing its candidates.
+    candidates: Option<LineMaskSet>,
+    /// While `candidates` isn't listed, how many would fit the line,
+    /// as of the last [Solver::refresh_candidates].
+    counts: Option<PlacementCounts>,
+    /// The cells all candidates fitting the line agree on.
+    consensus: LineMask,
+    /// The line is fully decided and was verified against its
+    /// candidates, so passes skip it.
+    done: bool,
+    /// The consensus came from the [LineCache], and the candidates
+    /// weren't filtered against the line yet.
+    stale: bool,
+    /// How many cells were decided since [Schedule::MostChangedFirst]
+    /// last solved the line, all of them to begin with.
+    changed: usize,
+}
+
+impl LineState {
+    fn new(len: usize) -> LineState {
+        LineState {
+            candidates: None,
+            counts: None,
+            consensus: LineMask::undecided(len),
+            done: false,
+            stale: false,
+            changed: len,
+        }
+    }
+
+    /// What the solver knows of the line but its candidates, which
+    /// [Solver::rewind] puts back from the journal instead.
+    fn without_candidates(&self) -> LineState {
+        LineState {
+            candidates: None,
+            counts: self.counts.clone(),
+            consensus: self.consensus.clone(),
+            ..*self
+        }
+    }
+
+    /// Filter the candidates against the current state of the line,
+    /// unless `cache` knows the consensus already, and mark the line
+    /// done if it's complete.  Candidates are listed the first time
+    /// they fit in `budget` bytes, which is then reduced.  Those
+    /// filtered out go to `removed`, if there's one, and `packed`
+    /// holds the line as bitsets meanwhile.
+    fn filter(
+        &mut self,
+        clue: &Constraint,
+        line: &LineMask,
+        packed: &mut PackedLine,
+        cache: &mut LineCache,
+        budget: &mut usize,
+        removed: Option<&mut RemovedMasks>,
+    ) -> Propagation<()> {
+        let consensus = match cache.get(clue, line) {
+            Some(consensus) => {
+                self.stale = true;
+                consensus
+            }
+            None => {
+                self.stale = false;
+                let consensus = match &mut self.candidates {
+                    Some(candidates) => {
+                        packed.load(line);
+                        candidates.retain_packed(packed, removed);
+                        candidates.consensus()
+                    }
+                    // Listing every line for an unknown clue is no use.
+                    None if clue.is_unknown() => line_consensus(clue, line),
+                    None => {
+                        let size = candidate_bytes(count_placements(clue, line).total, line.len());
+                        if size <= *budget as f64 {
+                            *budget -= size as usize;
+                            let candidates = LineMaskSet::fitting(clue, line);
+                            let consensus = candidates.consensus();
+                            self.candidates = Some(candidates);
+                            consensus
+                        } else {
+                            line_consensus(clue, line)
+                        }
+                    }
+                };
+                cache.insert(clue, line, consensus.clone());
+                consensus
+            }
+        };
+        self.consensus = consensus.ok_or(Stop::Contradiction)?;
+        if !line.cells().contains(&CellState::Undecided) {
+            self.done = true;
+        }
+        Ok(())
+    }
+
+    /// Bring the candidates, or their counts, up to date with `line`.
+    /// Candidates filtered out go to `removed`, if there's one, as in
+    /// [LineState::filter].
+    fn refresh(
+        &mut self,
+        clue: &Constraint,
+        line: &LineMask,
+        packed: &mut PackedLine,
+        removed: Option<&mut RemovedMasks>,
+    ) {
+        match &mut self.candidates {
+            Some(candidates) if self.stale => {
+                packed.load(line);
+                candidates.retain_packed(packed, removed);
+            }
+            Some(_) => (),
+            None => self.counts = Some(count_placements(clue, line)),
+        }
+        self.stale = false;
+    }
 
-// @FIXME Convert into newtype.
-type CandidateMask = Vec<CellState>;
-type CandidateMaskSet = Vec<CandidateMask>;
+    /// How many candidates fit the line, as of the last refresh.
+    fn size(&self) -> usize {
+        match (&self.candidates, &self.counts) {
+            (Some(candidates), _) => candidates.len(),
+            (None, Some(counts)) => counts.total as usize,
+            (None, None) => usize::MAX,
+        }
+    }
 
-pub struct Solver<'a> {
-    rows: Vec<CandidateMaskSet>,
-    cols: Vec<CandidateMaskSet>,
-    nono: &'a mut Nonogram,
+    /// Whether most candidates fill cell `i`, as of the last refresh.
+    fn mostly_filled(&self, i: usize) -> bool {
+        match (&self.candidates, &self.counts) {
+            (Some(candidates), _) => 2 * candidates.fill_count(i) >= candidates.len(),
+            (None, Some(counts)) => 2.0 * counts.filled[i] >= counts.total,
+            (None, None) => true,
+        }
+    }
 }
+
+// * The board
+
+/// What the solver works on: the clues, and the grid it fills in.
+/// This borrows either a [Nonogram], or a [Puzzle] and a [Grid], or
+/// a [Puzzle] and some other [GridStorage].
+struct Board<'a, S> {
+    width: usize,
+    rows: &'a [Constraint],
+    cols: &'a [Constraint],
+    cells: &'a mut S,
+}
+
+impl<S: GridStorage> Board<'_, S> {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    fn get(&self, p: Point) -> CellState {
+        self.cells.get(p.index(self.width))
+    }
+
+    /// The cells of row `y`, from the left.
+    fn row(&self, y: usize) -> impl Iterator<Item = CellState> + '_ {
+        (0..self.width).map(move |x| self.get(Point::new(x, y)))
+    }
+
+    /// The cells of column `x`, from the top.
+    fn column(&self, x: usize) -> impl Iterator<Item = CellState> + '_ {
+        (0..self.height()).map(move |y| self.get(Point::new(x, y)))
+    }
+
+    /// The indices of the undecided cells.
+    fn undecided(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.cells.len()).filter(|i| self.cells.get(*i) == CellState::Undecided)
+    }
+
+    fn clear_solution(&mut self) {
+        self.cells.fill(CellState::Undecided)
+    }
+}
+
+/// The grid, for the [History] of the solver.
+impl<S: GridStorage> Cells for Board<'_, S> {
+    type Cell = CellState;
+
+    fn cell(&self, index: usize) -> CellState {
+        self.cells.get(index)
+    }
+
+    fn set_cell(&mut self, index: usize, value: CellState) {
+        self.cells.set(index, value)
+    }
+}
+
+// * Solving
+
 impl<'a> Solver<'a> {
     pub fn new(from: &'a mut Nonogram) -> Solver<'a> {
+        Solver::with_config(from, SolverConfig::default())
+    }
+
+    pub fn with_config(from: &'a mut Nonogram, config: SolverConfig) -> Solver<'a> {
+        let board = Board {
+            width: from.width(),
+            rows: &from.rows,
+            cols: &from.cols,
+            cells: &mut from.cells,
+        };
+        Solver::on_board(board, config)
+    }
+
+    /// A solver filling `grid`, which must have the dimensions of
+    /// `puzzle`.  [Solver::solve] starts from a blank grid, and only
+    /// [Solver::resume] goes on from the cells `grid` already has.
+    pub fn for_grid(puzzle: &'a Puzzle, grid: &'a mut Grid, config: SolverConfig) -> Solver<'a> {
+        assert!(puzzle.width() == grid.width() && puzzle.height() == grid.height());
+        let board = Board {
+            width: puzzle.width(),
+            rows: &puzzle.rows,
+            cols: &puzzle.cols,
+            cells: grid.cells_mut(),
+        };
+        Solver::on_board(board, config)
+    }
+
+    /// Solve `puzzle` into a new grid, without touching the puzzle.
+    pub fn solve_puzzle(puzzle: &Puzzle, config: SolverConfig) -> Result<Grid, SolveError> {
+        let mut grid = puzzle.grid();
+        match Solver::for_grid(puzzle, &mut grid, config).solve() {
+            SolveOutcome::Solved => Ok(grid),
+            SolveOutcome::Stalled => Err(SolveError::Stalled(grid)),
+            SolveOutcome::Contradiction => Err(SolveError::Contradiction),
+            SolveOutcome::Cancelled => Err(SolveError::Cancelled),
+        }
+    }
+
+    /// Roughly how many bytes [Solver::solve] takes on `nono` with the
+    /// default configuration, before search: the grid and what the
+    /// solver knows of it, the tables of the line solver, as many
+    /// candidates as [SolverConfig::candidate_memory] allows, and the
+    /// line cache when full.  Search keeps the candidates it filters
+    /// out under guesses, to put them back when it backtracks.
+    ///
+    /// This counts candidates without listing them, so it's quick
+    /// even on puzzles too large to solve.  Without
+    /// [SolverConfig::candidate_memory], it would be
+    /// [MemoryEstimate::candidates] instead.
+    pub fn estimate_memory(nono: &Nonogram) -> usize {
+        let memory = MemoryEstimate::of(nono.width(), &nono.rows, &nono.cols);
+        let config = SolverConfig::default();
+        memory.fixed
+            + memory.candidates.min(config.candidate_memory)
+            + memory.cache_entry * config.line_cache
+    }
+}
+
+impl<'a, S: GridStorage> Solver<'a, S> {
+    /// A solver filling `cells`, the grid of `puzzle` row by row, which
+    /// [Solver::solve] clears first and [Solver::resume] goes on from,
+    /// as with [Solver::for_grid].  This lets huge puzzles keep their
+    /// grid packed, or out of core: see [crate::storage].
+    pub fn for_storage(
+        puzzle: &'a Puzzle,
+        cells: &'a mut S,
+        config: SolverConfig,
+    ) -> Solver<'a, S> {
+        assert!(cells.len() == puzzle.width() * puzzle.height());
+        let board = Board {
+            width: puzzle.width(),
+            rows: &puzzle.rows,
+            cols: &puzzle.cols,
+            cells,
+        };
+        Solver::on_board(board, config)
+    }
+
+    fn on_board(board: Board<'a, S>, config: SolverConfig) -> Solver<'a, S> {
+        let (rows, cols) = initial_lines(&board);
+        let size = board.cells.len();
+        let memory = MemoryEstimate::of(board.width(), board.rows, board.cols);
+        let (line_cache, candidate_memory) = memory.limits(&config);
         Solver {
-            rows: from
+            rows,
+            cols,
+            board,
+            cancel: None,
+            pass: 0,
+            stats: SolveStats::default(),
+            trace: config.trace.then(SolveTrace::new),
+            history: History::new(),
+            journal: vec![],
+            open_snapshots: 0,
+            cache: LineCache::new(line_cache),
+            budget: candidate_memory,
+            candidate_memory,
+            guesses: 0,
+            decided_by: vec![Decided::Deduced; size],
+            line: LineMask::default(),
+            packed: PackedLine::default(),
+            logger: None,
+            rng: config.seed.map(Rng::new),
+            snapshots: vec![],
+            pause_at: None,
+            stops: vec![],
+            config,
+        }
+    }
+
+    /// Report what the solver does to `logger`, as [SolverEvent]s.
+    /// Nothing is reported by default: the solver never prints.
+    pub fn set_logger(&mut self, logger: impl FnMut(&SolverEvent) + Send + 'static) {
+        self.logger = Some(Box::new(logger));
+    }
+
+    fn log(&mut self, event: SolverEvent) {
+        if let Some(logger) = &mut self.logger {
+            logger(&event);
+        }
+    }
+
+    /// Make [Solver::solve] check `flag` regularly, and return
+    /// [SolveOutcome::Cancelled] as soon as it is set.  This is meant
+    /// to interrupt a long solve from another thread.
+    pub fn set_cancel_flag(&mut self, flag: Arc<AtomicBool>) {
+        self.cancel = Some(flag);
+    }
+
+    /// Solve the puzzle from scratch, clearing the grid first: see
+    /// [Solver::resume] to go on from the cells it has.
+    pub fn solve(&mut self) -> SolveOutcome {
+        self.board.clear_solution();
+        self.pass = 0;
+        self.stats = SolveStats::default();
+        self.history.clear();
+        self.rng = self.config.seed.map(Rng::new);
+        self.snapshots.clear();
+        (self.rows, self.cols) = initial_lines(&self.board);
+        self.budget = self.candidate_memory;
+        self.guesses = 0;
+        self.decided_by.fill(Decided::Deduced);
+        if let Some(trace) = &mut self.trace {
+            trace.truncate(0);
+        }
+        if self.warm_start().is_err() {
+            return SolveOutcome::Contradiction;
+        }
+        self.resume()
+    }
+
+    /// Mark what the clues alone tell of the blank grid, see
+    /// [clue_overlap], before the first pass.  This lists no
+    /// candidates and solves no lines, so it's cheap even on lines
+    /// too long for either, and leaves them less to do.
+    fn warm_start(&mut self) -> Propagation<()> {
+        // What this deduces counts as part of the first pass.
+        let pass = self.pass;
+        self.pass += 1;
+        let (width, height) = (self.board.width(), self.board.height());
+        let mut lines = (0..height)
+            .map(LineRef::Row)
+            .chain((0..width).map(LineRef::Column));
+        let result = lines.try_for_each(|line| {
+            let overlap = match line {
+                LineRef::Row(y) => clue_overlap(&self.board.rows[y], width),
+                LineRef::Column(x) => clue_overlap(&self.board.cols[x], height),
+            };
+            for (i, value) in overlap.cells().iter().enumerate() {
+                self.mark(line.cell(i), *value, Source::Line(line))?;
+            }
+            Ok(())
+        });
+        self.pass = pass;
+        result
+    }
+
+    /// Continue solving from the current state of the grid, eg after
+    /// [Solver::restore].
+    pub fn resume(&mut self) -> SolveOutcome {
+        let result = if self.config.search {
+            self.search()
+        } else {
+            self.deduce(self.config.probe_depth)
+        };
+        match result {
+            Err(Stop::Contradiction) => SolveOutcome::Contradiction,
+            Err(Stop::Cancelled) => SolveOutcome::Cancelled,
+            Ok(()) if self.board.undecided().next().is_some() => SolveOutcome::Stalled,
+            Ok(()) => SolveOutcome::Solved,
+        }
+    }
+
+    /// Deduce what can be, then guess a cell and recurse, trying the
+    /// other value if the guess fails.
+    fn search(&mut self) -> Propagation<()> {
+        self.deduce(self.config.probe_depth)?;
+        self.refresh_candidates();
+        let Some((index, first)) = self.pick_guess() else {
+            return Ok(());
+        };
+        self.shed_candidates();
+        #[cfg(feature = "std")]
+        if self.guesses < self.config.threads.max(1).ilog2() as usize {
+            return self.search_in_parallel(index, first);
+        }
+        let cell = Point::from_index(index, self.board.width());
+        for value in [first, first.opposite()] {
+            let saved = self.snapshot();
+            let depth = self.guesses;
+            self.log(SolverEvent::Guess { cell, value, depth });
+            self.stats.guesses += 1;
+            self.decide(index, value, Source::Guess);
+            self.guesses += 1;
+            match self.search() {
+                Ok(()) => {
+                    self.keep(saved);
+                    return Ok(());
+                }
+                Err(Stop::Contradiction) => {
+                    self.rewind(saved);
+                    self.log(SolverEvent::Backtrack { cell, depth });
+                }
+                Err(Stop::Cancelled) => {
+                    self.rewind(saved);
+                    return Err(Stop::Cancelled);
+                }
+            }
+        }
+        Err(Stop::Contradiction)
+    }
+
+    /// Filter the candidates of lines whose consensus came from the
+    /// cache, and count those of unlisted lines, for
+    /// [Solver::pick_guess].
+    fn refresh_candidates(&mut self) {
+        let (width, height) = (self.board.width(), self.board.height());
+        let lines = (0..height)
+            .map(LineRef::Row)
+            .chain((0..width).map(LineRef::Column));
+        for line in lines {
+            if self.state(line).done {
+                continue;
+            }
+            let (clue, state) = match line {
+                LineRef::Row(y) => {
+                    self.line.load(self.board.row(y));
+                    (&self.board.rows[y], &mut self.rows[y])
+                }
+                LineRef::Column(x) => {
+                    self.line.load(self.board.column(x));
+                    (&self.board.cols[x], &mut self.cols[x])
+                }
+            };
+            let mut removed = RemovedMasks::default();
+            let journal = (self.open_snapshots > 0).then_some(&mut removed);
+            state.refresh(clue, &self.line, &mut self.packed, journal);
+            self.journal_filter(line, true, removed);
+        }
+    }
+
+    /// How far the solve got: eg to show a percentage, or where the
+    /// puzzle is the hardest, from lines with the most candidates.
+    pub fn progress(&self) -> Progress {
+        let lines = (self.board.rows.iter().zip(&self.rows))
+            .enumerate()
+            .map(|(y, (clue, state))| {
+                (
+                    clue,
+                    state,
+                    LineMask::from(self.board.row(y).collect::<Vec<_>>()),
+                )
+            })
+            .chain((self.board.cols.iter().zip(&self.cols)).enumerate().map(
+                |(x, (clue, state))| {
+                    (
+                        clue,
+                        state,
+                        LineMask::from(self.board.column(x).collect::<Vec<_>>()),
+                    )
+                },
+            ));
+        let mut active_lines = 0;
+        let mut candidate_counts = vec![];
+        for (clue, state, line) in lines {
+            if line.cells().contains(&CellState::Undecided) {
+                active_lines += 1;
+            }
+            candidate_counts.push(match &state.candidates {
+                Some(candidates) if !state.stale => candidates.len() as f64,
+                _ => count_placements(clue, &line).total,
+            });
+        }
+        let total = self.board.cells.len();
+        Progress {
+            decided: total - self.board.undecided().count(),
+            total,
+            active_lines,
+            candidate_counts,
+        }
+    }
+
+    /// The line cache, eg to read its statistics.
+    pub fn line_cache(&self) -> &LineCache {
+        &self.cache
+    }
+
+    /// Choose an undecided cell to guess, per the configured
+    /// [GuessStrategy], and the value to try first.  None if the grid
+    /// is complete.
+    fn pick_guess(&mut self) -> Option<(usize, CellState)> {
+        let width = self.board.width();
+        let height = self.board.height();
+        // The lower, the better.
+        let rate = |p: &Point| match self.config.guess_strategy {
+            GuessStrategy::MostConstrainedLine => self.rows[p.y].size().min(self.cols[p.x].size()),
+            GuessStrategy::FewestCandidates => {
+                self.rows[p.y].size().saturating_add(self.cols[p.x].size())
+            }
+            GuessStrategy::BorderFirst => p.x.min(width - 1 - p.x).min(p.y).min(height - 1 - p.y),
+        };
+        let undecided = || self.board.undecided().map(|i| Point::from_index(i, width));
+        let best = undecided().map(|p| rate(&p)).min()?;
+        let mut tied = undecided().filter(|p| rate(p) == best);
+        let cell = match &mut self.rng {
+            Some(rng) => {
+                let tied: Vec<Point> = tied.collect();
+                tied[rng.below(tied.len())]
+            }
+            None => tied.next().unwrap(),
+        };
+
+        // Try first what most of the row's candidates agree on.
+        let value = if self.rows[cell.y].mostly_filled(cell.x) {
+            CellState::Filled
+        } else {
+            CellState::Empty
+        };
+        Some((cell.index(width), value))
+    }
+
+    /// Propagate, then probe at `depth` for as long as probing makes
+    /// progress.
+    fn deduce(&mut self, depth: usize) -> Propagation<()> {
+        self.propagate()?;
+        while depth > 0 && self.probe_step(depth)? {}
+        Ok(())
+    }
+
+    /// Deduce from lines until the grid stops changing, in the order
+    /// of [SolverConfig::schedule].
+    fn propagate(&mut self) -> Propagation<()> {
+        match self.config.schedule {
+            Schedule::RowsThenColumns => self.propagate_by_passes(),
+            Schedule::MostChangedFirst => self.propagate_by_changes(),
+        }
+    }
+
+    /// Alternate filter and consensus steps until the grid stops
+    /// changing.
+    fn propagate_by_passes(&mut self) -> Propagation<()> {
+        loop {
+            self.check_cancelled()?;
+            self.pass += 1;
+            self.stats.passes += 1;
+            self.log(SolverEvent::Pass(self.pass));
+            if self.config.edge_logic {
+                self.edge_step()?;
+            }
+            let applied = self.filter_step()?;
+            let changed = self.consensus_step()?;
+            self.end_pass();
+            if !changed && !applied {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Solve the line with the most changes, mark its consensus, and
+    /// so on until no line changed.  Each pass solves a line once at
+    /// most, and lines that change again wait for the next one.
+    fn propagate_by_changes(&mut self) -> Propagation<()> {
+        let height = self.rows.len();
+        let mut visited = vec![false; height + self.cols.len()];
+        loop {
+            self.check_cancelled()?;
+            self.pass += 1;
+            self.stats.passes += 1;
+            self.log(SolverEvent::Pass(self.pass));
+            if self.config.edge_logic {
+                self.edge_step()?;
+            }
+            visited.fill(false);
+            let mut any = false;
+            while let Some(i) = self.most_changed_line(&visited) {
+                visited[i] = true;
+                any = true;
+                if i < height {
+                    self.rows[i].changed = 0;
+                    self.filter_row(i)?;
+                    self.apply_row(i)?;
+                } else {
+                    self.cols[i - height].changed = 0;
+                    self.filter_column(i - height)?;
+                    self.apply_column(i - height)?;
+                }
+            }
+            self.end_pass();
+            if !any {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Copy the grid if [SolverConfig::snapshot_every] says so.
+    fn end_pass(&mut self) {
+        let every = self.config.snapshot_every;
+        if every > 0 && self.pass.is_multiple_of(every) {
+            let (width, height) = (self.board.width(), self.board.height());
+            self.snapshots.push(PassSnapshot {
+                pass: self.pass,
+                grid: Grid::from_cells(width, height, self.board.cells.to_vec()),
+            });
+        }
+    }
+
+    /// The undecided line not `visited` yet with the most changes, or
+    /// with the fewest free cells among those: rows by index, then
+    /// columns.
+    fn most_changed_line(&self, visited: &[bool]) -> Option<usize> {
+        let rows =
+            (self.board.rows.iter().zip(&self.rows)).map(|(c, l)| (c, l, self.board.width()));
+        let cols =
+            (self.board.cols.iter().zip(&self.cols)).map(|(c, l)| (c, l, self.board.height()));
+        (rows.chain(cols))
+            .enumerate()
+            .filter(|(i, (_, line, _))| !visited[*i] && !line.done && line.changed > 0)
+            .min_by_key(|(_, (clue, line, len))| {
+                (Reverse(line.changed), len.saturating_sub(clue.min_length()))
+            })
+            .map(|(i, _)| i)
+    }
+
+    /// Probe every undecided cell once, and return true if that
+    /// decided any of them.
+    fn probe_step(&mut self, depth: usize) -> Propagation<bool> {
+        let mut progress = false;
+        for index in 0..self.board.cells.len() {
+            if self.board.cells.get(index) != CellState::Undecided {
+                continue;
+            }
+            let value = if self.probe(index, CellState::Filled, depth)? {
+                CellState::Empty
+            } else if self.probe(index, CellState::Empty, depth)? {
+                CellState::Filled
+            } else {
+                continue;
+            };
+            self.decide(index, value, Source::Probe);
+            let cell = Point::from_index(index, self.board.width());
+            self.log(SolverEvent::Probed { cell, value });
+            self.propagate()?;
+            progress = true;
+        }
+        Ok(progress)
+    }
+
+    /// Determine whether setting the cell at `index` to `value`
+    /// leads to a contradiction.  This leaves the solver unchanged.
+    fn probe(&mut self, index: usize, value: CellState, depth: usize) -> Propagation<bool> {
+        let saved = self.snapshot();
+        self.set(index, value);
+        let result = self.deduce(depth - 1);
+        self.rewind(saved);
+        match result {
+            Ok(()) => Ok(false),
+            Err(Stop::Contradiction) => Ok(true),
+            Err(Stop::Cancelled) => Err(Stop::Cancelled),
+        }
+    }
+
+    /// How each cell was decided, row by row, or None for undecided
+    /// cells.  Where the search had to guess is where the clues of a
+    /// puzzle need work.
+    pub fn guess_map(&self) -> Vec<Option<Decided>> {
+        (self.board.cells.to_vec().into_iter().zip(&self.decided_by))
+            .map(|(cell, decided)| (cell != CellState::Undecided).then_some(*decided))
+            .collect()
+    }
+
+    /// The grids of the passes so far, every
+    /// [SolverConfig::snapshot_every] passes.  These include the
+    /// passes of probes, and of guesses that were undone: they show
+    /// what the solver tried, not only what it kept.
+    pub fn pass_snapshots(&self) -> &[PassSnapshot] {
+        &self.snapshots
+    }
+
+    /// How many passes and guesses the solve took so far, on every
+    /// thread, since the last [Solver::solve] or [Solver::restore].
+    pub fn stats(&self) -> SolveStats {
+        self.stats
+    }
+
+    /// The deductions made so far, if [SolverConfig::trace] is set.
+    pub fn trace(&self) -> Option<&SolveTrace> {
+        self.trace.as_ref()
+    }
+
+    /// Record the current state of the solve, to [Solver::restore]
+    /// it later, possibly in another process.
+    pub fn checkpoint(&self) -> SolverCheckpoint {
+        SolverCheckpoint {
+            width: self.board.width(),
+            height: self.board.height(),
+            cells: self.board.cells.to_vec(),
+        }
+    }
+
+    /// Return to the state recorded in `checkpoint`.  Call
+    /// [Solver::resume] to carry on solving from there.
+    pub fn restore(&mut self, checkpoint: SolverCheckpoint) -> Result<(), CheckpointError> {
+        if checkpoint.width != self.board.width() || checkpoint.height != self.board.height() {
+            return Err(CheckpointError::DimensionMismatch);
+        }
+        for (i, cell) in checkpoint.cells.into_iter().enumerate() {
+            self.board.cells.set(i, cell);
+        }
+        self.history.clear();
+        self.rng = self.config.seed.map(Rng::new);
+        self.snapshots.clear();
+        self.stats = SolveStats::default();
+        (self.rows, self.cols) = initial_lines(&self.board);
+        self.budget = self.candidate_memory;
+        self.guesses = 0;
+        self.decided_by.fill(Decided::Deduced);
+        self.filter_step()
+            .map(|_| ())
+            .map_err(|_| CheckpointError::Inconsistent)
+    }
+
+    fn check_cancelled(&self) -> Propagation<()> {
+        match self.pause_at {
+            _ if self.cancelled() => Err(Stop::Cancelled),
+            _ if self.stops.iter().any(|stop| stop.load(Ordering::Relaxed)) => Err(Stop::Cancelled),
+            Some(pass) if self.pass >= pass => Err(Stop::Cancelled),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether the cancel flag is set.
+    fn cancelled(&self) -> bool {
+        (self.cancel.as_ref()).is_some_and(|flag| flag.load(Ordering::Relaxed))
+    }
+
+    /// Under [SolverConfig::max_memory_bytes], drop the candidates of
+    /// every line if they and what search keeps of them for the
+    /// guesses in effect and one more could take more than twice the
+    /// candidates may.
+    /// Lines without candidates are solved by dynamic programming
+    /// instead, which gives the same results.
+    fn shed_candidates(&mut self) {
+        if self.config.max_memory_bytes.is_none() {
+            return;
+        }
+        let listed = self.candidate_memory - self.budget;
+        if listed.saturating_mul(self.guesses + 2) > self.candidate_memory.saturating_mul(2) {
+            for line in self.rows.iter_mut().chain(&mut self.cols) {
+                line.candidates = None;
+            }
+            if self.open_snapshots > 0 {
+                for change in &mut self.journal {
+                    if let CandidateChange::Removed(..) = change {
+                        *change = CandidateChange::Shed;
+                    }
+                }
+                self.journal.push(CandidateChange::Shed);
+            }
+            self.budget = self.candidate_memory;
+            self.log(SolverEvent::Shed);
+        }
+    }
+
+    fn snapshot(&mut self) -> Snapshot {
+        self.open_snapshots += 1;
+        Snapshot {
+            cells: self.history.point(),
+            candidates: self.journal.len(),
+            rows: self
                 .rows
                 .iter()
-                .map(|r| candidates(r, from.width()))
+                .map(LineState::without_candidates)
                 .collect(),
-            cols: from
+            cols: self
                 .cols
                 .iter()
-                .map(|r| candidates(r, from.height()))
+                .map(LineState::without_candidates)
                 .collect(),
-            nono: from,
+            trace_len: self.trace.as_ref().map_or(0, SolveTrace::len),
+            budget: self.budget,
+            guesses: self.guesses,
         }
     }
 
-    pub fn solve(&mut self) {
-        // TODO Prepare
-        self.nono.clear_solution();
-        while self.nono.cells.iter().any(|c| *c == CellState::Undecided) {
-            self.consensus_step();
-            self.filter_step();
+    /// Return to `saved`, undoing the changes to the candidates
+    /// journaled since.
+    fn rewind(&mut self, saved: Snapshot) {
+        self.history.revert_to(&mut self.board, saved.cells);
+        let mut shed = false;
+        while self.journal.len() > saved.candidates {
+            match self.journal.pop().unwrap() {
+                CandidateChange::Removed(line, removed) => {
+                    if let Some(candidates) = &mut self.state_mut(line).candidates {
+                        candidates.restore(removed);
+                    }
+                }
+                CandidateChange::Listed(line) => self.state_mut(line).candidates = None,
+                CandidateChange::Shed => shed = true,
+            }
+        }
+        let lines = self.rows.iter_mut().chain(&mut self.cols);
+        for (line, saved) in lines.zip(saved.rows.into_iter().chain(saved.cols)) {
+            let candidates = line.candidates.take();
+            *line = LineState {
+                candidates,
+                ..saved
+            };
+        }
+        self.budget = match shed {
+            true => self.candidate_memory,
+            false => saved.budget,
+        };
+        self.guesses = saved.guesses;
+        if let Some(trace) = &mut self.trace {
+            trace.truncate(saved.trace_len);
+        }
+        self.open_snapshots -= 1;
+    }
+
+    /// Go on from the current state, never to rewind to `saved`.
+    fn keep(&mut self, _saved: Snapshot) {
+        self.open_snapshots -= 1;
+        if self.open_snapshots == 0 {
+            self.journal.clear();
         }
-        // TODO Finalize
+    }
+
+    /// Set the cell at `index` to `value`, and count the change in
+    /// its lines.
+    fn set(&mut self, index: usize, value: CellState) {
+        self.history.set(&mut self.board, index, value);
+        let cell = Point::from_index(index, self.board.width());
+        self.rows[cell.y].changed += 1;
+        self.cols[cell.x].changed += 1;
+    }
+
+    /// Set the cell at `index` to `value`, as decided by `source`.
+    fn decide(&mut self, index: usize, value: CellState, source: Source) {
+        self.set(index, value);
+        self.decided_by[index] = match source {
+            Source::Guess => Decided::Guessed,
+            _ if self.guesses > 0 => Decided::AfterGuess,
+            _ => Decided::Deduced,
+        };
+        self.record(Point::from_index(index, self.board.width()), value, source);
+    }
+
+    /// Apply the consensus `value` of a line to `cell`.  Returns true
+    /// if the cell changed, and a contradiction if it was already set
+    /// to the other value.
+    fn mark(&mut self, cell: Point, value: CellState, source: Source) -> Propagation<bool> {
+        match (self.board.get(cell), value) {
+            (_, CellState::Undecided) => Ok(false),
+            (CellState::Undecided, _) => {
+                self.decide(cell.index(self.board.width()), value, source);
+                Ok(true)
+            }
+            (old, new) if old == new => Ok(false),
+            _ => Err(Stop::Contradiction),
+        }
+    }
+
+    fn record(&mut self, cell: Point, value: CellState, source: Source) {
+        if let Some(trace) = &mut self.trace {
+            trace.push(Deduction {
+                cell,
+                value,
+                source,
+                pass: self.pass,
+            });
+        }
+    }
+
+    /// Mark what the border technique tells of every line left, see
+    /// [edge_logic], before lines are solved.
+    fn edge_step(&mut self) -> Propagation<()> {
+        let (width, height) = (self.board.width(), self.board.height());
+        let lines = (0..height)
+            .map(LineRef::Row)
+            .chain((0..width).map(LineRef::Column));
+        for line in lines {
+            let edges = match line {
+                LineRef::Row(y) if !self.rows[y].done => {
+                    self.line.load(self.board.row(y));
+                    edge_cells(&self.board.rows[y], &self.line)
+                }
+                LineRef::Column(x) if !self.cols[x].done => {
+                    self.line.load(self.board.column(x));
+                    edge_cells(&self.board.cols[x], &self.line)
+                }
+                _ => continue,
+            };
+            let Some(edges) = edges else {
+                self.log(SolverEvent::Conflict(line));
+                return Err(Stop::Contradiction);
+            };
+            for (i, value) in edges {
+                self.mark(line.cell(i), value, Source::Edge(line))?;
+            }
+        }
+        Ok(())
     }
 
     /// The consensus determines the cells that *must* be empty or
     /// filled given the constraint (and only the constraint, not
     /// the state of the grid) for each row and column, and marks
-    /// those cells' statuses on the grid.
-    fn consensus_step(&mut self) {
-        // Rows
-        for (y, row) in self.rows.iter().enumerate() {
-            let consensus = find_consensus(row);
-            for (x, square) in consensus.iter().enumerate() {
-                if *square != CellState::Undecided {
-                    // FIXME don't overwrite a different value: check for conflicts.
-                    self.nono[(x, y)] = *square;
-                }
-            }
-        }
-
-        // Columns
-        for (x, col) in self.cols.iter().enumerate() {
-            let consensus = find_consensus(col);
-                for (y, square) in consensus.iter().enumerate() {
-                    if *square != CellState::Undecided {
-                        // FIXME don't overwrite a different value: check for conflicts.
-                        self.nono[(x, y)] = *square;
-                    }
+    /// those cells' statuses on the grid.  Returns true if this
+    /// changed the grid.
+    fn consensus_step(&mut self) -> Propagation<bool> {
+        let mut changed = false;
+        for y in 0..self.rows.len() {
+            if !self.rows[y].done {
+                changed |= self.apply_row(y)?;
+            }
+        }
+        for x in 0..self.cols.len() {
+            if !self.cols[x].done {
+                changed |= self.apply_column(x)?;
+            }
+        }
+        Ok(changed)
+    }
+
+    /// Mark the consensus of row `y` on the grid, and return true if
+    /// that changed it.
+    fn apply_row(&mut self, y: usize) -> Propagation<bool> {
+        // Marking cells doesn't touch the consensus, so borrow it.
+        let consensus = mem::take(&mut self.rows[y].consensus);
+        let marked = (consensus.cells().iter().enumerate())
+            .map(|(x, square)| self.mark(Point::new(x, y), *square, Source::Line(LineRef::Row(y))))
+            .try_fold(false, |changed, marked| Ok(changed | marked?));
+        self.rows[y].consensus = consensus;
+        marked
+    }
+
+    fn apply_column(&mut self, x: usize) -> Propagation<bool> {
+        let consensus = mem::take(&mut self.cols[x].consensus);
+        let marked = (consensus.cells().iter().enumerate())
+            .map(|(y, square)| {
+                self.mark(Point::new(x, y), *square, Source::Line(LineRef::Column(x)))
+            })
+            .try_fold(false, |changed, marked| Ok(changed | marked?));
+        self.cols[x].consensus = consensus;
+        marked
+    }
+
+    /// The filter step eliminates, for each row and column, the
+    /// candidates that don't fit in the grid, that is, that
+    /// require that a cell marked as empty be filled, or filled
+    /// be empty.
+    ///
+    /// Lines left with a single candidate are marked at once, so that
+    /// the lines after them already use it.  Returns true if that
+    /// changed the grid.
+    fn filter_step(&mut self) -> Propagation<bool> {
+        let mut applied = false;
+        for y in 0..self.rows.len() {
+            if !self.rows[y].done {
+                self.filter_row(y)?;
+                if is_complete(&self.rows[y].consensus) {
+                    applied |= self.apply_row(y)?;
+                    self.rows[y].done = true;
                 }
             }
         }
+        for x in 0..self.cols.len() {
+            if !self.cols[x].done {
+                self.filter_column(x)?;
+                if is_complete(&self.cols[x].consensus) {
+                    applied |= self.apply_column(x)?;
+                    self.cols[x].done = true;
+                }
+            }
+        }
+        Ok(applied)
+    }
+
+    fn filter_row(&mut self, y: usize) -> Propagation<()> {
+        self.line.load(self.board.row(y));
+        let (clue, row) = (&self.board.rows[y], &mut self.rows[y]);
+        let listed = row.candidates.is_some();
+        let mut removed = RemovedMasks::default();
+        let journal = (self.open_snapshots > 0).then_some(&mut removed);
+        let result = row.filter(
+            clue,
+            &self.line,
+            &mut self.packed,
+            &mut self.cache,
+            &mut self.budget,
+            journal,
+        );
+        self.journal_filter(LineRef::Row(y), listed, removed);
+        self.log_filter(LineRef::Row(y), listed, &result);
+        result
+    }
+
+    fn filter_column(&mut self, x: usize) -> Propagation<()> {
+        self.line.load(self.board.column(x));
+        let (clue, col) = (&self.board.cols[x], &mut self.cols[x]);
+        let listed = col.candidates.is_some();
+        let mut removed = RemovedMasks::default();
+        let journal = (self.open_snapshots > 0).then_some(&mut removed);
+        let result = col.filter(
+            clue,
+            &self.line,
+            &mut self.packed,
+            &mut self.cache,
+            &mut self.budget,
+            journal,
+        );
+        self.journal_filter(LineRef::Column(x), listed, removed);
+        self.log_filter(LineRef::Column(x), listed, &result);
+        result
+    }
+
+    /// Journal what filtering `line` did to its candidates, if a
+    /// snapshot may be rewound to: listed them, if they weren't
+    /// `listed` before, or `removed` some.
+    fn journal_filter(&mut self, line: LineRef, listed: bool, removed: RemovedMasks) {
+        if self.open_snapshots == 0 {
+            return;
+        }
+        if !listed && self.state(line).candidates.is_some() {
+            self.journal.push(CandidateChange::Listed(line));
+        }
+        if !removed.is_empty() {
+            self.journal.push(CandidateChange::Removed(line, removed));
+        }
+    }
+
+    fn state(&self, line: LineRef) -> &LineState {
+        match line {
+            LineRef::Row(y) => &self.rows[y],
+            LineRef::Column(x) => &self.cols[x],
+        }
+    }
+
+    fn state_mut(&mut self, line: LineRef) -> &mut LineState {
+        match line {
+            LineRef::Row(y) => &mut self.rows[y],
+            LineRef::Column(x) => &mut self.cols[x],
+        }
+    }
+
+    /// Report what filtering `line` did, if there's a logger: whether
+    /// it listed candidates, which weren't `listed` before, or found
+    /// a conflict.
+    fn log_filter(&mut self, line: LineRef, listed: bool, result: &Propagation<()>) {
+        if self.logger.is_none() {
+            return;
+        }
+        match (result, &self.state(line).candidates) {
+            (Err(Stop::Contradiction), _) => self.log(SolverEvent::Conflict(line)),
+            (_, Some(candidates)) if !listed => {
+                let count = candidates.len();
+                self.log(SolverEvent::Listed { line, count })
+            }
+            _ => (),
+        }
+    }
+}
+
+// * Parallel search
 
-        /// The filter step eliminates, for each row and column, the
-        /// candidates that don't fit in the grid, that is, that
-        /// require that a cell marked as empty be filled, or filled
-        /// be empty.
-        fn filter_step(&mut self) {
-            // Rows
-            for y in 0..self.nono.height() {
-                let grid_row = self.nono.row(y).unwrap();
-                self.rows[y].retain(|cand| can_place(&grid_row, cand));
+/// What a branch of a parallel search takes from the solver, and
+/// brings back if it solves the puzzle.
+#[cfg(feature = "std")]
+struct Branch {
+    cells: Vec<CellState>,
+    rows: Vec<LineState>,
+    cols: Vec<LineState>,
+    pass: usize,
+    /// What the branch did, for [Solver::stats].
+    stats: SolveStats,
+    trace: Option<SolveTrace>,
+    cache: LineCache,
+    budget: usize,
+    guesses: usize,
+    decided_by: Vec<Decided>,
+    rng: Option<Rng>,
+    snapshots: Vec<PassSnapshot>,
+}
+
+#[cfg(feature = "std")]
+impl<'a, S: GridStorage> Solver<'a, S> {
+    /// [Solver::search] from a guess at `index`, trying `first` and
+    /// the other value at once, each in a solver of its own on a
+    /// thread of its own.
+    fn search_in_parallel(&mut self, index: usize, first: CellState) -> Propagation<()> {
+        let cell = Point::from_index(index, self.board.width());
+        let depth = self.guesses;
+        let values = [first, first.opposite()];
+        for value in values {
+            self.log(SolverEvent::Guess { cell, value, depth });
+            self.stats.guesses += 1;
+        }
+        let found = Arc::new(AtomicBool::new(false));
+        let mut stops = self.stops.clone();
+        stops.push(Arc::clone(&found));
+        let (width, rows, cols) = (self.board.width(), self.board.rows, self.board.cols);
+        let (config, cancel) = (&self.config, &self.cancel);
+        let (candidate_memory, pause_at) = (self.candidate_memory, self.pause_at);
+        let branches = values.map(|value| (value, self.branch()));
+        let results: Vec<(Propagation<()>, Branch)> = std::thread::scope(|scope| {
+            let threads: Vec<_> = (branches.into_iter())
+                .map(|(value, mut branch)| {
+                    let (found, stops) = (Arc::clone(&found), stops.clone());
+                    scope.spawn(move || {
+                        let mut cells = mem::take(&mut branch.cells);
+                        let board = Board {
+                            width,
+                            rows,
+                            cols,
+                            cells: &mut cells,
+                        };
+                        let mut solver = Solver {
+                            rows: branch.rows,
+                            cols: branch.cols,
+                            board,
+                            config: config.clone(),
+                            cancel: cancel.clone(),
+                            pass: branch.pass,
+                            stats: SolveStats::default(),
+                            trace: branch.trace,
+                            history: History::new(),
+                            journal: vec![],
+                            open_snapshots: 0,
+                            cache: branch.cache,
+                            budget: branch.budget,
+                            candidate_memory,
+                            guesses: branch.guesses,
+                            decided_by: branch.decided_by,
+                            line: LineMask::default(),
+                            packed: PackedLine::default(),
+                            logger: None,
+                            rng: branch.rng,
+                            snapshots: branch.snapshots,
+                            pause_at,
+                            stops,
+                        };
+                        solver.decide(index, value, Source::Guess);
+                        solver.guesses += 1;
+                        let result = solver.search();
+                        if result.is_ok() {
+                            found.store(true, Ordering::Relaxed);
+                        }
+                        let branch = Branch {
+                            cells: vec![],
+                            rows: solver.rows,
+                            cols: solver.cols,
+                            pass: solver.pass,
+                            stats: solver.stats,
+                            trace: solver.trace,
+                            cache: solver.cache,
+                            budget: solver.budget,
+                            guesses: solver.guesses,
+                            decided_by: solver.decided_by,
+                            rng: solver.rng,
+                            snapshots: solver.snapshots,
+                        };
+                        (result, Branch { cells, ..branch })
+                    })
+                })
+                .collect();
+            (threads.into_iter())
+                .map(|thread| {
+                    thread
+                        .join()
+                        .unwrap_or_else(|e| std::panic::resume_unwind(e))
+                })
+                .collect()
+        });
+        // Both branches worked, whichever solved the puzzle.
+        for (_, branch) in &results {
+            self.stats += branch.stats;
+        }
+        let mut cancelled = false;
+        for (result, branch) in results {
+            match result {
+                Ok(()) => {
+                    self.adopt(branch);
+                    return Ok(());
+                }
+                Err(Stop::Contradiction) => self.log(SolverEvent::Backtrack { cell, depth }),
+                Err(Stop::Cancelled) => cancelled = true,
             }
-            // Cols
-            for x in 0..self.nono.width() {
-                let grid_col = self.nono.column(x).unwrap();
-                self.cols[x].retain(|cand| can_place(&grid_col, cand));
+        }
+        match cancelled {
+            true => Err(Stop::Cancelled),
+            false => Err(Stop::Contradiction),
+        }
+    }
+
+    /// A copy of the state of the solver, for a branch to start from.
+    fn branch(&self) -> Branch {
+        Branch {
+            cells: self.board.cells.to_vec(),
+            rows: self.rows.clone(),
+            cols: self.cols.clone(),
+            pass: self.pass,
+            stats: SolveStats::default(),
+            trace: self.trace.clone(),
+            cache: self.cache.clone(),
+            budget: self.budget,
+            guesses: self.guesses,
+            decided_by: self.decided_by.clone(),
+            rng: self.rng.clone(),
+            snapshots: vec![],
+        }
+    }
+
+    /// Take on the state of a branch that solved the puzzle.
+    fn adopt(&mut self, branch: Branch) {
+        for (i, cell) in branch.cells.into_iter().enumerate() {
+            if self.board.cells.get(i) != cell {
+                self.set(i, cell);
             }
         }
+        self.rows = branch.rows;
+        self.cols = branch.cols;
+        self.pass = branch.pass;
+        self.trace = branch.trace;
+        self.cache = branch.cache;
+        self.budget = branch.budget;
+        self.guesses = branch.guesses;
+        self.decided_by = branch.decided_by;
+        self.rng = branch.rng;
+        self.snapshots.extend(branch.snapshots);
     }
+}
 
-    /// Find the intersection of a set of a [CandidateMask], that is,
-    /// the common part of all the masks in the set.
-    pub fn find_consensus(cands: &CandidateMaskSet) -> CandidateMask {
-        let mut ret: CandidateMask = cands[0].clone();
+// * Solving without blocking
+//
+// The solver recurses, so it can't stop in the middle of a solve and
+// go on later.  Instead, a poll stops it as if cancelled after a few
+// passes, which undoes the guesses and probes in progress but keeps
+// what was deduced without them, and the next poll resumes from
+// there.  Polls that keep nothing get twice as many passes as the
+// last, so that every solve ends.
 
-        // @FIXME Use a real iterator (this was tricky when I tried.)
-        for cand in cands.iter().skip(1) {
-            ret = ret
-                .iter()
-                .zip(cand.iter())
-                .map(|(a, b)| a.consensus_eq(b))
+/// How many propagation passes the first poll of a [SolveFuture]
+/// runs.
+#[cfg(feature = "async")]
+const POLL_PASSES: usize = 4;
+
+/// A solve that runs a few propagation passes each time it's polled,
+/// see [Solver::solve_async].
+#[cfg(feature = "async")]
+pub struct SolveFuture<'s, 'a, S> {
+    solver: &'s mut Solver<'a, S>,
+    started: bool,
+    /// How many passes the next poll runs.
+    passes: usize,
+}
+
+#[cfg(feature = "async")]
+impl<'a, S: GridStorage> Solver<'a, S> {
+    /// Solve the puzzle from scratch, like [Solver::solve], as a
+    /// future that returns to the executor every few propagation
+    /// passes, so that an event loop, a GUI or a web page goes on
+    /// while it solves.  This needs no particular runtime: the future
+    /// asks to be polled again at once.
+    ///
+    /// Dropping the future cancels the solve, and the grid keeps what
+    /// was deduced, as does the cancel flag, see
+    /// [Solver::set_cancel_flag].  Solving this way takes longer than
+    /// [Solver::solve] on puzzles that need search, which restarts
+    /// from its first guess at each poll.
+    pub fn solve_async(&mut self) -> SolveFuture<'_, 'a, S> {
+        SolveFuture {
+            solver: self,
+            started: false,
+            passes: POLL_PASSES,
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<'a, S: GridStorage> SolveFuture<'_, 'a, S> {
+    /// The solver, eg to show its [Solver::progress] between polls.
+    pub fn solver(&self) -> &Solver<'a, S> {
+        self.solver
+    }
+}
+
+#[cfg(feature = "async")]
+impl<S: GridStorage> Future for SolveFuture<'_, '_, S> {
+    type Output = SolveOutcome;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<SolveOutcome> {
+        let this = self.get_mut();
+        let solver = &mut *this.solver;
+        let undecided = match this.started {
+            true => solver.board.undecided().count(),
+            false => solver.board.cells.len(),
+        };
+        solver.pause_at = Some(solver.pass + this.passes);
+        let outcome = match mem::replace(&mut this.started, true) {
+            true => solver.resume(),
+            false => solver.solve(),
+        };
+        solver.pause_at = None;
+        if outcome != SolveOutcome::Cancelled || solver.cancelled() {
+            return Poll::Ready(outcome);
+        }
+        if solver.board.undecided().count() >= undecided {
+            this.passes = this.passes.saturating_mul(2);
+        }
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+// * Memory
+
+/// What the solver needs in memory for a puzzle, in bytes, roughly.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryEstimate {
+    /// The grid, what the solver knows of its lines, and the tables
+    /// of the line solver, which it can't do without.
+    pub fixed: usize,
+    /// Every candidate of every line, on the empty grid.
+    pub candidates: usize,
+    /// An entry of the [LineCache], for the longest line.
+    pub cache_entry: usize,
+}
+
+impl MemoryEstimate {
+    /// The needs of the puzzle of `width` columns with clues `rows`
+    /// and `cols`.
+    pub fn of(width: usize, rows: &[Constraint], cols: &[Constraint]) -> MemoryEstimate {
+        let height = rows.len();
+        let lines =
+            (rows.iter().map(|clue| (clue, width))).chain(cols.iter().map(|clue| (clue, height)));
+        let (mut candidates, mut table, mut cache_entry) = (0.0, 0, 0);
+        for (clue, len) in lines {
+            let count = count_placements(clue, &LineMask::undecided(len)).total;
+            candidates += candidate_bytes(count, len);
+            // Both tables of placements, by run and by cell.
+            table = table.max(2 * (clue.len() + 1) * (len + 1) * mem::size_of::<f64>());
+            cache_entry = cache_entry.max(
+                mem::size_of::<(Constraint, Vec<u64>, Option<LineMask>)>()
+                    + clue.len() * mem::size_of::<usize>()
+                    + len.div_ceil(32) * mem::size_of::<u64>()
+                    + len,
+            );
+        }
+        // Per cell: the grid, the guess map, an entry of the history,
+        // and the consensus of its row and its column.
+        let cell = 3 + mem::size_of::<(usize, CellState)>() + mem::size_of::<Decided>();
+        let line = mem::size_of::<LineState>();
+        MemoryEstimate {
+            fixed: width * height * cell + (width + height) * line + table,
+            candidates: candidates.min(usize::MAX as f64) as usize,
+            cache_entry,
+        }
+    }
+
+    /// The capacity of the line cache and the budget of candidates
+    /// under `config`, sharing what [SolverConfig::max_memory_bytes]
+    /// leaves beyond [MemoryEstimate::fixed] between them: a tenth
+    /// for the cache, and the rest for the candidates and those
+    /// search keeps to put back.
+    fn limits(&self, config: &SolverConfig) -> (usize, usize) {
+        let Some(max) = config.max_memory_bytes else {
+            return (config.line_cache, config.candidate_memory);
+        };
+        let left = max.saturating_sub(self.fixed);
+        let cache = config.line_cache.min(left / 10 / self.cache_entry.max(1));
+        let candidates = config
+            .candidate_memory
+            .min((left - cache * self.cache_entry) / 2);
+        (cache, candidates)
+    }
+}
+
+/// The bytes `count` candidates take on a line of `len` cells.
+fn candidate_bytes(count: f64, len: usize) -> f64 {
+    let mask = mem::size_of::<LineMask>() + len + len.div_ceil(64) * mem::size_of::<u64>();
+    count * mask as f64
+}
+
+/// Whether every cell of `line` is decided: a consensus is when a
+/// single candidate is left.
+fn is_complete(line: &LineMask) -> bool {
+    !line.cells().contains(&CellState::Undecided)
+}
+
+/// The state of every row and column of `board` before solving,
+/// with no candidates listed yet.
+fn initial_lines<S: GridStorage>(board: &Board<S>) -> (Vec<LineState>, Vec<LineState>) {
+    let rows = vec![LineState::new(board.width()); board.height()];
+    let cols = vec![LineState::new(board.height()); board.width()];
+    (rows, cols)
+}
+
+// * Brute force
+//
+// A reference solver, which shares no code with the rest: it tries
+// every filling of every row, so it is slow, but simple enough to
+// check the [Solver] against.
+
+/// The widest puzzle [brute_force] solves, since it enumerates the
+/// fillings of rows.
+pub const BRUTE_FORCE_MAX_WIDTH: usize = 15;
+
+/// The largest puzzle [brute_force] solves, in cells.
+pub const BRUTE_FORCE_MAX_CELLS: usize = 15 * 15;
+
+/// Up to `limit` solutions of `puzzle`, or every one with a `limit`
+/// of `usize::MAX`, or None if the puzzle is larger than
+/// [BRUTE_FORCE_MAX_WIDTH] or [BRUTE_FORCE_MAX_CELLS].
+///
+/// This tries every filling of each row that matches its clue, from
+/// the top, and drops those that no column can go on from.  It takes
+/// time exponential in the size of the puzzle, and is meant as ground
+/// truth in tests.
+pub fn brute_force(puzzle: &Puzzle, limit: usize) -> Option<Vec<Grid>> {
+    let (w, h) = (puzzle.width(), puzzle.height());
+    if w > BRUTE_FORCE_MAX_WIDTH || w * h > BRUTE_FORCE_MAX_CELLS {
+        return None;
+    }
+    let rows: Vec<Vec<Vec<bool>>> = (puzzle.rows.iter())
+        .map(|clue| {
+            (0..1u32 << w)
+                .map(|bits| (0..w).map(|x| bits & (1 << x) != 0).collect::<Vec<bool>>())
+                .filter(|row| matches(clue, row))
                 .collect()
+        })
+        .collect();
+
+    let mut ret = vec![];
+    let mut picked: Vec<&[bool]> = vec![];
+    search(puzzle, &rows, &mut picked, &mut ret, limit);
+    let grids = ret.into_iter().map(|cells: Vec<bool>| {
+        Grid::from_cells(w, h, cells.into_iter().map(CellState::from).collect())
+    });
+    Some(grids.collect())
+}
+
+fn search<'a>(
+    puzzle: &Puzzle,
+    rows: &'a [Vec<Vec<bool>>],
+    picked: &mut Vec<&'a [bool]>,
+    found: &mut Vec<Vec<bool>>,
+    limit: usize,
+) {
+    if found.len() >= limit {
+        return;
+    }
+    let y = picked.len();
+    if y == rows.len() {
+        let cols_match = (puzzle.cols.iter().enumerate())
+            .all(|(x, clue)| matches(clue, &picked.iter().map(|r| r[x]).collect::<Vec<_>>()));
+        if cols_match {
+            found.push(picked.concat());
         }
-        ret
+        return;
+    }
+    for row in &rows[y] {
+        picked.push(row);
+        let feasible = (puzzle.cols.iter().enumerate()).all(|(x, clue)| {
+            let prefix: Vec<bool> = picked.iter().map(|r| r[x]).collect();
+            may_start(clue, &prefix, rows.len())
+        });
+        if feasible {
+            search(puzzle, rows, picked, found, limit);
+        }
+        picked.pop();
     }
+}
 
-    /// Generate the full set of candidates for a constraint and a
-    /// given capacity (height or width)
-    pub fn candidates(constraint: &Constraint, capacity: usize) -> CandidateMaskSet {
-        // How many sequences of blanks we need.
-        let count = constraint.len() + 1;
-        // The total count of squares to fill.
-        let occupation = constraint.iter().sum::<usize>();
-        // The number of blanks to distribute.
-        let blanks = capacity - occupation;
+/// The runs of filled cells of `cells`, as the clue reads them.
+fn runs(cells: &[bool], wraps: bool) -> Vec<usize> {
+    let mut ret: Vec<usize> = cells
+        .split(|c| !c)
+        .map(<[bool]>::len)
+        .filter(|n| *n > 0)
+        .collect();
+    let ends_filled = cells.first() == Some(&true) && cells.last() == Some(&true);
+    if wraps && ends_filled && ret.len() > 1 {
+        let head = ret.remove(0);
+        *ret.last_mut().unwrap() += head;
+    }
+    ret
+}
 
-        // println!(
-        //     "Proceeding ({:?} in {}). {} seqs, {} squares to fill, so {} blanks",
-        //     constraint, capacity, count, occupation, blanks
-        // );
+/// Which of `cells` are filled.
+fn filled(cells: impl IntoIterator<Item = CellState>) -> Vec<bool> {
+    cells.into_iter().map(|c| c == CellState::Filled).collect()
+}
 
-        let mut results = vec![];
-        make_candidates(blanks, 1, count, vec![], &mut results);
+/// Whether the line `cells` matches `clue`.
+fn matches(clue: &Constraint, cells: &[bool]) -> bool {
+    if clue.is_unknown() {
+        return true;
+    }
+    let runs = runs(cells, clue.wraps());
+    runs.len() == clue.len()
+        && (clue.lengths().zip(&runs)).all(|(len, run)| len.is_none_or(|len| len == *run))
+}
 
-        results
-            .iter()
-            .map(|cand| into_mask(cand, constraint))
-            .collect()
+/// Whether some line of `len` cells starting with `prefix` may match
+/// `clue`.  Wrapping runs are only checked by their total.
+fn may_start(clue: &Constraint, prefix: &[bool], len: usize) -> bool {
+    if clue.is_unknown() || (clue.wraps() && clue.has_unknown_lengths()) {
+        return true;
+    } else if clue.wraps() {
+        let filled = prefix.iter().filter(|c| **c).count();
+        return filled <= clue.sum() && clue.sum() <= filled + len - prefix.len();
+    }
+    let lengths: Vec<Option<usize>> = clue.lengths().collect();
+    let mut runs = runs(prefix, false);
+    let open = if prefix.last() == Some(&true) {
+        runs.pop()
+    } else {
+        None
+    };
+    let closed_match = runs.len() <= lengths.len()
+        && (lengths.iter().zip(&runs)).all(|(len, run)| len.is_none_or(|len| len == *run));
+    if !closed_match {
+        return false;
     }
+    let rest = Constraint::with_unknowns(lengths[runs.len()..].to_vec()).unwrap();
+    match open {
+        Some(open) => {
+            lengths
+                .get(runs.len())
+                .is_some_and(|next| next.is_none_or(|next| open <= next))
+                && prefix.len() - open + rest.min_length() <= len
+        }
+        None => prefix.len() + rest.min_length() <= len,
+    }
+}
 
-    /// Convert a [Vec<usize>] as produced by [candidates] and a
-    /// [Constraint] as lists of lenghths into a CellState mask.
-    pub fn into_mask(empty: &Vec<usize>, filled: &Constraint) -> CandidateMask {
-        assert!(empty.len() == filled.len() + 1);
-        let mut ret: CandidateMask = vec![];
-        for (e, f) in empty.iter().zip(filled.iter().chain(once::<&usize>(&0))) {
-            for _ in 0..*e {
-                ret.push(CellState::Empty);
-            }
-            for _ in 0..*f {
-                ret.push(CellState::Filled);
-            }
+/// Whether `grid` matches every clue of `puzzle`, checked without
+/// line logic, as [brute_force] does.
+pub fn is_solution(puzzle: &Puzzle, grid: &Grid) -> bool {
+    let rows = (0..puzzle.height()).all(|y| {
+        matches(
+            &puzzle.rows[y],
+            &filled(grid.row(y).unwrap().iter().copied()),
+        )
+    });
+    let cols = (0..puzzle.width())
+        .all(|x| matches(&puzzle.cols[x], &filled(grid.column_view(x).unwrap())));
+    rows && cols
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+    use core::sync::atomic::AtomicUsize;
+
+    /// A puzzle of random clues, from a random goal of at most 6×6
+    /// cells, made toroidal, or contradictory, as `rng` decides.
+    fn random_puzzle(rng: &mut Rng) -> Puzzle {
+        let (w, h) = (1 + rng.below(6), 1 + rng.below(6));
+        let cells = (0..w * h)
+            .map(|_| CellState::from(rng.below(2) == 0))
+            .collect();
+        let mut ret = Puzzle::from_solution(&Grid::from_cells(w, h, cells));
+        ret.goal = None;
+        if rng.below(4) == 0 {
+            ret.set_toroidal(true);
+        }
+        if rng.below(4) == 0 {
+            // Some other clue, which usually leaves no solution.
+            let y = rng.below(h);
+            let run = 1 + rng.below(w);
+            ret.rows[y] = Constraint::new(vec![run]).unwrap();
+            let wraps = ret.is_toroidal();
+            ret.rows[y].set_wraps(wraps);
         }
         ret
     }
 
-    /// Recursively generate the candidate set.
-    fn make_candidates(
-        blanks: usize,
-        nth_seq: usize,
-        total_seqs: usize,
-        base: Vec<usize>,
-        results: &mut Vec<Vec<usize>>,
-    ) {
-        if nth_seq > total_seqs {
-            if blanks == 0 {
-                results.push(base);
+    #[test]
+    fn solver_agrees_with_brute_force() {
+        let mut rng = Rng::new(868);
+        let (mut solved, mut contradictory) = (0, 0);
+        for _ in 0..500 {
+            let puzzle = random_puzzle(&mut rng);
+            let expected = brute_force(&puzzle, 2).unwrap();
+            match Solver::solve_puzzle(&puzzle, SolverConfig::default()) {
+                Ok(grid) => {
+                    assert!(is_solution(&puzzle, &grid), "{:?}", puzzle);
+                    if let [only] = &expected[..] {
+                        assert_eq!(&grid, only, "{:?}", puzzle);
+                    }
+                    solved += 1;
+                }
+                Err(SolveError::Contradiction) => {
+                    assert!(expected.is_empty(), "{:?}", puzzle);
+                    contradictory += 1;
+                }
+                Err(e) => panic!("{:?}: {}", puzzle, e),
             }
-            return;
         }
+        // Both kinds of puzzles came up.
+        assert!(solved > 100 && contradictory > 10);
+    }
 
-        let min = if nth_seq == 1 || nth_seq == total_seqs {
-            0
-        } else {
-            1
-        };
-
-        for i in min..blanks + 1 {
-            let mut next = base.clone();
-            next.push(i);
-            make_candidates(blanks - i, nth_seq + 1, total_seqs, next, results);
+    #[test]
+    #[cfg(feature = "std")]
+    fn stats_count_every_branch() {
+        let mut rng = Rng::new(908);
+        let mut more = 0;
+        for _ in 0..200 {
+            let puzzle = random_puzzle(&mut rng);
+            let config = SolverConfig {
+                threads: 4,
+                ..SolverConfig::default()
+            };
+            let mut grid = puzzle.grid();
+            let mut solver = Solver::for_grid(&puzzle, &mut grid, config);
+            // What the logger sees, of this solver but not its branches.
+            let logged = Arc::new([AtomicUsize::new(0), AtomicUsize::new(0)]);
+            let count = Arc::clone(&logged);
+            solver.set_logger(move |event| match event {
+                SolverEvent::Pass(_) => _ = count[0].fetch_add(1, Ordering::Relaxed),
+                SolverEvent::Guess { .. } => _ = count[1].fetch_add(1, Ordering::Relaxed),
+                _ => (),
+            });
+            solver.solve();
+            let stats = solver.stats();
+            let [passes, guesses] = [0, 1].map(|i| logged[i].load(Ordering::Relaxed));
+            assert!(stats.passes >= passes && stats.guesses >= guesses);
+            if stats.passes > passes {
+                more += 1;
+            }
         }
+        assert!(more > 10);
     }
 
-    /// Compare a row or column of the grid with a candidate, and
-    /// return true if this candidate would fit this row or column,
-    /// that is, if there are no incompatible Filled/Empty cells
-    /// between the grid and the candidate.
-    fn can_place(grid: &[CellState], cand: &[CellState]) -> bool {
-        grid.iter().zip(cand).all(|(g, c)| g.accepts(c))
-        // println!(
-        //     "{} over {}? {:?}",
-        //     mask_as_string(&cand),
-        //     mask_as_string(&grid),
-        //     ret
-        // );
-        // ret
+    #[test]
+    fn shedding_candidates_changes_no_solution() {
+        let mut rng = Rng::new(833);
+        let sheds = Arc::new(AtomicUsize::new(0));
+        for _ in 0..500 {
+            let puzzle = random_puzzle(&mut rng);
+            let memory = MemoryEstimate::of(puzzle.width(), &puzzle.rows, &puzzle.cols);
+            // Room for about half the candidates, so that search sheds
+            // them under guesses, and backtracks past that.
+            let config = SolverConfig {
+                max_memory_bytes: Some(memory.fixed + memory.candidates),
+                ..SolverConfig::default()
+            };
+            let mut grid = puzzle.grid();
+            let mut solver = Solver::for_grid(&puzzle, &mut grid, config);
+            let count = Arc::clone(&sheds);
+            solver.set_logger(move |event| {
+                if let SolverEvent::Shed = event {
+                    count.fetch_add(1, Ordering::Relaxed);
+                }
+            });
+            let outcome = solver.solve();
+            match Solver::solve_puzzle(&puzzle, SolverConfig::default()) {
+                Ok(expected) => {
+                    assert_eq!(outcome, SolveOutcome::Solved, "{:?}", puzzle);
+                    assert_eq!(grid, expected, "{:?}", puzzle);
+                }
+                Err(SolveError::Contradiction) => {
+                    assert_eq!(outcome, SolveOutcome::Contradiction, "{:?}", puzzle)
+                }
+                Err(e) => panic!("{:?}: {}", puzzle, e),
+            }
+        }
+        assert!(sheds.load(Ordering::Relaxed) > 10);
     }
+}
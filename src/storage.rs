@@ -0,0 +1,396 @@
+//! Where the solver keeps the cells of a grid.
+//!
+//! The [crate::Solver] reads and writes cells through [GridStorage],
+//! so the grid of a huge puzzle can be kept in less memory than a
+//! `Vec<CellState>`, a byte per cell, takes: [PackedCells] take two
+//! bits per cell, and [FileCells] keep them on disk, out of core.
+//! See [crate::Solver::for_storage].
+//!
+//! Only the grid is kept there.  The rest of the state of the solver,
+//! the candidates of its lines foremost, stays in memory, and is
+//! usually bigger: see [crate::MemoryEstimate].
+
+use crate::CellState;
+
+use alloc::{vec, vec::Vec};
+
+/// The cells of a grid, row by row, by index.
+pub trait GridStorage {
+    /// The number of cells.
+    fn len(&self) -> usize;
+
+    fn get(&self, index: usize) -> CellState;
+
+    fn set(&mut self, index: usize, value: CellState);
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Set every cell to `value`.
+    fn fill(&mut self, value: CellState) {
+        for i in 0..self.len() {
+            self.set(i, value);
+        }
+    }
+
+    /// A copy of every cell.
+    fn to_vec(&self) -> Vec<CellState> {
+        (0..self.len()).map(|i| self.get(i)).collect()
+    }
+}
+
+impl GridStorage for Vec<CellState> {
+    fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    #[inline]
+    fn get(&self, index: usize) -> CellState {
+        self[index]
+    }
+
+    #[inline]
+    fn set(&mut self, index: usize, value: CellState) {
+        self[index] = value
+    }
+
+    fn fill(&mut self, value: CellState) {
+        self.as_mut_slice().fill(value)
+    }
+
+    fn to_vec(&self) -> Vec<CellState> {
+        self.clone()
+    }
+}
+
+// * Two bits per cell
+
+/// How many cells fit in a word of [PackedCells].
+const CELLS_PER_WORD: usize = 32;
+
+/// The two bits of `state`: zero is undecided, so that zeroed memory,
+/// or a new file, is an undecided grid.
+fn bits(state: CellState) -> u8 {
+    match state {
+        CellState::Undecided => 0,
+        CellState::Empty => 1,
+        CellState::Filled => 2,
+    }
+}
+
+fn state(bits: u8) -> CellState {
+    match bits & 3 {
+        0 => CellState::Undecided,
+        1 => CellState::Empty,
+        _ => CellState::Filled,
+    }
+}
+
+/// Cells packed two bits each, a quarter of the memory of a
+/// `Vec<CellState>`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PackedCells {
+    len: usize,
+    words: Vec<u64>,
+}
+
+impl PackedCells {
+    /// `len` undecided cells.
+    pub fn new(len: usize) -> PackedCells {
+        PackedCells {
+            len,
+            words: vec![0; len.div_ceil(CELLS_PER_WORD)],
+        }
+    }
+
+    pub fn from_cells(cells: &[CellState]) -> PackedCells {
+        let mut ret = PackedCells::new(cells.len());
+        for (i, cell) in cells.iter().enumerate() {
+            ret.set(i, *cell);
+        }
+        ret
+    }
+}
+
+impl GridStorage for PackedCells {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn get(&self, index: usize) -> CellState {
+        assert!(index < self.len);
+        let shift = 2 * (index % CELLS_PER_WORD);
+        state((self.words[index / CELLS_PER_WORD] >> shift) as u8)
+    }
+
+    fn set(&mut self, index: usize, value: CellState) {
+        assert!(index < self.len);
+        let shift = 2 * (index % CELLS_PER_WORD);
+        let word = &mut self.words[index / CELLS_PER_WORD];
+        *word = (*word & !(3 << shift)) | ((bits(value) as u64) << shift);
+    }
+
+    fn fill(&mut self, value: CellState) {
+        // Every two bits of the word set to those of `value`.
+        let word = bits(value) as u64 * 0x5555_5555_5555_5555;
+        self.words.fill(word);
+        // Keep the bits past the last cell clear, for Eq and Hash.
+        let used = self.len % CELLS_PER_WORD;
+        if let Some(last) = self.words.last_mut().filter(|_| used > 0) {
+            *last &= (1 << (2 * used)) - 1;
+        }
+    }
+}
+
+// * Out of core
+
+#[cfg(feature = "std")]
+pub use file::FileCells;
+
+#[cfg(feature = "std")]
+mod file {
+    use super::{bits, state, GridStorage};
+    use crate::CellState;
+
+    use std::cell::RefCell;
+    use std::fs::{File, OpenOptions};
+    use std::io::{self, Read, Seek, SeekFrom, Write};
+    use std::path::Path;
+
+    /// How many cells fit in a byte of the file.
+    const CELLS_PER_BYTE: usize = 4;
+
+    /// The size of the pages read from and written to the file, in
+    /// bytes.
+    const PAGE: usize = 4096;
+
+    /// How many pages [FileCells::create] keeps in memory by default:
+    /// 1 MiB, or four million cells.
+    const DEFAULT_PAGES: usize = 256;
+
+    /// Cells packed two bits each in a file, of which only some pages
+    /// are in memory at a time, to solve puzzles whose grid doesn't
+    /// fit in memory.
+    ///
+    /// The file is read and written through this cache, rather than
+    /// memory-mapped, which the standard library can't do.  Changes
+    /// reach the file when their page leaves the cache, on
+    /// [FileCells::flush], or when this is dropped.
+    ///
+    /// # Panics
+    ///
+    /// [GridStorage] can't fail, so reading and writing cells panics
+    /// if the file can't be read or written.
+    #[derive(Debug)]
+    pub struct FileCells {
+        len: usize,
+        cache: RefCell<Cache>,
+    }
+
+    #[derive(Debug)]
+    struct Cache {
+        file: File,
+        /// The size of the file.
+        size: usize,
+        pages: Vec<Page>,
+        capacity: usize,
+        /// Counts accesses, to tell the least recently used page.
+        clock: u64,
+    }
+
+    #[derive(Debug)]
+    struct Page {
+        /// The offset of the page in the file, in pages.
+        number: usize,
+        bytes: Vec<u8>,
+        dirty: bool,
+        used: u64,
+    }
+
+    impl FileCells {
+        /// `len` undecided cells in a new file at `path`, which is
+        /// truncated if it exists.
+        pub fn create(path: &Path, len: usize) -> io::Result<FileCells> {
+            FileCells::with_cache(path, len, DEFAULT_PAGES)
+        }
+
+        /// Like [FileCells::create], keeping up to `pages` pages of
+        /// 4 KiB in memory.
+        pub fn with_cache(path: &Path, len: usize, pages: usize) -> io::Result<FileCells> {
+            let file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(path)?;
+            let size = len.div_ceil(CELLS_PER_BYTE);
+            file.set_len(size as u64)?;
+            let cache = Cache {
+                file,
+                size,
+                pages: vec![],
+                capacity: pages.max(1),
+                clock: 0,
+            };
+            Ok(FileCells {
+                len,
+                cache: RefCell::new(cache),
+            })
+        }
+
+        /// Write the changed pages to the file.
+        pub fn flush(&self) -> io::Result<()> {
+            let cache = &mut *self.cache.borrow_mut();
+            for page in cache.pages.iter_mut().filter(|p| p.dirty) {
+                write_page(&mut cache.file, page)?;
+            }
+            cache.file.flush()
+        }
+    }
+
+    impl Drop for FileCells {
+        fn drop(&mut self) {
+            // Nowhere to report errors: call flush first to see them.
+            let _ = self.flush();
+        }
+    }
+
+    impl GridStorage for FileCells {
+        fn len(&self) -> usize {
+            self.len
+        }
+
+        fn get(&self, index: usize) -> CellState {
+            assert!(index < self.len);
+            let byte = index / CELLS_PER_BYTE;
+            let mut cache = self.cache.borrow_mut();
+            let page = cache.page(byte / PAGE);
+            state(page.bytes[byte % PAGE] >> (2 * (index % CELLS_PER_BYTE)))
+        }
+
+        fn set(&mut self, index: usize, value: CellState) {
+            assert!(index < self.len);
+            let byte = index / CELLS_PER_BYTE;
+            let shift = 2 * (index % CELLS_PER_BYTE);
+            let page = self.cache.get_mut().page(byte / PAGE);
+            let old = &mut page.bytes[byte % PAGE];
+            *old = (*old & !(3 << shift)) | (bits(value) << shift);
+            page.dirty = true;
+        }
+    }
+
+    impl Cache {
+        /// Page `number`, read from the file if it isn't in memory.
+        fn page(&mut self, number: usize) -> &mut Page {
+            self.clock += 1;
+            let i = match self.pages.iter().position(|p| p.number == number) {
+                Some(i) => i,
+                None => self
+                    .load(number)
+                    .unwrap_or_else(|e| panic!("Cell file: {}", e)),
+            };
+            self.pages[i].used = self.clock;
+            &mut self.pages[i]
+        }
+
+        /// Read page `number` into the cache, in place of the least
+        /// recently used one if it's full, and return its index.
+        fn load(&mut self, number: usize) -> io::Result<usize> {
+            let start = number * PAGE;
+            let mut bytes = vec![0; PAGE.min(self.size - start)];
+            self.file.seek(SeekFrom::Start(start as u64))?;
+            self.file.read_exact(&mut bytes)?;
+            let page = Page {
+                number,
+                bytes,
+                dirty: false,
+                used: self.clock,
+            };
+            if self.pages.len() < self.capacity {
+                self.pages.push(page);
+                return Ok(self.pages.len() - 1);
+            }
+            let (i, _) = (self.pages.iter().enumerate())
+                .min_by_key(|(_, p)| p.used)
+                .unwrap();
+            if self.pages[i].dirty {
+                write_page(&mut self.file, &mut self.pages[i])?;
+            }
+            self.pages[i] = page;
+            Ok(i)
+        }
+    }
+
+    fn write_page(file: &mut File, page: &mut Page) -> io::Result<()> {
+        file.seek(SeekFrom::Start((page.number * PAGE) as u64))?;
+        file.write_all(&page.bytes)?;
+        page.dirty = false;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    const STATES: [CellState; 3] = [CellState::Undecided, CellState::Empty, CellState::Filled];
+
+    /// Set random cells of `storage` and of a `Vec` alike, and check
+    /// they still hold the same cells.
+    fn same_as_vec(storage: &mut impl GridStorage, rng: &mut Rng, writes: usize) {
+        let mut cells = vec![CellState::Undecided; storage.len()];
+        assert_eq!(storage.to_vec(), cells);
+        for _ in 0..writes {
+            let (i, value) = (rng.below(cells.len()), STATES[rng.below(3)]);
+            storage.set(i, value);
+            cells[i] = value;
+            let j = rng.below(cells.len());
+            assert_eq!(storage.get(j), cells[j], "cell {}", j);
+        }
+        assert_eq!(storage.to_vec(), cells);
+    }
+
+    #[test]
+    fn packed_cells_hold_what_a_vec_holds() {
+        let mut rng = Rng::new(901);
+        for len in [1, 31, 32, 33, 1000] {
+            let mut packed = PackedCells::new(len);
+            same_as_vec(&mut packed, &mut rng, 4 * len);
+            assert_eq!(PackedCells::from_cells(&packed.to_vec()), packed);
+            for value in STATES {
+                packed.fill(value);
+                assert_eq!(packed.to_vec(), vec![value; len]);
+                assert_eq!(packed, PackedCells::from_cells(&vec![value; len]));
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn file_cells_keep_cells_across_pages() {
+        let path = std::env::temp_dir().join(format!("nonograms-901-{}", std::process::id()));
+        // Five pages of 4 KiB and a partial one, two of them in memory.
+        let len = 4 * (5 * 4096 + 100);
+        let mut rng = Rng::new(901);
+        let mut file = FileCells::with_cache(&path, len, 2).unwrap();
+        same_as_vec(&mut file, &mut rng, 20_000);
+
+        // Cells on either side of the page boundaries, written in an
+        // order that evicts the page of the previous one.
+        let mut cells = file.to_vec();
+        let edges = (1..6).flat_map(|page| [4 * 4096 * page - 1, 4 * 4096 * page]);
+        for (n, i) in edges.chain([0, len - 1]).enumerate() {
+            file.set(i, STATES[n % 3]);
+            cells[i] = STATES[n % 3];
+        }
+        file.flush().unwrap();
+        assert_eq!(file.to_vec(), cells);
+        for i in (0..len).rev().step_by(997) {
+            assert_eq!(file.get(i), cells[i], "cell {}", i);
+        }
+        drop(file);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
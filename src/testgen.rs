@@ -0,0 +1,156 @@
+//! Random puzzles for property tests, with feature `testgen`: each
+//! comes from a random grid, which is its goal, so a test can check
+//! that the solver finds it.
+//!
+//! The puzzles only depend on a seed, so that a failing case can be
+//! replayed.  To use them with proptest or quickcheck, generate the
+//! seed there and map it through [puzzle]:
+//!
+//! <pre>
+//! let config = TestGenConfig { unique: true, ..TestGenConfig::default() };
+//! for puzzle in TestGen::with_config(1, config).take(100) {
+//!     let goal = puzzle.goal.clone().unwrap();
+//!     assert_eq!(puzzle.solve().unwrap(), goal);
+//! }
+//! </pre>
+
+use crate::analysis::{self, Uniqueness};
+use crate::puzzle::Grid;
+use crate::rng::Rng;
+use crate::{CellState, Nonogram, Point, Puzzle};
+
+use std::ops::RangeInclusive;
+
+/// What puzzles to generate.
+#[derive(Clone, Debug)]
+pub struct TestGenConfig {
+    /// The possible widths, which must not be empty.
+    pub width: RangeInclusive<usize>,
+    /// The possible heights, which must not be empty.
+    pub height: RangeInclusive<usize>,
+    /// The probability that a cell of the goal is filled, and so
+    /// roughly the fraction of filled cells.
+    pub density: f64,
+    /// How the goal mirrors itself.
+    pub symmetry: Symmetry,
+    /// Whether to only generate puzzles with a unique solution, by
+    /// retrying until one comes out.
+    pub unique: bool,
+}
+
+impl Default for TestGenConfig {
+    fn default() -> TestGenConfig {
+        TestGenConfig {
+            width: 1..=10,
+            height: 1..=10,
+            density: 0.5,
+            symmetry: Symmetry::None,
+            unique: false,
+        }
+    }
+}
+
+/// A symmetry of the goals, since symmetric pictures look better, and
+/// are the norm in published collections.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Symmetry {
+    #[default]
+    None,
+    /// The right half mirrors the left half.
+    Horizontal,
+    /// The bottom half mirrors the top half.
+    Vertical,
+    /// The grid looks the same after a half turn.
+    Rotational,
+}
+
+impl Symmetry {
+    /// The cell that mirrors `p` in a grid of `width` by `height`
+    /// cells.
+    fn mirror(self, p: Point, width: usize, height: usize) -> Point {
+        match self {
+            Symmetry::None => p,
+            Symmetry::Horizontal => Point::new(width - 1 - p.x, p.y),
+            Symmetry::Vertical => Point::new(p.x, height - 1 - p.y),
+            Symmetry::Rotational => Point::new(width - 1 - p.x, height - 1 - p.y),
+        }
+    }
+}
+
+/// An endless stream of random puzzles, whose goals are set.
+pub struct TestGen {
+    rng: Rng,
+    config: TestGenConfig,
+}
+
+impl TestGen {
+    /// Puzzles from `seed`, with the default configuration.
+    pub fn new(seed: u64) -> TestGen {
+        TestGen::with_config(seed, TestGenConfig::default())
+    }
+
+    pub fn with_config(seed: u64, config: TestGenConfig) -> TestGen {
+        TestGen {
+            rng: Rng::new(seed),
+            config,
+        }
+    }
+
+    /// The next puzzle.
+    pub fn puzzle(&mut self) -> Puzzle {
+        loop {
+            let ret = Puzzle::from_solution(&self.grid());
+            if !self.config.unique
+                || matches!(
+                    analysis::uniqueness(&Nonogram::from(ret.clone())),
+                    Uniqueness::Unique(_)
+                )
+            {
+                return ret;
+            }
+        }
+    }
+
+    /// A random complete grid.  Cells mirroring one already drawn
+    /// copy it.
+    fn grid(&mut self) -> Grid {
+        let width = self.pick(self.config.width.clone());
+        let height = self.pick(self.config.height.clone());
+        let mut cells = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let mirror = (self.config.symmetry)
+                    .mirror(Point::new(x, y), width, height)
+                    .index(width);
+                let cell = if mirror < cells.len() {
+                    cells[mirror]
+                } else if self.rng.chance(self.config.density) {
+                    CellState::Filled
+                } else {
+                    CellState::Empty
+                };
+                cells.push(cell);
+            }
+        }
+        Grid::from_cells(width, height, cells)
+    }
+
+    fn pick(&mut self, range: RangeInclusive<usize>) -> usize {
+        assert!(!range.is_empty(), "empty range of sizes");
+        range.start() + self.rng.below(range.end() - range.start() + 1)
+    }
+}
+
+impl Iterator for TestGen {
+    type Item = Puzzle;
+
+    fn next(&mut self) -> Option<Puzzle> {
+        Some(self.puzzle())
+    }
+}
+
+/// The puzzle for `seed` under `config`: the first of
+/// [TestGen::with_config].
+pub fn puzzle(seed: u64, config: &TestGenConfig) -> Puzzle {
+    TestGen::with_config(seed, config.clone()).puzzle()
+}
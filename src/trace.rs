@@ -0,0 +1,211 @@
+//! Recording of every deduction made during a solve.
+//!
+//! Enable it with [crate::SolverConfig::trace], then get the result
+//! from [crate::Solver::trace].  A [SolveTrace] can be written out as
+//! text, read back, replayed onto a grid, and compared with another
+//! trace, eg to see where two versions of the solver diverge.
+//!
+//! The text format is one deduction per line:
+//!
+//! <pre>
+//! pass x y value source
+//! </pre>
+//!
+//! where value is `0` (empty) or `1` (filled), and source is `row N`,
+//! `column N`, `edge row N`, `edge column N`, `probe` or `guess`.  Lines count from 0 here, like
+//! coordinates, unlike the display of [crate::LineRef].
+
+use crate::{CellState, LineRef, Nonogram, Point};
+
+use alloc::vec::Vec;
+use core::error;
+use core::fmt;
+use core::str::FromStr;
+
+/// What caused a cell to be decided.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Source {
+    /// The consensus of the candidates of a row or a column.
+    Line(LineRef),
+    /// The first or last run of a row or a column, from the edge of
+    /// the line, see [crate::line::edge_logic].
+    Edge(LineRef),
+    /// A probe of the other value led to a contradiction.
+    Probe,
+    /// The search guessed this value.
+    Guess,
+}
+
+/// A single cell being decided.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Deduction {
+    pub cell: Point,
+    pub value: CellState,
+    pub source: Source,
+    /// The propagation pass during which this was decided, counting
+    /// from 1.
+    pub pass: usize,
+}
+
+/// Every deduction of a solve, in order.  Deductions made in probes
+/// or in search branches that were abandoned are not included.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SolveTrace {
+    pub deductions: Vec<Deduction>,
+}
+
+/// A cell on which two traces disagree.  `ours` and `theirs` are the
+/// deductions of that cell in each trace, if any.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TraceDifference<'t> {
+    pub cell: Point,
+    pub ours: Option<&'t Deduction>,
+    pub theirs: Option<&'t Deduction>,
+}
+
+impl SolveTrace {
+    pub fn new() -> SolveTrace {
+        SolveTrace::default()
+    }
+
+    pub fn push(&mut self, deduction: Deduction) {
+        self.deductions.push(deduction)
+    }
+
+    pub fn len(&self) -> usize {
+        self.deductions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.deductions.is_empty()
+    }
+
+    /// Drop the deductions after the first `len`.
+    pub fn truncate(&mut self, len: usize) {
+        self.deductions.truncate(len)
+    }
+
+    /// Clear the grid of `nono`, and apply every deduction of this
+    /// trace onto it, in order.
+    pub fn replay(&self, nono: &mut Nonogram) {
+        nono.clear_solution();
+        for d in self.deductions.iter() {
+            nono[d.cell] = d.value;
+        }
+    }
+
+    /// Find the cells that the two traces decide differently: with
+    /// another value, from another source, or in another pass.
+    /// Differences are sorted by position, in row-major order.
+    pub fn diff<'t>(&'t self, other: &'t SolveTrace) -> Vec<TraceDifference<'t>> {
+        let mut cells: Vec<Point> = self
+            .deductions
+            .iter()
+            .chain(other.deductions.iter())
+            .map(|d| d.cell)
+            .collect();
+        cells.sort_unstable();
+        cells.dedup();
+
+        cells
+            .into_iter()
+            .map(|cell| TraceDifference {
+                cell,
+                ours: self.find(cell),
+                theirs: other.find(cell),
+            })
+            .filter(|d| d.ours != d.theirs)
+            .collect()
+    }
+
+    fn find(&self, cell: Point) -> Option<&Deduction> {
+        self.deductions.iter().find(|d| d.cell == cell)
+    }
+}
+
+impl fmt::Display for Deduction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} ",
+            self.pass,
+            self.cell.x,
+            self.cell.y,
+            self.value.as_char()
+        )?;
+        match self.source {
+            Source::Line(LineRef::Row(y)) => write!(f, "row {}", y),
+            Source::Line(LineRef::Column(x)) => write!(f, "column {}", x),
+            Source::Edge(LineRef::Row(y)) => write!(f, "edge row {}", y),
+            Source::Edge(LineRef::Column(x)) => write!(f, "edge column {}", x),
+            Source::Probe => f.write_str("probe"),
+            Source::Guess => f.write_str("guess"),
+        }
+    }
+}
+
+impl fmt::Display for SolveTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for d in self.deductions.iter() {
+            writeln!(f, "{}", d)?;
+        }
+        Ok(())
+    }
+}
+
+/// A trace line couldn't be read.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceParseError {
+    pub line: usize,
+}
+
+impl fmt::Display for TraceParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Malformed trace at line {}.", self.line)
+    }
+}
+
+impl error::Error for TraceParseError {}
+
+/// Read a single line of a trace.
+fn parse_deduction(s: &str) -> Option<Deduction> {
+    let fields: Vec<&str> = s.split_whitespace().collect();
+    let number = |i: usize| fields.get(i)?.parse::<usize>().ok();
+    let value = match fields.get(3) {
+        Some(&"1") => CellState::Filled,
+        Some(&"0") => CellState::Empty,
+        _ => return None,
+    };
+    let source = match fields.get(4) {
+        Some(&"row") => Source::Line(LineRef::Row(number(5)?)),
+        Some(&"column") => Source::Line(LineRef::Column(number(5)?)),
+        Some(&"edge") => match fields.get(5) {
+            Some(&"row") => Source::Edge(LineRef::Row(number(6)?)),
+            Some(&"column") => Source::Edge(LineRef::Column(number(6)?)),
+            _ => return None,
+        },
+        Some(&"probe") => Source::Probe,
+        Some(&"guess") => Source::Guess,
+        _ => return None,
+    };
+    Some(Deduction {
+        pass: number(0)?,
+        cell: Point::new(number(1)?, number(2)?),
+        value,
+        source,
+    })
+}
+
+impl FromStr for SolveTrace {
+    type Err = TraceParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let deductions = s
+            .lines()
+            .enumerate()
+            .filter(|(_, l)| !l.trim().is_empty())
+            .map(|(i, l)| parse_deduction(l).ok_or(TraceParseError { line: i + 1 }))
+            .collect::<Result<_, _>>()?;
+        Ok(SolveTrace { deductions })
+    }
+}
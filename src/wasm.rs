@@ -0,0 +1,198 @@
+//! Bindings for WebAssembly, so that a page can solve and draw
+//! puzzles in the browser, without a server.
+//!
+//! This has no dependencies, so rather than through `wasm-bindgen`,
+//! these are plain exported functions that pass strings through the
+//! memory of the module.  Build the module with:
+//!
+//! <pre>
+//! cargo rustc --release --lib --crate-type cdylib \
+//!     --target wasm32-unknown-unknown --features wasm
+//! </pre>
+//!
+//! A string argument is a pointer and a length in bytes, in memory
+//! from [alloc_bytes], which the caller fills with UTF-8 and frees
+//! with [free_bytes].  A result is a pointer to its length in bytes,
+//! as a little-endian `u32`, followed by that many bytes of JSON;
+//! free it with [free_result].  Results are objects, `{"error": ...}`
+//! if something went wrong.
+//!
+//! Grids are strings of cells row by row, as in
+//! [crate::formats::savefile]: `1` for filled, `0` for empty and `?`
+//! for undecided.  An empty string is an undecided grid.
+
+use crate::assist::next_hint;
+use crate::formats::json::{quote, Json};
+use crate::formats::{self, PuzzleFormat};
+use crate::{CellState, Grid, Nonogram, Puzzle};
+
+use std::fmt::Write;
+
+// * Memory
+
+/// Allocate `len` bytes, for a string argument.
+#[no_mangle]
+pub extern "C" fn alloc_bytes(len: usize) -> *mut u8 {
+    let mut buf = Vec::<u8>::with_capacity(len);
+    let ptr = buf.as_mut_ptr();
+    std::mem::forget(buf);
+    ptr
+}
+
+/// Free memory from [alloc_bytes].
+///
+/// # Safety
+///
+/// `ptr` must come from [alloc_bytes] with the same `len`, and not be
+/// freed already.
+#[no_mangle]
+pub unsafe extern "C" fn free_bytes(ptr: *mut u8, len: usize) {
+    drop(Vec::from_raw_parts(ptr, 0, len));
+}
+
+/// Free a result.
+///
+/// # Safety
+///
+/// `ptr` must come from a function of this module, and not be freed
+/// already.
+#[no_mangle]
+pub unsafe extern "C" fn free_result(ptr: *mut u8) {
+    let len = u32::from_le_bytes(*(ptr as *const [u8; 4])) as usize;
+    drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+        ptr,
+        4 + len,
+    )));
+}
+
+/// The string argument at `ptr`.
+unsafe fn argument(ptr: *const u8, len: usize) -> Result<String, String> {
+    let bytes = std::slice::from_raw_parts(ptr, len);
+    String::from_utf8(bytes.to_vec()).map_err(|_| String::from("Input is not UTF-8."))
+}
+
+/// `result` as a result in memory.
+fn result(result: Result<String, String>) -> *mut u8 {
+    let json = result.unwrap_or_else(|e| format!("{{\"error\": {}}}", quote(&e)));
+    let mut bytes = (json.len() as u32).to_le_bytes().to_vec();
+    bytes.extend_from_slice(json.as_bytes());
+    Box::into_raw(bytes.into_boxed_slice()) as *mut u8
+}
+
+// * Functions
+
+/// Read a puzzle in any format of [crate::formats], and return it in
+/// the format of [crate::formats::json].
+///
+/// # Safety
+///
+/// See the module: `ptr` and `len` must be a string argument.
+#[no_mangle]
+pub unsafe extern "C" fn parse(ptr: *const u8, len: usize) -> *mut u8 {
+    result(
+        argument(ptr, len)
+            .and_then(|text| read_puzzle(&text))
+            .map(|p| Json.write(&p)),
+    )
+}
+
+/// Deduce the cells of a single line, from the grid at `grid_ptr` of
+/// the puzzle at `puzzle_ptr`, see [crate::assist::next_hint].  This
+/// returns the new grid, the cells it decided as `[x, y, value]` with
+/// a value of 0 or 1, and how this went: `progress`, `solved` if
+/// there was nothing left to decide, `stalled` if line logic can't
+/// decide anything more, or `contradiction`.  Calling this until it
+/// doesn't make progress solves the puzzle by line logic, one step at
+/// a time.
+///
+/// # Safety
+///
+/// See the module: both pairs of arguments must be string arguments.
+#[no_mangle]
+pub unsafe extern "C" fn solve_step(
+    puzzle_ptr: *const u8,
+    puzzle_len: usize,
+    grid_ptr: *const u8,
+    grid_len: usize,
+) -> *mut u8 {
+    result((|| {
+        let mut nono = read_nonogram(
+            &argument(puzzle_ptr, puzzle_len)?,
+            &argument(grid_ptr, grid_len)?,
+        )?;
+        let (outcome, cells) = match next_hint(&nono) {
+            Err(_) => ("contradiction", vec![]),
+            Ok(Some(hint)) => ("progress", hint.cells),
+            Ok(None) if nono.cells.contains(&CellState::Undecided) => ("stalled", vec![]),
+            Ok(None) => ("solved", vec![]),
+        };
+        let mut changed = vec![];
+        for (cell, value) in cells {
+            nono[cell] = value;
+            changed.push(format!(
+                "[{}, {}, {}]",
+                cell.x,
+                cell.y,
+                (value == CellState::Filled) as u8
+            ));
+        }
+        let mut ret = String::new();
+        write!(
+            ret,
+            "{{\"grid\": {}, \"cells\": [{}], \"outcome\": \"{}\"}}",
+            quote(&write_grid(&nono)),
+            changed.join(", "),
+            outcome
+        )
+        .unwrap();
+        Ok(ret)
+    })())
+}
+
+/// Draw the puzzle at `puzzle_ptr` with the grid at `grid_ptr` as
+/// SVG, see [crate::render::svg], and return it as `{"svg": ...}`.
+///
+/// # Safety
+///
+/// See the module: both pairs of arguments must be string arguments.
+#[no_mangle]
+pub unsafe extern "C" fn render_svg(
+    puzzle_ptr: *const u8,
+    puzzle_len: usize,
+    grid_ptr: *const u8,
+    grid_len: usize,
+) -> *mut u8 {
+    result((|| {
+        let nono = read_nonogram(
+            &argument(puzzle_ptr, puzzle_len)?,
+            &argument(grid_ptr, grid_len)?,
+        )?;
+        Ok(format!("{{\"svg\": {}}}", quote(&nono.to_svg())))
+    })())
+}
+
+// * Conversions
+
+fn read_puzzle(text: &str) -> Result<Puzzle, String> {
+    formats::parse(text, None).map_err(|e| e.to_string())
+}
+
+fn read_nonogram(puzzle: &str, grid: &str) -> Result<Nonogram, String> {
+    let puzzle = read_puzzle(puzzle)?;
+    let (w, h) = (puzzle.width(), puzzle.height());
+    if grid.is_empty() {
+        return Ok(Nonogram::from(puzzle));
+    }
+    let cells = grid
+        .chars()
+        .map(|c| CellState::from_char(c).ok_or_else(|| format!("Not a cell: {:?}", c)))
+        .collect::<Result<Vec<CellState>, String>>()?;
+    if cells.len() != w * h {
+        return Err(format!("{} cells for a {}x{} grid", cells.len(), w, h));
+    }
+    Ok(Nonogram::from_parts(puzzle, Grid::from_cells(w, h, cells)))
+}
+
+fn write_grid(nono: &Nonogram) -> String {
+    nono.cells.iter().map(CellState::as_char).collect()
+}
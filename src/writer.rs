@@ -0,0 +1,120 @@
+//! Clues as text, the same way everywhere.
+//!
+//! Every text format and text rendering writes clues through here, in
+//! a [ClueStyle]: the `3,1` of [crate::formats::non] and of
+//! [Constraint]'s `Display`, the `3.1` of [crate::formats::tatham],
+//! the `3, 1` of JSON, or the right-aligned `3 1` of printed puzzles.
+//! Lists of clues can be lined up, see [Align].
+
+use crate::Constraint;
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+/// How to write clues.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClueStyle<'s> {
+    /// What goes between the numbers of a clue.
+    pub separator: &'s str,
+    /// The clue of a line with no runs, or nothing if empty.
+    pub empty: &'s str,
+    /// An unknown clue, or an unknown length.
+    pub unknown: &'s str,
+    /// How the clues of a list line up, see [lines].
+    pub align: Align,
+}
+
+/// How [lines] lines up clues.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Align {
+    /// Not at all.
+    #[default]
+    None,
+    /// Pad clues on the left to the longest, so that they end on the
+    /// same column, as the row clues of a printed puzzle.
+    Right,
+    /// Also pad each number to the widest of the list, so that the
+    /// numbers line up in columns, counting from the end.
+    Numbers,
+}
+
+impl ClueStyle<'static> {
+    /// `3,1`, with `0` for an empty line and `?` for unknown clues and
+    /// lengths, as clues display, and as [Constraint::from_str]
+    /// reads them.
+    ///
+    /// [Constraint::from_str]: core::str::FromStr::from_str
+    pub const fn compact() -> ClueStyle<'static> {
+        ClueStyle {
+            separator: ",",
+            empty: "0",
+            unknown: "?",
+            align: Align::None,
+        }
+    }
+
+    /// `3 1`, right-aligned, as the row clues of a printed puzzle.
+    pub const fn printed() -> ClueStyle<'static> {
+        ClueStyle {
+            separator: " ",
+            align: Align::Right,
+            ..ClueStyle::compact()
+        }
+    }
+}
+
+impl Default for ClueStyle<'static> {
+    fn default() -> Self {
+        ClueStyle::compact()
+    }
+}
+
+/// The numbers of `clue` as `style` writes them, without separators:
+/// a single [ClueStyle::unknown] for an unknown clue, and a single
+/// [ClueStyle::empty], if any, for an empty line.
+pub fn numbers(clue: &Constraint, style: &ClueStyle) -> Vec<String> {
+    if clue.is_unknown() {
+        return alloc::vec![style.unknown.to_string()];
+    } else if clue.runs().is_empty() {
+        return (!style.empty.is_empty())
+            .then(|| style.empty.to_string())
+            .into_iter()
+            .collect();
+    }
+    (clue.lengths())
+        .map(|n| n.map_or(style.unknown.to_string(), |n| n.to_string()))
+        .collect()
+}
+
+/// `clue`, written as `style` says.  A single clue is never aligned.
+pub fn clue(clue: &Constraint, style: &ClueStyle) -> String {
+    numbers(clue, style).join(style.separator)
+}
+
+/// Every clue of `clues`, lined up as [ClueStyle::align] says.
+pub fn lines(clues: &[Constraint], style: &ClueStyle) -> Vec<String> {
+    let numbers: Vec<Vec<String>> = clues.iter().map(|c| numbers(c, style)).collect();
+    let width = match style.align {
+        Align::Numbers => (numbers.iter().flatten())
+            .map(|n| n.chars().count())
+            .max()
+            .unwrap_or(0),
+        _ => 0,
+    };
+    let lines: Vec<String> = (numbers.iter())
+        .map(|numbers| {
+            let padded: Vec<String> = (numbers.iter())
+                .map(|n| format!("{:>1$}", n, width))
+                .collect();
+            padded.join(style.separator)
+        })
+        .collect();
+    if style.align == Align::None {
+        return lines;
+    }
+    let longest = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    (lines.into_iter())
+        .map(|l| format!("{:>1$}", l, longest))
+        .collect()
+}